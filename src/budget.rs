@@ -0,0 +1,536 @@
+use super::currency::ExchangeRates;
+use super::error::StoreError;
+use super::models::{Category, CategoryId, Limit, Money, Transaction};
+use super::summary::{month_start, summary};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::fmt;
+
+/// History of the global monthly spending limit.
+/// A limit set without a month applies from the month it is set in until it is changed again,
+/// so changing it doesn't rewrite the limits of past months. Single months can have limits of their own.
+/// Months are given in the format YYYY-MM.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LimitHistory {
+    /// Limit of the months before the first change, e.g. the single limit kept by older versions.
+    #[serde(default)]
+    initial: Option<Limit>,
+    /// Limits by the month from which they apply. None removes the limit from that month on.
+    #[serde(default)]
+    changes: BTreeMap<String, Option<Limit>>,
+    /// Limits of single months, overriding the inherited limit. None means no limit in that month.
+    #[serde(default)]
+    months: BTreeMap<String, Option<Limit>>,
+}
+
+impl LimitHistory {
+    /// Returns the limit of the months before the first change.
+    pub fn initial(&self) -> Option<Limit> {
+        self.initial
+    }
+
+    /// Sets the limit of the months before the first change.
+    pub fn set_initial(&mut self, limit: Option<Limit>) {
+        self.initial = limit;
+    }
+
+    /// Returns the limits by the month from which they apply.
+    pub fn changes(&self) -> &BTreeMap<String, Option<Limit>> {
+        &self.changes
+    }
+
+    /// Returns the limits of single months.
+    pub fn months(&self) -> &BTreeMap<String, Option<Limit>> {
+        &self.months
+    }
+
+    /// Sets the limit of the given month and every following month, until the next change.
+    /// Later changes and limits of single months are kept.
+    pub fn set_from(&mut self, month: String, limit: Option<Limit>) {
+        self.changes.insert(month, limit);
+    }
+
+    /// Sets the limit of a single month.
+    pub fn set_month(&mut self, month: String, limit: Option<Limit>) {
+        self.months.insert(month, limit);
+    }
+
+    /// Returns the limit that applies to the given month, if any.
+    pub fn get(&self, month: &str) -> Option<Limit> {
+        in_force(self.initial, &self.changes, &self.months, month)
+    }
+}
+
+/// Returns the value in force in a month: the value of that single month if it has one,
+/// otherwise the latest change up to the month, or the initial value before the first change.
+fn in_force<T: Copy>(initial: T, changes: &BTreeMap<String, T>, months: &BTreeMap<String, T>, month: &str) -> T {
+    if let Some(value) = months.get(month) {
+        return *value;
+    }
+    changes
+        .range::<str, _>((Bound::Unbounded, Bound::Included(month)))
+        .next_back()
+        .map_or(initial, |(_, value)| *value)
+}
+
+/// Period a category budget applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    /// Calendar weeks, from Monday to Sunday.
+    Weekly,
+    /// Calendar months.
+    Monthly,
+}
+
+impl BudgetPeriod {
+    /// Returns the first and the last day of the period containing the given date.
+    pub fn range(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            BudgetPeriod::Weekly => {
+                let first = date - Days::new(date.weekday().num_days_from_monday().into());
+                (first, first + Days::new(6))
+            }
+            BudgetPeriod::Monthly => {
+                let first = date.with_day(1).unwrap_or(date);
+                let last = first.checked_add_months(Months::new(1)).and_then(|next| next.pred_opt()).unwrap_or(date);
+                (first, last)
+            }
+        }
+    }
+}
+
+impl fmt::Display for BudgetPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetPeriod::Weekly => write!(f, "week"),
+            BudgetPeriod::Monthly => write!(f, "month"),
+        }
+    }
+}
+
+/// What happens to the balance of a monthly budget at the end of the month, envelope-style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rollover {
+    /// Every month starts from scratch.
+    #[default]
+    None,
+    /// Unspent money carries into the next month; overspending is forgiven.
+    Surplus,
+    /// Overspending reduces the next month; unspent money is dropped.
+    Deficit,
+    /// Both unspent money and overspending carry into the next month.
+    Full,
+}
+
+impl Rollover {
+    /// Returns the part of a closing balance that carries into the next month.
+    pub fn carry(self, balance: Money) -> Money {
+        match self {
+            Rollover::None => Money::ZERO,
+            Rollover::Surplus => balance.max(Money::ZERO),
+            Rollover::Deficit => balance.min(Money::ZERO),
+            Rollover::Full => balance,
+        }
+    }
+}
+
+/// Spending limit of a single category, in the base currency.
+/// It applies alongside the global monthly limit.
+/// Like the global limit, a changed amount applies from the month it is changed in, so past months
+/// keep their allocation. Single months can have amounts of their own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    category: Category,
+    /// Amount of the months before the first change, e.g. the single amount kept by older versions.
+    amount: Limit,
+    /// Amounts by the month from which they apply.
+    #[serde(default)]
+    changes: BTreeMap<String, Limit>,
+    /// Amounts of single months, overriding the inherited amount.
+    #[serde(default)]
+    months: BTreeMap<String, Limit>,
+    period: BudgetPeriod,
+    #[serde(default)]
+    rollover: Rollover,
+    /// First month of the budget in the format YYYY-MM, from which balances carry over.
+    /// Budgets set before rollovers existed have none and start with the first month the category was used.
+    #[serde(default)]
+    since: Option<String>,
+}
+
+impl Budget {
+    pub fn new(category: Category, new: NewBudget) -> Self {
+        Budget {
+            category,
+            amount: new.amount,
+            changes: BTreeMap::new(),
+            months: BTreeMap::new(),
+            period: new.period,
+            rollover: new.rollover,
+            since: new.since,
+        }
+    }
+
+    pub fn category(&self) -> &Category {
+        &self.category
+    }
+
+    /// Returns the amount that applies to the given month, in the format YYYY-MM.
+    pub fn amount_for(&self, month: &str) -> Limit {
+        in_force(self.amount, &self.changes, &self.months, month)
+    }
+
+    /// Returns the amount of the months before the first change.
+    pub fn initial_amount(&self) -> Limit {
+        self.amount
+    }
+
+    /// Returns the amounts by the month from which they apply.
+    pub fn changes(&self) -> &BTreeMap<String, Limit> {
+        &self.changes
+    }
+
+    /// Returns the amounts of single months.
+    pub fn months(&self) -> &BTreeMap<String, Limit> {
+        &self.months
+    }
+
+    /// Changes the budget from the given month on, keeping the amounts of earlier months.
+    /// Later changes and amounts of single months are kept.
+    pub fn update(&mut self, month: String, new: NewBudget) {
+        self.set_from(month, new.amount);
+        self.rollover = new.rollover;
+        self.since = new.since;
+    }
+
+    /// Sets the amount of the given month and every following month, until the next change.
+    pub fn set_from(&mut self, month: String, amount: Limit) {
+        self.changes.insert(month, amount);
+    }
+
+    /// Sets the amount of a single month.
+    pub fn set_month(&mut self, month: String, amount: Limit) {
+        self.months.insert(month, amount);
+    }
+
+    pub fn period(&self) -> BudgetPeriod {
+        self.period
+    }
+
+    pub fn rollover(&self) -> Rollover {
+        self.rollover
+    }
+
+    pub fn since(&self) -> Option<&str> {
+        self.since.as_deref()
+    }
+}
+
+/// A budget to set for a category.
+#[derive(Debug, Clone)]
+pub struct NewBudget {
+    pub amount: Limit,
+    pub period: BudgetPeriod,
+    pub rollover: Rollover,
+    /// First month of the budget. Defaults to the first month of the budget it replaces, or the current month.
+    pub since: Option<String>,
+}
+
+impl NewBudget {
+    pub fn new(amount: Limit, period: BudgetPeriod) -> Self {
+        NewBudget { amount, period, rollover: Rollover::None, since: None }
+    }
+}
+
+/// Budget moved from one category to another for a single month.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetTransfer {
+    /// Month in the format YYYY-MM.
+    pub month: String,
+    pub from: Category,
+    pub to: Category,
+    pub amount: Money,
+}
+
+impl BudgetTransfer {
+    /// Returns the amount the transfer adds to the budget of a category: negative for the source, positive for the target.
+    fn net(&self, category_id: CategoryId) -> Money {
+        if self.from.id() == category_id {
+            -self.amount
+        } else if self.to.id() == category_id {
+            self.amount
+        } else {
+            Money::ZERO
+        }
+    }
+}
+
+/// Spending of a category against its budget in the current period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    /// First day of the period.
+    pub from: NaiveDate,
+    /// Last day of the period.
+    pub to: NaiveDate,
+    /// Amount that may be spent in the period, in the base currency. For monthly budgets this is the
+    /// envelope of the month: the balance carried over plus the budget, including transferred budget.
+    pub allowance: Money,
+    /// Total spent in the category during the period, in the base currency.
+    pub spent: Money,
+}
+
+impl BudgetStatus {
+    /// Returns the amount left in the budget. If the budget is exceeded, it returns a negative value.
+    pub fn remaining(&self) -> Money {
+        self.allowance - self.spent
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.remaining().is_negative()
+    }
+}
+
+/// Function to check each budget for the period containing the given date.
+/// Monthly budgets are checked against their envelope, as computed by `envelope_report`, so rollovers
+/// and budget transfers count; weekly budgets against the amount of the month the week starts in.
+/// Amounts are converted into the base currency using the rate valid on each transaction's date.
+/// It returns the status of every budget, in the given order.
+pub fn budget_status(transactions: Vec<&Transaction>, budgets: &[Budget], transfers: &[BudgetTransfer], date: NaiveDate, rates: &ExchangeRates) -> Result<Vec<BudgetStatus>, StoreError> {
+    budgets
+        .iter()
+        .map(|budget| {
+            let (from, to) = budget.period.range(date);
+            let month = from.format("%Y-%m").to_string();
+            let allowance = match budget.period {
+                BudgetPeriod::Weekly => budget.amount_for(&month),
+                BudgetPeriod::Monthly => {
+                    let envelope = envelope_report(transactions.clone(), std::slice::from_ref(budget), transfers, &month, rates)?.remove(0);
+                    envelope.opening + envelope.allocated
+                }
+            };
+            let spent = transactions
+                .iter()
+                .filter(|transaction| transaction.is_expense())
+                .filter(|transaction| transaction.category().is_some_and(|category| category.id() == budget.category.id()))
+                .filter(|transaction| (from..=to).contains(&transaction.datetime().date_naive()))
+                .map(|transaction| rates.to_base(transaction))
+                .sum::<Result<Money, _>>()?;
+            Ok(BudgetStatus { budget: budget.clone(), from, to, allowance, spent })
+        })
+        .collect()
+}
+
+/// Envelope of a monthly budget in a single month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub category: Category,
+    /// Month in the format YYYY-MM.
+    pub month: String,
+    /// Balance carried over from the previous month.
+    pub opening: Money,
+    /// Budget of the month, including the budget transferred in or out.
+    pub allocated: Money,
+    /// Total spent in the category during the month, in the base currency.
+    pub spent: Money,
+}
+
+impl Envelope {
+    /// Returns the balance at the end of the month. If the envelope is overspent, it returns a negative value.
+    pub fn closing(&self) -> Money {
+        self.opening + self.allocated - self.spent
+    }
+}
+
+/// Function to compute the envelope of every monthly budget in a month in the format YYYY-MM.
+/// Balances are rolled over month by month according to each budget's rule, starting with an empty envelope
+/// in the first month of the budget. Weekly budgets have no envelopes.
+/// It returns the envelopes in the order of the budgets.
+pub fn envelope_report(transactions: Vec<&Transaction>, budgets: &[Budget], transfers: &[BudgetTransfer], month: &str, rates: &ExchangeRates) -> Result<Vec<Envelope>, StoreError> {
+    let month = month_start(month)?;
+    let mut envelopes = vec![];
+    for budget in budgets.iter().filter(|budget| budget.period == BudgetPeriod::Monthly) {
+        let category = &budget.category;
+        let start = match &budget.since {
+            Some(since) => month_start(since)?,
+            None => transactions
+                .iter()
+                .filter(|transaction| transaction.category().is_some_and(|c| c.id() == category.id()))
+                .map(|transaction| transaction.datetime().date_naive())
+                .chain(transfers.iter().filter(|transfer| !transfer.net(category.id()).is_zero()).filter_map(|transfer| month_start(&transfer.month).ok()))
+                .min()
+                .map_or(month, |first| first.with_day(1).unwrap_or(first).min(month)),
+        };
+        let mut envelope = None;
+        let mut current = start;
+        while current <= month {
+            let name = current.format("%Y-%m").to_string();
+            let opening = envelope.as_ref().map_or(Money::ZERO, |previous: &Envelope| budget.rollover.carry(previous.closing()));
+            let transferred: Money = transfers.iter().filter(|transfer| transfer.month == name).map(|transfer| transfer.net(category.id())).sum();
+            let allocated = budget.amount_for(&name) + transferred;
+            let (spent, _) = summary(transactions.clone(), Some(name.clone()), Some(category), rates)?;
+            envelope = Some(Envelope { category: category.clone(), month: name, opening, allocated, spent });
+            current = current + Months::new(1);
+        }
+        // Budgets starting after the month have an empty envelope.
+        envelopes.push(envelope.unwrap_or_else(|| Envelope {
+            category: category.clone(),
+            month: month.format("%Y-%m").to_string(),
+            opening: Money::ZERO,
+            allocated: Money::ZERO,
+            spent: Money::ZERO,
+        }));
+    }
+    Ok(envelopes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use chrono::prelude::Utc;
+
+    fn money(value: &str) -> Money {
+        value.parse().unwrap()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_limit_history() {
+        let mut limits = LimitHistory::default();
+        limits.set_initial(Some(money("100")));
+        limits.set_from("2025-03".to_string(), Some(money("200")));
+        limits.set_from("2025-06".to_string(), None);
+        limits.set_month("2025-04".to_string(), Some(money("50")));
+        assert_eq!(limits.get("2025-02"), Some(money("100")));
+        assert_eq!(limits.get("2025-03"), Some(money("200")));
+        assert_eq!(limits.get("2025-04"), Some(money("50")));
+        assert_eq!(limits.get("2025-05"), Some(money("200")));
+        assert_eq!(limits.get("2025-07"), None);
+        assert_eq!(LimitHistory::default().get("2025-01"), None);
+    }
+
+    #[test]
+    fn test_envelope_report() {
+        let food = Category::new(1, "Food".to_string());
+        let fun = Category::new(2, "Fun".to_string());
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        let mut transactions = vec![];
+        for (id, amount, datetime, category) in [
+            (1, "80", "2025-01-10T12:00:00Z", &food),
+            (2, "130", "2025-02-10T12:00:00Z", &food),
+            (3, "70", "2025-03-10T12:00:00Z", &fun),
+        ] {
+            let mut transaction = Transaction::new(id, money(amount), Currency::default(), "Test transaction".to_string(), Some(category.clone()));
+            transaction.set_datetime(at(datetime));
+            transactions.push(transaction);
+        }
+        let budgets = [
+            Budget::new(food.clone(), NewBudget { rollover: Rollover::Full, since: Some("2025-01".to_string()), ..NewBudget::new(money("100"), BudgetPeriod::Monthly) }),
+            Budget::new(fun.clone(), NewBudget { rollover: Rollover::Surplus, ..NewBudget::new(money("50"), BudgetPeriod::Monthly) }),
+            Budget::new(Category::new(3, "Gym".to_string()), NewBudget::new(money("10"), BudgetPeriod::Weekly)),
+        ];
+        let transfers = [BudgetTransfer { month: "2025-03".to_string(), from: food, to: fun, amount: money("30") }];
+        let envelopes = envelope_report(transactions.iter().collect(), &budgets, &transfers, "2025-03", &ExchangeRates::default()).unwrap();
+        assert_eq!(envelopes.len(), 2);
+        // Food: 100 - 80 = 20 left in January, 20 + 100 - 130 = -10 in February, carried into March.
+        assert_eq!((envelopes[0].opening, envelopes[0].allocated, envelopes[0].spent), (-money("10"), money("70"), money("0")));
+        assert_eq!(envelopes[0].closing(), money("60"));
+        // Fun starts with its first transaction in March.
+        assert_eq!((envelopes[1].opening, envelopes[1].allocated, envelopes[1].spent), (money("0"), money("80"), money("70")));
+        assert_eq!(envelopes[1].closing(), money("10"));
+        assert_eq!(Rollover::Surplus.carry(-money("10")), money("0"));
+        assert_eq!(Rollover::Deficit.carry(money("10")), money("0"));
+    }
+
+    #[test]
+    fn test_changed_budget_keeps_past_months() {
+        let food = Category::new(1, "Food".to_string());
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        let mut transactions = vec![];
+        for (id, amount, datetime) in [(1, "80", "2025-01-10T12:00:00Z"), (2, "90", "2025-02-10T12:00:00Z"), (3, "120", "2025-03-10T12:00:00Z")] {
+            let mut transaction = Transaction::new(id, money(amount), Currency::default(), "Test transaction".to_string(), Some(food.clone()));
+            transaction.set_datetime(at(datetime));
+            transactions.push(transaction);
+        }
+        let new = NewBudget { rollover: Rollover::Full, since: Some("2025-01".to_string()), ..NewBudget::new(money("100"), BudgetPeriod::Monthly) };
+        let mut budget = Budget::new(food, new.clone());
+        let envelopes = |budget: &Budget, month: &str| envelope_report(transactions.iter().collect(), std::slice::from_ref(budget), &[], month, &ExchangeRates::default()).unwrap().remove(0);
+        let february = envelopes(&budget, "2025-02");
+        assert_eq!((february.allocated, february.closing()), (money("100"), money("30")));
+
+        budget.update("2025-03".to_string(), NewBudget { amount: money("150"), ..new });
+        budget.set_month("2025-04".to_string(), money("20"));
+        assert_eq!(envelopes(&budget, "2025-01").allocated, money("100"));
+        assert_eq!(envelopes(&budget, "2025-02"), february);
+        let march = envelopes(&budget, "2025-03");
+        assert_eq!((march.opening, march.allocated, march.closing()), (money("30"), money("150"), money("60")));
+        assert_eq!(envelopes(&budget, "2025-04").allocated, money("20"));
+        assert_eq!(envelopes(&budget, "2025-05").allocated, money("150"));
+        let status = budget_status(transactions.iter().collect(), &[budget], &[], date("2025-02-15"), &ExchangeRates::default()).unwrap();
+        assert_eq!((status[0].allowance, status[0].remaining()), (money("120"), money("30")));
+    }
+
+    #[test]
+    fn test_budget_periods() {
+        // 2025-04-02 is a Wednesday.
+        assert_eq!(BudgetPeriod::Weekly.range(date("2025-04-02")), (date("2025-03-31"), date("2025-04-06")));
+        assert_eq!(BudgetPeriod::Monthly.range(date("2024-02-10")), (date("2024-02-01"), date("2024-02-29")));
+    }
+
+    #[test]
+    fn test_budget_status() {
+        let food = Category::new(1, "Food".to_string());
+        let fun = Category::new(2, "Fun".to_string());
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        let mut lunch = Transaction::new(1, money("30"), Currency::default(), "Lunch".to_string(), Some(food.clone()));
+        lunch.set_datetime(at("2025-04-01T12:00:00Z"));
+        let mut dinner = Transaction::new(2, money("50"), Currency::default(), "Dinner".to_string(), Some(food.clone()));
+        dinner.set_datetime(at("2025-04-08T19:00:00Z"));
+        let mut cinema = Transaction::new(3, money("15"), Currency::default(), "Cinema".to_string(), Some(fun.clone()));
+        cinema.set_datetime(at("2025-04-08T21:00:00Z"));
+        let transactions = [lunch, dinner, cinema];
+        let budgets = [
+            Budget::new(food, NewBudget::new(money("60"), BudgetPeriod::Monthly)),
+            Budget::new(fun, NewBudget::new(money("20"), BudgetPeriod::Weekly)),
+        ];
+        let status = budget_status(transactions.iter().collect(), &budgets, &[], date("2025-04-09"), &ExchangeRates::default()).unwrap();
+        assert_eq!(status[0].spent, money("80"));
+        assert_eq!(status[0].remaining(), -money("20"));
+        assert!(status[0].is_exceeded());
+        assert_eq!((status[1].from, status[1].to), (date("2025-04-07"), date("2025-04-13")));
+        assert_eq!(status[1].remaining(), money("5"));
+        assert!(!status[1].is_exceeded());
+    }
+
+    #[test]
+    fn test_budget_status_with_transfers() {
+        let food = Category::new(1, "Food".to_string());
+        let fun = Category::new(2, "Fun".to_string());
+        let mut concert = Transaction::new(1, money("70"), Currency::default(), "Concert".to_string(), Some(fun.clone()));
+        concert.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-05T20:00:00Z").unwrap().with_timezone(&Utc));
+        let transactions = [concert];
+        let budgets = [
+            Budget::new(food.clone(), NewBudget::new(money("100"), BudgetPeriod::Monthly)),
+            Budget::new(fun.clone(), NewBudget::new(money("50"), BudgetPeriod::Monthly)),
+        ];
+        let status = budget_status(transactions.iter().collect(), &budgets, &[], date("2025-04-09"), &ExchangeRates::default()).unwrap();
+        assert!(status[1].is_exceeded());
+
+        // Moving budget into Fun clears the overspend, in the status as in the envelopes.
+        let transfers = [BudgetTransfer { month: "2025-04".to_string(), from: food, to: fun, amount: money("30") }];
+        let status = budget_status(transactions.iter().collect(), &budgets, &transfers, date("2025-04-09"), &ExchangeRates::default()).unwrap();
+        assert_eq!((status[0].allowance, status[0].remaining()), (money("70"), money("70")));
+        assert_eq!((status[1].allowance, status[1].remaining()), (money("80"), money("10")));
+        assert!(!status[1].is_exceeded());
+        let envelopes = envelope_report(transactions.iter().collect(), &budgets, &transfers, "2025-04", &ExchangeRates::default()).unwrap();
+        assert_eq!(envelopes[1].closing(), status[1].remaining());
+    }
+}
@@ -1,55 +1,484 @@
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rust_decimal::Decimal;
+use roadmap_sh_expense_tracker::budget::{BudgetPeriod, Rollover};
+use roadmap_sh_expense_tracker::currency::Currency;
+use roadmap_sh_expense_tracker::dates::{parse_date, parse_datetime};
+use roadmap_sh_expense_tracker::error::StoreError;
+use roadmap_sh_expense_tracker::import::csv::{Column, CsvProfile, SignConvention};
+use roadmap_sh_expense_tracker::import::ofx::OfxDescription;
+use roadmap_sh_expense_tracker::recurring::{DayOfMonth, Frequency};
+use std::path::Path;
+use roadmap_sh_expense_tracker::models::{parse_tag, Money, TransactionFilter, TransactionKind};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
-pub struct CLI {
+pub struct Cli {
+    /// Storage backend holding the data
+    #[arg(long, global = true, value_enum, env = "EXPENSE_TRACKER_STORAGE", default_value_t = Storage::Json)]
+    pub storage: Storage,
+    /// Path to the data file. Defaults to data/data.json for JSON and data/data.db for SQLite
+    #[arg(long, global = true, env = "EXPENSE_TRACKER_DATA")]
+    pub data: Option<String>,
     #[command(subcommand)]
     pub commands: Commands,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Storage {
+    /// Pretty-printed JSON file
+    Json,
+    /// Embedded SQLite database
+    Sqlite,
+}
+
+impl Storage {
+    /// Returns the default path of the data file for this backend.
+    pub fn default_path(&self) -> &'static str {
+        match self {
+            Storage::Json => "data/data.json",
+            Storage::Sqlite => "data/data.db",
+        }
+    }
+}
+
+/// Commands of the application.
+/// `migrate` works on data files of its own, so it runs without opening the store.
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    #[command(flatten)]
+    Store(Box<StoreCommands>),
+    /// Import an existing JSON data file into a SQLite database
+    Migrate {
+        /// Path to the JSON data file
+        #[arg(long, default_value = "data/data.json")]
+        from: String,
+        /// Path to the SQLite database, which must be empty
+        #[arg(long, default_value = "data/data.db")]
+        to: String,
+    },
+}
+
+/// Commands working on the data of the store.
 #[derive(Subcommand, Debug)]
-pub enum Commands { 
+pub enum StoreCommands {
     /// Add a new transaction
     Add {
-        /// Description of the transaction
+        /// Description of the transaction. Words like `#work` are taken off it as tags
         description: String,
         /// Amount of the transaction
-        amount: f64,
+        amount: Money,
         /// Category of the transaction
         category: Option<u32>,
+        /// Currency of the transaction, e.g. EUR. Defaults to the base currency
+        #[arg(long)]
+        currency: Option<Currency>,
+        /// Date of the transaction: YYYY-MM-DD, YYYY-MM-DD HH:MM (UTC), `yesterday`, or an offset
+        /// like `-3d`, `-2w` or `-1m`. Defaults to now
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_datetime_arg)]
+        date: Option<DateTime<Utc>>,
+        /// Whether the transaction is an expense, income, or a transfer between own accounts
+        #[arg(long, value_enum, default_value_t = KindArg::Expense)]
+        kind: KindArg,
+        /// ID of the account the transaction is paid from, or paid into for income
+        #[arg(long)]
+        account: Option<u32>,
+        /// Tag of the transaction, e.g. `trip-lisbon`. Can be repeated
+        #[arg(long = "tag", value_parser = parse_tag)]
+        tags: Vec<String>,
     },
     /// Delete a transaction
     Delete {
         /// ID of the transaction to delete
         id: u32,
     },
+    /// Edit an existing transaction, keeping its ID
+    Edit {
+        /// ID of the transaction to edit
+        id: u32,
+        /// New description of the transaction
+        #[arg(long)]
+        description: Option<String>,
+        /// New amount of the transaction
+        #[arg(long)]
+        amount: Option<Money>,
+        /// ID of the new category of the transaction
+        #[arg(long, conflicts_with = "no_category")]
+        category: Option<u32>,
+        /// Remove the category from the transaction
+        #[arg(long)]
+        no_category: bool,
+        /// New date of the transaction: YYYY-MM-DD, `yesterday`, or an offset like `-3d`.
+        /// The time of day is kept
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+        date: Option<NaiveDate>,
+        /// New kind of the transaction. A transaction that is no longer a transfer loses its destination account
+        #[arg(long, value_enum)]
+        kind: Option<KindArg>,
+        /// ID of the new account of the transaction
+        #[arg(long, conflicts_with = "no_account")]
+        account: Option<u32>,
+        /// Remove the account from the transaction
+        #[arg(long)]
+        no_account: bool,
+        /// ID of the new account a transfer goes to
+        #[arg(long)]
+        to_account: Option<u32>,
+        /// Tag to add to the transaction. Can be repeated
+        #[arg(long = "tag", value_parser = parse_tag)]
+        tags: Vec<String>,
+        /// Tag to remove from the transaction. Can be repeated
+        #[arg(long = "untag", value_parser = parse_tag)]
+        untags: Vec<String>,
+    },
     /// List all transactions
     List {
         /// Filter transactions by category
         category: Option<u32>,
+        #[command(flatten)]
+        filter: FilterArgs,
     },
-    /// Show a summary of transactions for a given month, or overall
+    /// Show a summary of the expenses for a given month, or overall
     Summary {
         /// Month in the format YYYY-MM, or "overall" for all transactions
         #[arg(default_value = "overall")]
         month: String,
         category: Option<u32>,
+        #[command(flatten)]
+        filter: FilterArgs,
     },
-    /// Limits spending for the current month
+    /// Show the income, expenses and net cash flow of each month
+    Cashflow {
+        /// Only transactions of this category
+        category: Option<u32>,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Limits monthly spending, or shows the limit and spending of each month if no amount is given
     Limit {
-        /// Amount to limit spending to. Set to 0 to remove the limit
-        amount: f64,
+        /// Amount to limit spending to, in the base currency. Set to 0 to remove the limit.
+        /// Applies from the current month on, past months keep their limit
+        amount: Option<Money>,
+        /// Only set the limit of this month, in the format YYYY-MM
+        #[arg(long, requires = "amount")]
+        month: Option<String>,
+    },
+    /// Manage spending budgets of categories
+    Budget {
+        #[command(subcommand)]
+        budget_subcommand: BudgetSubcommand,
     },
-    /// Export all transactions to a CSV file
+    /// Export transactions to a file
     Export {
-        /// Path to the output CSV file
+        /// Path to the output file
         filename: String,
+        /// Only export transactions of this category
+        category: Option<u32>,
+        /// Format of the output file. Defaults to the format matching the file extension, or CSV
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Parent account of the expense accounts in journals; each category becomes a sub-account
+        #[arg(long, default_value = "Expenses")]
+        account_prefix: String,
+        /// Account expenses are paid from, and income is paid into, in journals
+        #[arg(long, default_value = "Assets:Cash")]
+        funding_account: String,
+        /// Parent account of the income accounts in journals; each category becomes a sub-account
+        #[arg(long, default_value = "Income")]
+        income_prefix: String,
+        /// Account transfers go to in journals
+        #[arg(long, default_value = "Assets:Transfers")]
+        transfer_account: String,
+        /// Parent account of the tracker's accounts in journals; transactions assigned to an account are booked on a sub-account
+        #[arg(long, default_value = "Assets")]
+        asset_prefix: String,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Import transactions from a bank statement or another file
+    Import {
+        /// Path to the input file
+        filename: String,
+        /// Format of the input file. Defaults to the format matching the file extension, or CSV
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        #[command(flatten)]
+        csv: CsvArgs,
+        /// OFX fields that make up the description of a transaction
+        #[arg(long, value_enum, default_value_t = OfxDescriptionArg::Name)]
+        ofx_description: OfxDescriptionArg,
+        /// Parent account of the expense accounts in journals; postings to its sub-accounts are imported
+        #[arg(long, default_value = "Expenses")]
+        account_prefix: String,
+        /// ID of the account the imported transactions are assigned to
+        #[arg(long)]
+        account: Option<u32>,
     },
     /// Manage categories
     Category {
         #[command(subcommand)]
         category_subcommand: CategorySubcommand,
     },
+    /// Manage accounts, like cards and cash, transfer between them and show their balances
+    Account {
+        #[command(subcommand)]
+        account_subcommand: AccountSubcommand,
+    },
+    /// List, rename and merge the tags of transactions
+    Tag {
+        #[command(subcommand)]
+        tag_subcommand: TagSubcommand,
+    },
+    /// Manage recurring transactions, like rent and subscriptions
+    Recurring {
+        #[command(subcommand)]
+        recurring_subcommand: RecurringSubcommand,
+    },
+    /// Manage the base currency and exchange rates
+    Currency {
+        #[command(subcommand)]
+        currency_subcommand: CurrencySubcommand,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ImportFormat {
+    /// CSV file, read with a column mapping profile
+    Csv,
+    /// OFX statement, SGML (1.x) or XML (2.x). Also used for QFX files
+    Ofx,
+    /// Quicken interchange format
+    Qif,
+    /// ISO 20022 camt.053 bank-to-customer statement (XML)
+    Camt053,
+    /// SWIFT MT940 statement
+    Mt940,
+    /// Ledger, hledger or beancount journal
+    Journal,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the extension of the file, falling back to CSV.
+    pub fn from_path(path: &str) -> ImportFormat {
+        match extension(path).as_deref() {
+            Some("ofx" | "qfx") => ImportFormat::Ofx,
+            Some("qif") => ImportFormat::Qif,
+            Some("xml") => ImportFormat::Camt053,
+            Some("sta" | "mt940") => ImportFormat::Mt940,
+            Some("ledger" | "dat" | "journal" | "hledger" | "beancount" | "bean") => ImportFormat::Journal,
+            _ => ImportFormat::Csv,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// CSV file with a header row, which `import` reads back by default
+    Csv,
+    /// JSON array of transaction records
+    Json,
+    /// Newline-delimited JSON, one transaction record per line
+    Ndjson,
+    /// Quicken interchange format, with amounts in the base currency
+    Qif,
+    /// Ledger journal
+    Ledger,
+    /// hledger journal
+    Hledger,
+    /// Beancount journal
+    Beancount,
+    /// Excel workbook with the transactions, monthly totals and category totals
+    Xlsx,
+    /// Apache Parquet file with a typed schema, for analytics tools
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Guesses the format from the extension of the file, falling back to CSV.
+    pub fn from_path(path: &str) -> ExportFormat {
+        match extension(path).as_deref() {
+            Some("json") => ExportFormat::Json,
+            Some("ndjson" | "jsonl") => ExportFormat::Ndjson,
+            Some("qif") => ExportFormat::Qif,
+            Some("xlsx") => ExportFormat::Xlsx,
+            Some("parquet") => ExportFormat::Parquet,
+            Some("ledger" | "dat") => ExportFormat::Ledger,
+            Some("journal" | "hledger") => ExportFormat::Hledger,
+            Some("beancount" | "bean") => ExportFormat::Beancount,
+            _ => ExportFormat::Csv,
+        }
+    }
+}
+
+/// Returns the lower-case extension of a file path.
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OfxDescriptionArg {
+    /// The payee, or the memo if there is no payee
+    Name,
+    /// The memo, or the payee if there is no memo
+    Memo,
+    /// The payee and the memo
+    NameMemo,
+}
+
+impl From<OfxDescriptionArg> for OfxDescription {
+    fn from(value: OfxDescriptionArg) -> Self {
+        match value {
+            OfxDescriptionArg::Name => OfxDescription::Name,
+            OfxDescriptionArg::Memo => OfxDescription::Memo,
+            OfxDescriptionArg::NameMemo => OfxDescription::NameMemo,
+        }
+    }
+}
+
+/// Criteria shared by the commands that select transactions.
+#[derive(Args, Debug)]
+pub struct FilterArgs {
+    /// Only transactions on or after this date: YYYY-MM-DD, `yesterday`, or an offset like `-1m`
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+    pub from: Option<NaiveDate>,
+    /// Only transactions on or before this date: YYYY-MM-DD, `yesterday`, or an offset like `-1m`
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+    pub to: Option<NaiveDate>,
+    /// Only transactions of at least this amount, in their own currency
+    #[arg(long)]
+    pub min_amount: Option<Money>,
+    /// Only transactions of at most this amount, in their own currency
+    #[arg(long)]
+    pub max_amount: Option<Money>,
+    /// Only transactions of this kind. Summaries only count expenses
+    #[arg(long, value_enum)]
+    pub kind: Option<KindArg>,
+    /// Only transactions paid from or into this account, including transfers to it
+    #[arg(long)]
+    pub account: Option<u32>,
+    /// Only transactions with this tag. Repeat to require several tags
+    #[arg(long = "tag", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+}
+
+impl FilterArgs {
+    /// Builds the filter for the given category and these criteria.
+    pub fn filter(self, category: Option<u32>) -> TransactionFilter {
+        TransactionFilter {
+            from: self.from,
+            to: self.to,
+            category,
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            kind: self.kind.map(Into::into),
+            account: self.account,
+            tags: self.tags.into_iter().collect(),
+        }
+    }
+}
+
+/// Column mapping for CSV imports.
+/// Options given on the command line override the profile.
+#[derive(Args, Debug)]
+pub struct CsvArgs {
+    /// JSON file with the column mapping profile. Defaults to the layout written by `export`
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// Field delimiter
+    #[arg(long)]
+    pub delimiter: Option<char>,
+    /// The first row holds column names
+    #[arg(long)]
+    pub has_headers: bool,
+    /// The first row holds a transaction, not column names
+    #[arg(long, conflicts_with = "has_headers")]
+    pub no_headers: bool,
+    /// Date column, by header name or zero-based position
+    #[arg(long)]
+    pub date_column: Option<Column>,
+    /// Format of the date column, e.g. %d.%m.%Y
+    #[arg(long)]
+    pub date_format: Option<String>,
+    /// Amount column, by header name or zero-based position
+    #[arg(long)]
+    pub amount_column: Option<Column>,
+    /// Sign convention of the amounts
+    #[arg(long, value_enum)]
+    pub sign: Option<Sign>,
+    /// Amounts use a decimal comma, e.g. 1.234,56
+    #[arg(long)]
+    pub decimal_comma: bool,
+    /// Description column, by header name or zero-based position
+    #[arg(long)]
+    pub description_column: Option<Column>,
+    /// Category name column, by header name or zero-based position. Missing categories are created
+    #[arg(long)]
+    pub category_column: Option<Column>,
+    /// Currency column, by header name or zero-based position
+    #[arg(long)]
+    pub currency_column: Option<Column>,
+    /// Kind column (expense, income or transfer), by header name or zero-based position
+    #[arg(long)]
+    pub kind_column: Option<Column>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Sign {
+    /// Expenses are positive amounts
+    ExpensesPositive,
+    /// Expenses are negative amounts, as in most bank statements
+    ExpensesNegative,
+}
+
+impl From<Sign> for SignConvention {
+    fn from(value: Sign) -> Self {
+        match value {
+            Sign::ExpensesPositive => SignConvention::ExpensesPositive,
+            Sign::ExpensesNegative => SignConvention::ExpensesNegative,
+        }
+    }
+}
+
+impl CsvArgs {
+    /// Builds the profile from the profile file, if any, and the options given on the command line.
+    pub fn profile(self) -> Result<CsvProfile, StoreError> {
+        let mut profile = match &self.profile {
+            Some(path) => CsvProfile::load(path)?,
+            None => CsvProfile::default(),
+        };
+        if let Some(delimiter) = self.delimiter {
+            profile.delimiter = delimiter;
+        }
+        profile.has_headers = (profile.has_headers || self.has_headers) && !self.no_headers;
+        if let Some(column) = self.date_column {
+            profile.date_column = column;
+        }
+        if self.date_format.is_some() {
+            profile.date_format = self.date_format;
+        }
+        if let Some(column) = self.amount_column {
+            profile.amount_column = column;
+        }
+        if let Some(sign) = self.sign {
+            profile.sign = sign.into();
+        }
+        profile.decimal_comma |= self.decimal_comma;
+        if let Some(column) = self.description_column {
+            profile.description_column = column;
+        }
+        if self.category_column.is_some() {
+            profile.category_column = self.category_column;
+        }
+        if self.currency_column.is_some() {
+            profile.currency_column = self.currency_column;
+        }
+        if self.kind_column.is_some() {
+            profile.kind_column = self.kind_column;
+        }
+        Ok(profile)
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -67,3 +496,277 @@ pub enum CategorySubcommand {
     /// List all categories
     List,
 }
+
+#[derive(Subcommand, Debug)]
+pub enum BudgetSubcommand {
+    /// Set the budget of a category
+    Set {
+        /// ID of the category
+        category: u32,
+        /// Amount to limit spending in the category to, in the base currency. Set to 0 to remove the budget.
+        /// Applies from the current month on, past months keep their amount
+        amount: Money,
+        /// Period the budget applies to
+        #[arg(long, value_enum, default_value_t = BudgetPeriodArg::Monthly)]
+        period: BudgetPeriodArg,
+        /// What happens to the balance of a monthly budget at the end of the month
+        #[arg(long, value_enum, default_value_t = RolloverArg::None)]
+        rollover: RolloverArg,
+        /// First month of the budget, in the format YYYY-MM, from which balances roll over.
+        /// Defaults to the first month of the budget being replaced, or the current month
+        #[arg(long)]
+        since: Option<String>,
+        /// Only set the amount of this month, in the format YYYY-MM. The category must have a budget
+        #[arg(long, conflicts_with_all = ["period", "rollover", "since"])]
+        month: Option<String>,
+    },
+    /// Move budget from one category to another for a month
+    Transfer {
+        /// ID of the category giving budget
+        from: u32,
+        /// ID of the category receiving budget
+        to: u32,
+        /// Amount to move, in the base currency
+        amount: Money,
+        /// Month in the format YYYY-MM. Defaults to the current month
+        #[arg(long)]
+        month: Option<String>,
+    },
+    /// Show the opening balance, allocated budget, spending and closing balance of each monthly budget
+    Envelopes {
+        /// Month in the format YYYY-MM. Defaults to the current month
+        #[arg(long)]
+        month: Option<String>,
+    },
+    /// Show the spent, limit and remaining amount of each category budget
+    Status {
+        /// Show the periods containing this date instead of today: YYYY-MM-DD, or an offset like `-1m`
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+        date: Option<NaiveDate>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BudgetPeriodArg {
+    /// Calendar weeks, from Monday to Sunday
+    Weekly,
+    /// Calendar months
+    Monthly,
+}
+
+impl From<BudgetPeriodArg> for BudgetPeriod {
+    fn from(value: BudgetPeriodArg) -> Self {
+        match value {
+            BudgetPeriodArg::Weekly => BudgetPeriod::Weekly,
+            BudgetPeriodArg::Monthly => BudgetPeriod::Monthly,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RolloverArg {
+    /// Every month starts from scratch
+    None,
+    /// Unspent money carries into the next month; overspending is forgiven
+    Surplus,
+    /// Overspending reduces the next month; unspent money is dropped
+    Deficit,
+    /// Both unspent money and overspending carry into the next month
+    Full,
+}
+
+impl From<RolloverArg> for Rollover {
+    fn from(value: RolloverArg) -> Self {
+        match value {
+            RolloverArg::None => Rollover::None,
+            RolloverArg::Surplus => Rollover::Surplus,
+            RolloverArg::Deficit => Rollover::Deficit,
+            RolloverArg::Full => Rollover::Full,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AccountSubcommand {
+    /// Add a new account
+    Add {
+        /// Name of the account
+        name: String,
+    },
+    /// Delete an account. Its transactions are kept without an account
+    Delete {
+        /// ID of the account to delete
+        id: u32,
+    },
+    /// List all accounts with their current balance
+    List,
+    /// Move money from one account to another. Transfers don't count as spending
+    Transfer {
+        /// ID of the account the money comes from
+        from: u32,
+        /// ID of the account the money goes to
+        to: u32,
+        /// Amount of the transfer
+        amount: Money,
+        /// Description of the transfer
+        #[arg(long, default_value = "Transfer")]
+        description: String,
+        /// Currency of the transfer, e.g. EUR. Defaults to the base currency
+        #[arg(long)]
+        currency: Option<Currency>,
+        /// Date of the transfer: YYYY-MM-DD, YYYY-MM-DD HH:MM (UTC), `yesterday`, or an offset like `-3d`.
+        /// Defaults to now
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_datetime_arg)]
+        date: Option<DateTime<Utc>>,
+    },
+    /// Show the transactions of an account with the running balance after each
+    Balance {
+        /// ID of the account
+        id: u32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TagSubcommand {
+    /// List all tags with the number of transactions carrying each
+    List,
+    /// Rename a tag on all its transactions. Renaming it to a tag already in use merges the two
+    Rename {
+        /// Tag to rename
+        from: String,
+        /// New name of the tag
+        to: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RecurringSubcommand {
+    /// Add a recurring transaction
+    Add {
+        /// Description of the transactions
+        description: String,
+        /// Amount of each transaction
+        amount: Money,
+        /// Category of the transactions
+        category: Option<u32>,
+        /// How often the transaction occurs
+        #[arg(long, value_enum, default_value_t = FrequencyArg::Monthly)]
+        every: FrequencyArg,
+        /// Number of periods between two transactions, e.g. 3 with `--every monthly` for quarterly
+        #[arg(long, default_value_t = 1)]
+        interval: u32,
+        /// Date of the first transaction: YYYY-MM-DD, `yesterday`, or an offset like `-1m`. Defaults to today
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+        start: Option<NaiveDate>,
+        /// Date after which no more transactions occur: YYYY-MM-DD, or an offset from today like `1m` or `+2w`
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+        end: Option<NaiveDate>,
+        /// Day of the month of monthly and yearly transactions, 1 to 31 or `last`.
+        /// Defaults to the day of the start date. Shorter months use their last day
+        #[arg(long)]
+        day: Option<DayOfMonth>,
+        /// Currency of the transactions, e.g. EUR. Defaults to the base currency
+        #[arg(long)]
+        currency: Option<Currency>,
+        /// Whether the transactions are expenses, income, or transfers between own accounts
+        #[arg(long, value_enum, default_value_t = KindArg::Expense)]
+        kind: KindArg,
+        /// ID of the account the transactions are paid from, or paid into for income
+        #[arg(long)]
+        account: Option<u32>,
+        /// ID of the account recurring transfers go to
+        #[arg(long)]
+        to_account: Option<u32>,
+    },
+    /// Delete a recurring transaction. Transactions already created from it are kept
+    Delete {
+        /// ID of the recurring transaction to delete
+        id: u32,
+    },
+    /// List all recurring transactions
+    List,
+    /// Create the transactions that are due, including any missed since the last run.
+    /// Running it again does not create duplicates
+    Run {
+        /// Create transactions due on or before this date instead of today
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_date_arg)]
+        until: Option<NaiveDate>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum KindArg {
+    /// Money spent
+    Expense,
+    /// Money received, like a salary or a refund
+    Income,
+    /// Money moved between own accounts, like savings
+    Transfer,
+}
+
+impl From<KindArg> for TransactionKind {
+    fn from(value: KindArg) -> Self {
+        match value {
+            KindArg::Expense => TransactionKind::Expense,
+            KindArg::Income => TransactionKind::Income,
+            KindArg::Transfer => TransactionKind::Transfer,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum FrequencyArg {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl From<FrequencyArg> for Frequency {
+    fn from(value: FrequencyArg) -> Self {
+        match value {
+            FrequencyArg::Daily => Frequency::Daily,
+            FrequencyArg::Weekly => Frequency::Weekly,
+            FrequencyArg::Monthly => Frequency::Monthly,
+            FrequencyArg::Yearly => Frequency::Yearly,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CurrencySubcommand {
+    /// Show the base currency, or set it if a currency is given
+    Base {
+        /// New base currency, e.g. EUR
+        currency: Option<Currency>,
+    },
+    /// Add an exchange rate valid from the given date
+    Rate {
+        /// Date in the format YYYY-MM-DD from which the rate is valid
+        date: NaiveDate,
+        /// Currency being converted, e.g. USD
+        currency: Currency,
+        /// Value of one unit of the currency in the target currency
+        rate: Decimal,
+        /// Target currency. Defaults to the base currency
+        #[arg(long)]
+        to: Option<Currency>,
+    },
+    /// Import dated exchange rates from a CSV file with `date,currency,rate` columns
+    Import {
+        /// Path to the input CSV file
+        filename: String,
+    },
+    /// List all exchange rates
+    Rates,
+}
+
+/// Parses a date argument, which may be relative to today.
+fn parse_date_arg(value: &str) -> Result<NaiveDate, String> {
+    parse_date(value, Utc::now().date_naive())
+}
+
+/// Parses a date and time argument, which may be relative to now.
+fn parse_datetime_arg(value: &str) -> Result<DateTime<Utc>, String> {
+    parse_datetime(value, Utc::now())
+}
@@ -0,0 +1,323 @@
+use super::error::StoreError;
+use super::models::{Money, Transaction};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// ISO 4217 currency code, e.g. `EUR` or `USD`.
+/// Codes are always stored in upper case.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Currency(String);
+
+impl Currency {
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The default currency, used for data files created before currencies were tracked.
+impl Default for Currency {
+    fn default() -> Self {
+        Currency("USD".to_string())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim();
+        if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+            Ok(Currency(code.to_ascii_uppercase()))
+        } else {
+            Err(format!("invalid currency code `{}`, expected three letters like `EUR`", s))
+        }
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Currency> for String {
+    fn from(value: Currency) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Exchange rate between two currencies, valid from its date until a newer rate for the same pair.
+/// One unit of `from` is worth `rate` units of `to`.
+/// Rates read from the data file are checked like new ones, so a data file can't hold a rate that isn't positive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ExchangeRateRecord")]
+pub struct ExchangeRate {
+    date: NaiveDate,
+    from: Currency,
+    to: Currency,
+    rate: Decimal,
+}
+
+/// Exchange rate as persisted, before its rate is checked.
+#[derive(Deserialize)]
+struct ExchangeRateRecord {
+    date: NaiveDate,
+    from: Currency,
+    to: Currency,
+    rate: Decimal,
+}
+
+impl TryFrom<ExchangeRateRecord> for ExchangeRate {
+    type Error = StoreError;
+
+    fn try_from(record: ExchangeRateRecord) -> Result<Self, Self::Error> {
+        ExchangeRate::new(record.date, record.from, record.to, record.rate)
+    }
+}
+
+impl ExchangeRate {
+    /// Creates an exchange rate, the value of one unit of `from` in `to`.
+    /// It fails if the rate is not positive, since amounts can't be converted with it.
+    pub fn new(date: NaiveDate, from: Currency, to: Currency, rate: Decimal) -> Result<Self, StoreError> {
+        if rate <= Decimal::ZERO {
+            return Err(StoreError::InvalidInput(format!("exchange rate must be positive, got {}", rate)));
+        }
+        Ok(ExchangeRate { date, from, to, rate })
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn from(&self) -> &Currency {
+        &self.from
+    }
+
+    pub fn to(&self) -> &Currency {
+        &self.to
+    }
+
+    pub fn rate(&self) -> Decimal {
+        self.rate
+    }
+}
+
+/// Locally maintained table of exchange rates, together with the base currency of the store.
+/// All reports (summary, limits, export) convert amounts into the base currency.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    base: Currency,
+    rates: Vec<ExchangeRate>,
+}
+
+impl ExchangeRates {
+    pub fn base(&self) -> &Currency {
+        &self.base
+    }
+
+    pub fn set_base(&mut self, base: Currency) {
+        self.base = base;
+    }
+
+    /// Adds a rate to the table.
+    /// A rate for the same date and currency pair replaces the existing one.
+    pub fn add(&mut self, rate: ExchangeRate) {
+        self.rates.retain(|r| !(r.date == rate.date && r.from == rate.from && r.to == rate.to));
+        self.rates.push(rate);
+    }
+
+    /// Lists all rates, sorted by currency pair and date.
+    pub fn list(&self) -> Vec<&ExchangeRate> {
+        let mut rates: Vec<&ExchangeRate> = self.rates.iter().collect();
+        rates.sort_by(|a, b| (&a.from, &a.to, a.date).cmp(&(&b.from, &b.to, b.date)));
+        rates
+    }
+
+    /// Returns the rate to convert `from` into `to` that is valid on the given date.
+    /// The most recent rate dated on or before `date` is used; an inverse rate is used
+    /// if only the opposite direction is known.
+    pub fn rate(&self, from: &Currency, to: &Currency, date: NaiveDate) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+        let direct = self.latest(from, to, date).map(|r| (r.date, r.rate));
+        let inverse = self.latest(to, from, date)
+            .map(|r| (r.date, Decimal::ONE / r.rate));
+        match (direct, inverse) {
+            (Some(d), Some(i)) => Some(if i.0 > d.0 { i.1 } else { d.1 }),
+            (d, i) => d.or(i).map(|(_, rate)| rate),
+        }
+    }
+
+    fn latest(&self, from: &Currency, to: &Currency, date: NaiveDate) -> Option<&ExchangeRate> {
+        self.rates
+            .iter()
+            .filter(|r| &r.from == from && &r.to == to && r.date <= date)
+            .max_by_key(|r| r.date)
+    }
+
+    /// Converts an amount between currencies using the rate valid on the given date.
+    /// Converted amounts are rounded to cents.
+    pub fn convert(&self, amount: Money, from: &Currency, to: &Currency, date: NaiveDate) -> Option<Money> {
+        if from == to {
+            return Some(amount);
+        }
+        self.rate(from, to, date).map(|rate| Money::from((amount.as_decimal() * rate).round_dp(2)))
+    }
+
+    /// Converts the amount of a transaction into the base currency,
+    /// using the rate valid on the transaction's date.
+    /// It fails if there is no such rate.
+    pub fn to_base(&self, transaction: &Transaction) -> Result<Money, StoreError> {
+        let date = transaction.datetime().date_naive();
+        self.convert(transaction.amount(), transaction.currency(), &self.base, date)
+            .ok_or_else(|| StoreError::MissingExchangeRate {
+                from: transaction.currency().clone(),
+                to: self.base.clone(),
+                date,
+            })
+    }
+}
+
+/// Reads dated exchange rates from a CSV file with a header row.
+/// Supported columns are `date` (YYYY-MM-DD), `currency` (or `from`), an optional `to`
+/// (defaults to the base currency) and `rate`, the value of one unit of `currency` in `to`.
+pub fn read_rates_csv(path: &str, base: &Currency) -> Result<Vec<ExchangeRate>, StoreError> {
+    let invalid = |message: String| StoreError::InvalidInput(format!("`{}`: {}", path, message));
+    let mut reader = csv::Reader::from_path(path).map_err(|e| csv_error(path, e))?;
+    let headers = reader.headers().map_err(|e| csv_error(path, e))?.clone();
+    let column = |name: &str, aliases: &[&str]| {
+        headers
+            .iter()
+            .position(|h| aliases.contains(&h.trim().to_ascii_lowercase().as_str()))
+            .ok_or_else(|| invalid(format!("no `{}` column", name)))
+    };
+    let date_col = column("date", &["date"])?;
+    let from_col = column("currency", &["currency", "from"])?;
+    let to_col = column("to", &["to"]).ok();
+    let rate_col = column("rate", &["rate"])?;
+
+    let mut rates = vec![];
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+        let record = record.map_err(|e| csv_error(path, e))?;
+        let field = |col: usize| record.get(col).unwrap_or("").trim();
+        let date = NaiveDate::parse_from_str(field(date_col), "%Y-%m-%d")
+            .map_err(|_| invalid(format!("invalid date `{}` on line {}", field(date_col), line)))?;
+        let from: Currency = field(from_col).parse()
+            .map_err(|e| invalid(format!("{} on line {}", e, line)))?;
+        let to: Currency = match to_col {
+            Some(col) if !field(col).is_empty() => field(col).parse()
+                .map_err(|e| invalid(format!("{} on line {}", e, line)))?,
+            _ => base.clone(),
+        };
+        let rate = Decimal::from_str_exact(field(rate_col))
+            .map_err(|_| invalid(format!("invalid rate `{}` on line {}", field(rate_col), line)))?;
+        let rate = ExchangeRate::new(date, from, to, rate)
+            .map_err(|e| invalid(format!("{} on line {}", e, line)))?;
+        rates.push(rate);
+    }
+    Ok(rates)
+}
+
+/// Converts an error from the CSV reader: I/O errors are reported as such,
+/// anything else means the file is malformed.
+pub(crate) fn csv_error(path: &str, error: csv::Error) -> StoreError {
+    if error.is_io_error() {
+        match error.into_kind() {
+            csv::ErrorKind::Io(e) => StoreError::io(path, e),
+            _ => unreachable!("checked with is_io_error"),
+        }
+    } else {
+        StoreError::InvalidInput(format!("`{}`: {}", path, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn currency(code: &str) -> Currency {
+        code.parse().unwrap()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn rates() -> ExchangeRates {
+        let mut rates = ExchangeRates::default();
+        rates.set_base(currency("EUR"));
+        rates.add(ExchangeRate::new(date("2025-01-01"), currency("USD"), currency("EUR"), Decimal::new(90, 2)).unwrap());
+        rates.add(ExchangeRate::new(date("2025-02-01"), currency("USD"), currency("EUR"), Decimal::new(95, 2)).unwrap());
+        rates.add(ExchangeRate::new(date("2025-01-01"), currency("EUR"), currency("GBP"), Decimal::new(80, 2)).unwrap());
+        rates
+    }
+
+    #[test]
+    fn test_currency_code() {
+        assert_eq!(currency("eur").code(), "EUR");
+        assert!("EURO".parse::<Currency>().is_err());
+        assert!("E1R".parse::<Currency>().is_err());
+    }
+
+    #[test]
+    fn test_rate_valid_on_date() {
+        let rates = rates();
+        let usd = currency("USD");
+        let eur = currency("EUR");
+        assert_eq!(rates.rate(&usd, &eur, date("2024-12-31")), None);
+        assert_eq!(rates.rate(&usd, &eur, date("2025-01-15")), Some(Decimal::new(90, 2)));
+        assert_eq!(rates.rate(&usd, &eur, date("2025-02-01")), Some(Decimal::new(95, 2)));
+        assert_eq!(rates.rate(&eur, &eur, date("2000-01-01")), Some(Decimal::ONE));
+    }
+
+    #[test]
+    fn test_convert_with_inverse_rate() {
+        let rates = rates();
+        let amount: Money = "100".parse().unwrap();
+        let converted = rates.convert(amount, &currency("GBP"), &currency("EUR"), date("2025-03-01"));
+        assert_eq!(converted, Some("125".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_add_replaces_same_day_rate() {
+        let mut rates = rates();
+        rates.add(ExchangeRate::new(date("2025-01-01"), currency("USD"), currency("EUR"), Decimal::new(91, 2)).unwrap());
+        assert_eq!(rates.list().len(), 3);
+        assert_eq!(rates.rate(&currency("USD"), &currency("EUR"), date("2025-01-02")), Some(Decimal::new(91, 2)));
+    }
+
+    #[test]
+    fn test_rate_must_be_positive() {
+        // `currency rate` creates its rate with `ExchangeRate::new`, like the CSV reader.
+        let new = |rate| ExchangeRate::new(date("2025-01-01"), currency("USD"), currency("EUR"), rate);
+        assert!(matches!(new(Decimal::ZERO), Err(StoreError::InvalidInput(_))));
+        assert!(matches!(new(Decimal::new(-5, 1)), Err(StoreError::InvalidInput(_))));
+        let temp_file = "test_data_rates_1.csv";
+        fs::write(temp_file, "date,currency,rate\n2025-01-01,USD,-1\n").expect("Unable to write file");
+        let result = read_rates_csv(temp_file, &currency("EUR"));
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        assert!(matches!(result, Err(StoreError::InvalidInput(message)) if message.contains("exchange rate must be positive, got -1 on line 2")));
+
+        // Rates read from the data file are checked too.
+        let json = r#"{"base": "EUR", "rates": [{"date": "2025-01-01", "from": "USD", "to": "EUR", "rate": "0"}]}"#;
+        let error = serde_json::from_str::<ExchangeRates>(json).unwrap_err();
+        assert!(error.to_string().contains("exchange rate must be positive"));
+        let json = r#"{"base": "EUR", "rates": [{"date": "2025-01-01", "from": "USD", "to": "EUR", "rate": "0.9"}]}"#;
+        assert_eq!(serde_json::from_str::<ExchangeRates>(json).unwrap().list().len(), 1);
+    }
+}
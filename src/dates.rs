@@ -0,0 +1,101 @@
+use chrono::{DateTime, Days, Months, NaiveDate, NaiveDateTime, Utc};
+
+/// Parses a date given on the command line, relative to `today`.
+/// Besides `YYYY-MM-DD`, it accepts `today`, `yesterday`, `tomorrow` and offsets in days, weeks
+/// and months, into the past like `-3d`, `-2w` or `-1m`, or into the future like `3d` or `+1m`.
+pub fn parse_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let value = value.trim();
+    let invalid = || {
+        format!(
+            "invalid date `{}`, use YYYY-MM-DD, `today`, `yesterday`, `tomorrow` or an offset like `-3d`, `-2w`, `-1m` or `+1m`",
+            value
+        )
+    };
+    match value.to_ascii_lowercase().as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return today.checked_sub_days(Days::new(1)).ok_or_else(invalid),
+        "tomorrow" => return today.checked_add_days(Days::new(1)).ok_or_else(invalid),
+        _ => {}
+    }
+    let (past, offset) = match value.strip_prefix('-') {
+        Some(offset) => (true, offset),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let Some(unit) = offset.chars().last().filter(char::is_ascii_alphabetic) else {
+        // Only offsets have a sign.
+        if offset.len() != value.len() {
+            return Err(invalid());
+        }
+        return NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid());
+    };
+    let count: u32 = offset[..offset.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+    let days = match unit.to_ascii_lowercase() {
+        'd' => Days::new(count.into()),
+        'w' => Days::new(u64::from(count) * 7),
+        'm' if past => return today.checked_sub_months(Months::new(count)).ok_or_else(invalid),
+        'm' => return today.checked_add_months(Months::new(count)).ok_or_else(invalid),
+        _ => return Err(invalid()),
+    };
+    if past { today.checked_sub_days(days) } else { today.checked_add_days(days) }.ok_or_else(invalid)
+}
+
+/// Parses a date and time given on the command line, relative to `now`.
+/// It accepts RFC 3339 timestamps, `YYYY-MM-DD HH:MM[:SS]` in UTC, and all forms of `parse_date`;
+/// a date without a time keeps the time of day of `now`, so transactions added in a row stay in order.
+pub fn parse_datetime(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(datetime.and_utc());
+        }
+    }
+    parse_date(value, now.date_naive()).map(|date| date.and_time(now.time()).and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_relative_dates() {
+        let today = date("2025-03-31");
+        assert_eq!(parse_date("today", today), Ok(today));
+        assert_eq!(parse_date("Yesterday", today), Ok(date("2025-03-30")));
+        assert_eq!(parse_date("-3d", today), Ok(date("2025-03-28")));
+        assert_eq!(parse_date("-2w", today), Ok(date("2025-03-17")));
+        assert_eq!(parse_date("-1m", today), Ok(date("2025-02-28")));
+        assert_eq!(parse_date("2024-12-24", today), Ok(date("2024-12-24")));
+        assert!(parse_date("-3x", today).is_err());
+        assert!(parse_date("-d", today).is_err());
+        assert!(parse_date("last friday", today).is_err());
+    }
+
+    #[test]
+    fn test_parse_future_dates() {
+        let today = date("2025-01-31");
+        assert_eq!(parse_date("tomorrow", today), Ok(date("2025-02-01")));
+        assert_eq!(parse_date("3d", today), Ok(date("2025-02-03")));
+        assert_eq!(parse_date("+2w", today), Ok(date("2025-02-14")));
+        assert_eq!(parse_date("1m", today), Ok(date("2025-02-28")));
+        assert_eq!(parse_date("+12M", today), Ok(date("2026-01-31")));
+        assert!(parse_date("+x", today).is_err());
+        assert!(parse_date("+2025-01-01", today).is_err());
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let now = DateTime::parse_from_rfc3339("2025-03-31T18:30:00Z").unwrap().with_timezone(&Utc);
+        let expected = |value: &str| DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc);
+        assert_eq!(parse_datetime("2025-03-01T10:00:00+02:00", now), Ok(expected("2025-03-01T08:00:00Z")));
+        assert_eq!(parse_datetime("2025-03-01 10:15", now), Ok(expected("2025-03-01T10:15:00Z")));
+        assert_eq!(parse_datetime("2025-03-01", now), Ok(expected("2025-03-01T18:30:00Z")));
+        assert_eq!(parse_datetime("yesterday", now), Ok(expected("2025-03-30T18:30:00Z")));
+    }
+}
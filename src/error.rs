@@ -0,0 +1,107 @@
+use super::currency::Currency;
+use super::models::{AccountId, CategoryId, TransactionId};
+use super::recurring::RecurringId;
+use chrono::NaiveDate;
+use std::io;
+use thiserror::Error;
+
+/// Errors returned by the store, its storage backends and the summary functions.
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("category with ID {0} not found")]
+    CategoryNotFound(CategoryId),
+
+    #[error("account with ID {0} not found")]
+    AccountNotFound(AccountId),
+
+    #[error("no transaction is tagged `{0}`")]
+    TagNotFound(String),
+
+    #[error("transaction with ID {0} not found")]
+    TransactionNotFound(TransactionId),
+
+    #[error("recurring transaction with ID {0} not found")]
+    RecurringNotFound(RecurringId),
+
+    #[error("invalid month `{0}`, use YYYY-MM or `overall`")]
+    InvalidMonth(String),
+
+    #[error("no exchange rate from {from} to {to} valid on {date}, add one with `expense-tracker currency rate`")]
+    MissingExchangeRate { from: Currency, to: Currency, date: NaiveDate },
+
+    #[error("{0}")]
+    InvalidInput(String),
+
+    #[error("{0}")]
+    Locked(String),
+
+    #[error("`{path}` is corrupted: {message}")]
+    Corrupted { path: String, message: String },
+
+    #[error("unable to access `{path}`: {source}")]
+    Io { path: String, source: io::Error },
+
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+}
+
+impl StoreError {
+    /// Wraps an I/O error on the given path.
+    /// Lock timeouts are reported as `Locked`, since they are usually temporary.
+    pub fn io(path: &str, source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::WouldBlock {
+            StoreError::Locked(source.to_string())
+        } else {
+            StoreError::Io { path: path.to_string(), source }
+        }
+    }
+}
+
+/// Errors reported by the command-line application.
+/// Each kind of error maps to a distinct process exit code, see `AppError::exit_code`.
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Store(#[from] StoreError),
+
+    #[error("unable to write `{path}`: {source}")]
+    Output { path: String, source: io::Error },
+
+    #[error("{0}")]
+    Usage(String),
+}
+
+impl AppError {
+    /// Exit code for a general error.
+    pub const EXIT_FAILURE: i32 = 1;
+    /// Exit code for invalid command-line usage, the same as for argument errors reported by clap.
+    pub const EXIT_USAGE: i32 = 2;
+    /// Exit code when an ID given on the command line does not exist.
+    pub const EXIT_NOT_FOUND: i32 = 3;
+    /// Exit code for invalid user input, e.g. a bad month or a malformed input file.
+    pub const EXIT_INVALID_INPUT: i32 = 4;
+    /// Exit code when the data file can't be read, parsed or written.
+    pub const EXIT_DATA_ERROR: i32 = 5;
+    /// Exit code when another process holds the lock on the data file.
+    pub const EXIT_LOCKED: i32 = 6;
+
+    /// Returns the process exit code for the error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Store(
+                StoreError::CategoryNotFound(_)
+                | StoreError::AccountNotFound(_)
+                | StoreError::TagNotFound(_)
+                | StoreError::TransactionNotFound(_)
+                | StoreError::RecurringNotFound(_),
+            ) => Self::EXIT_NOT_FOUND,
+            AppError::Store(
+                StoreError::InvalidMonth(_) | StoreError::MissingExchangeRate { .. } | StoreError::InvalidInput(_),
+            ) => Self::EXIT_INVALID_INPUT,
+            AppError::Store(StoreError::Locked(_)) => Self::EXIT_LOCKED,
+            AppError::Store(StoreError::Corrupted { .. } | StoreError::Io { .. } | StoreError::Database(_)) => Self::EXIT_DATA_ERROR,
+            AppError::Output { .. } => Self::EXIT_FAILURE,
+            AppError::Usage(_) => Self::EXIT_USAGE,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use super::ExportRow;
+use std::io::{self, Write};
+
+/// Writes the transactions as CSV, with a header row.
+/// The columns are `id`, `description`, `amount`, `currency`, `base_amount` (the amount in the
+/// base currency), `datetime` (ISO 8601, UTC), `category_id`, `category`, `kind` (`expense`,
+/// `income` or `transfer`), `account`, `to_account` (the account a transfer goes to) and `tags`
+/// (separated by spaces); the category and account columns are empty for transactions without one.
+pub fn write_csv(out: impl Write, rows: &[ExportRow]) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    for row in rows {
+        writer.serialize(row.record())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, Category, Transaction};
+
+    #[test]
+    fn test_write_csv() {
+        let datetime = chrono::DateTime::parse_from_rfc3339("2025-04-01T12:30:00.5Z").unwrap().to_utc();
+        let mut lunch = Transaction::new(1, "12.5".parse().unwrap(), "EUR".parse().unwrap(), "Lunch, with \"dessert\"".to_string(), Some(Category::new(3, "Food".to_string())));
+        lunch.set_datetime(datetime);
+        let mut taxi = Transaction::new(2, "20".parse().unwrap(), "USD".parse().unwrap(), "Taxi".to_string(), None);
+        taxi.set_datetime(datetime);
+        taxi.set_account(Some(Account::new(1, "Card".to_string())));
+        taxi.set_tags(["work".to_string(), "trip-lisbon".to_string()].into());
+        let rows = [
+            ExportRow { transaction: &lunch, base_amount: "13.6".parse().unwrap() },
+            ExportRow { transaction: &taxi, base_amount: "20".parse().unwrap() },
+        ];
+        let mut out = vec![];
+        write_csv(&mut out, &rows).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id,description,amount,currency,base_amount,datetime,category_id,category,kind,account,to_account,tags\n\
+             1,\"Lunch, with \"\"dessert\"\"\",12.5,EUR,13.6,2025-04-01T12:30:00.500Z,3,Food,expense,,,\n\
+             2,Taxi,20,USD,20,2025-04-01T12:30:00.500Z,,,expense,Card,,trip-lisbon work\n"
+        );
+    }
+}
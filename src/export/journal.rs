@@ -0,0 +1,229 @@
+use super::ExportRow;
+use crate::models::{Account, TransactionKind};
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+/// Plain-text accounting tool a journal is written for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+/// Accounts the journal entries are booked on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalAccounts {
+    /// Parent of the expense accounts; each category becomes a sub-account of it.
+    pub expense_prefix: String,
+    /// Account the expenses are paid from, and income is paid into.
+    pub funding_account: String,
+    /// Parent of the income accounts; each category becomes a sub-account of it.
+    pub income_prefix: String,
+    /// Account transfers from the funding account go to, e.g. a savings account.
+    pub transfer_account: String,
+    /// Parent of the accounts of the tracker; a transaction assigned to an account is paid from
+    /// a sub-account of it instead of the funding account, and a transfer goes to the sub-account
+    /// of its destination instead of the transfer account.
+    pub asset_prefix: String,
+}
+
+impl Default for JournalAccounts {
+    fn default() -> Self {
+        JournalAccounts {
+            expense_prefix: "Expenses".to_string(),
+            funding_account: "Assets:Cash".to_string(),
+            income_prefix: "Income".to_string(),
+            transfer_account: "Assets:Transfers".to_string(),
+            asset_prefix: "Assets".to_string(),
+        }
+    }
+}
+
+/// Name of the expense or income account for transactions without a category.
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// Writes the transactions as a journal of balanced entries: the category's expense
+/// account is debited with the amount in the transaction's currency, and the funding
+/// account is credited with the balancing amount, which is left for the tool to infer.
+/// Income is credited to the category's income account instead, and transfers are
+/// debited to the transfer account. Transactions assigned to an account of the tracker
+/// are booked on its asset account instead of the funding or transfer account.
+/// The description is the payee (ledger, hledger) or the narration (beancount), and
+/// the transaction ID is kept as an `id` tag or metadata. The tags of a transaction become tags
+/// of its entry: `:work:` comments (ledger), `work:` tags (hledger) or `#work` (beancount).
+/// Beancount journals start with `open` directives for all accounts, dated on the first transaction.
+pub fn write_journal(mut out: impl Write, rows: &[ExportRow], dialect: Dialect, accounts: &JournalAccounts) -> io::Result<()> {
+    let asset_account = |account: Option<Account>, default: &str| match account {
+        Some(account) => account_name(&format!("{}:{}", accounts.asset_prefix, account.name()), dialect),
+        None => account_name(default, dialect),
+    };
+    let booked_account = |row: &ExportRow| {
+        let prefix = match row.transaction.kind() {
+            TransactionKind::Expense => &accounts.expense_prefix,
+            TransactionKind::Income => &accounts.income_prefix,
+            TransactionKind::Transfer => return asset_account(row.transaction.to_account(), &accounts.transfer_account),
+        };
+        let category = row.transaction.category();
+        let name = category.as_ref().map_or(UNCATEGORIZED, |category| category.name());
+        account_name(&format!("{}:{}", prefix, name), dialect)
+    };
+    let funding_account = |row: &ExportRow| asset_account(row.transaction.account(), &accounts.funding_account);
+
+    let first = rows.iter().map(|row| row.transaction.datetime()).min();
+    if let (Dialect::Beancount, Some(first)) = (dialect, first) {
+        let opened: BTreeSet<String> = rows.iter().flat_map(|row| [booked_account(row), funding_account(row)]).collect();
+        for account in opened {
+            writeln!(out, "{} open {}", first.format("%Y-%m-%d"), account)?;
+        }
+        writeln!(out)?;
+    }
+
+    for row in rows {
+        let transaction = row.transaction;
+        let amount = match transaction.kind() {
+            TransactionKind::Income => -transaction.amount(),
+            TransactionKind::Expense | TransactionKind::Transfer => transaction.amount(),
+        };
+        let amount = format!("{} {}", amount, transaction.currency());
+        let description = single_line(transaction.description());
+        let tags = transaction.tags();
+        match dialect {
+            Dialect::Ledger => {
+                writeln!(out, "{} {}  ; id:{}", transaction.datetime().format("%Y/%m/%d"), description, transaction.id())?;
+                if !tags.is_empty() {
+                    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+                    writeln!(out, "    ; :{}:", tags.join(":"))?;
+                }
+            }
+            Dialect::Hledger => {
+                let tags: String = tags.iter().map(|tag| format!(", {}:", tag)).collect();
+                writeln!(out, "{} {}  ; id:{}{}", transaction.datetime().format("%Y-%m-%d"), description, transaction.id(), tags)?;
+            }
+            Dialect::Beancount => {
+                let tags: String = tags.iter().map(|tag| format!(" #{}", tag)).collect();
+                writeln!(out, "{} * \"{}\"{}", transaction.datetime().format("%Y-%m-%d"), description.replace('\\', "\\\\").replace('"', "\\\""), tags)?;
+                writeln!(out, "  id: \"{}\"", transaction.id())?;
+            }
+        }
+        writeln!(out, "    {}  {}", booked_account(row), amount)?;
+        writeln!(out, "    {}", funding_account(row))?;
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+/// Turns a colon-separated account path into a valid account name.
+/// Ledger and hledger allow spaces, but two of them end the account name, so whitespace is collapsed.
+/// Beancount only allows letters, digits and dashes, with each component starting with a capital
+/// letter, so `eating out` becomes `Eating-Out`, and components that don't start with a Latin
+/// letter are prefixed with `X`.
+fn account_name(path: &str, dialect: Dialect) -> String {
+    let components = path.split(':').map(str::trim).filter(|component| !component.is_empty());
+    match dialect {
+        Dialect::Ledger | Dialect::Hledger => components
+            .map(|component| component.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join(":"),
+        Dialect::Beancount => components
+            .map(|component| {
+                let words: Vec<String> = component
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .map(capitalize)
+                    .collect();
+                let component = words.join("-");
+                if component.starts_with(|c: char| c.is_ascii_uppercase()) {
+                    component
+                } else {
+                    format!("X{}", component)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(":"),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Journal lines can't span lines.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, Category, Transaction};
+
+    fn transactions() -> Vec<Transaction> {
+        let datetime = chrono::DateTime::parse_from_rfc3339("2025-04-01T12:00:00Z").unwrap().to_utc();
+        let mut lunch = Transaction::new(1, "12.5".parse().unwrap(), "EUR".parse().unwrap(), "Lunch \"Da Mario\"".to_string(), Some(Category::new(1, "eating  out".to_string())));
+        lunch.set_datetime(datetime);
+        lunch.set_tags(["trip-lisbon".to_string(), "work".to_string()].into());
+        let mut taxi = Transaction::new(2, "20".parse().unwrap(), "USD".parse().unwrap(), "Taxi".to_string(), None);
+        taxi.set_datetime(datetime);
+        let mut salary = Transaction::new(3, "2000".parse().unwrap(), "USD".parse().unwrap(), "Salary".to_string(), None);
+        salary.set_datetime(datetime);
+        salary.set_kind(TransactionKind::Income);
+        let mut savings = Transaction::new(4, "500".parse().unwrap(), "USD".parse().unwrap(), "Savings".to_string(), None);
+        savings.set_datetime(datetime);
+        savings.set_kind(TransactionKind::Transfer);
+        let mut card = Transaction::new(5, "300".parse().unwrap(), "USD".parse().unwrap(), "Pay off card".to_string(), None);
+        card.set_datetime(datetime);
+        card.set_kind(TransactionKind::Transfer);
+        card.set_account(Some(Account::new(1, "Checking".to_string())));
+        card.set_to_account(Some(Account::new(2, "credit card".to_string())));
+        vec![lunch, taxi, salary, savings, card]
+    }
+
+    fn write(dialect: Dialect, accounts: &JournalAccounts) -> String {
+        let transactions = transactions();
+        let rows: Vec<ExportRow> = transactions
+            .iter()
+            .map(|transaction| ExportRow { transaction, base_amount: transaction.amount() })
+            .collect();
+        let mut out = vec![];
+        write_journal(&mut out, &rows, dialect, accounts).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_hledger() {
+        let accounts = JournalAccounts {
+            expense_prefix: "Expenses:Personal".to_string(),
+            funding_account: "Assets:Bank:Checking".to_string(),
+            ..JournalAccounts::default()
+        };
+        assert_eq!(
+            write(Dialect::Hledger, &accounts),
+            "2025-04-01 Lunch \"Da Mario\"  ; id:1, trip-lisbon:, work:\n    Expenses:Personal:eating out  12.50 EUR\n    Assets:Bank:Checking\n\n\
+             2025-04-01 Taxi  ; id:2\n    Expenses:Personal:Uncategorized  20.00 USD\n    Assets:Bank:Checking\n\n\
+             2025-04-01 Salary  ; id:3\n    Income:Uncategorized  -2000.00 USD\n    Assets:Bank:Checking\n\n\
+             2025-04-01 Savings  ; id:4\n    Assets:Transfers  500.00 USD\n    Assets:Bank:Checking\n\n\
+             2025-04-01 Pay off card  ; id:5\n    Assets:credit card  300.00 USD\n    Assets:Checking\n\n"
+        );
+        assert!(write(Dialect::Ledger, &accounts).starts_with("2025/04/01 Lunch \"Da Mario\"  ; id:1\n    ; :trip-lisbon:work:\n"));
+    }
+
+    #[test]
+    fn test_write_beancount() {
+        assert_eq!(
+            write(Dialect::Beancount, &JournalAccounts::default()),
+            "2025-04-01 open Assets:Cash\n2025-04-01 open Assets:Checking\n2025-04-01 open Assets:Credit-Card\n\
+             2025-04-01 open Assets:Transfers\n2025-04-01 open Expenses:Eating-Out\n\
+             2025-04-01 open Expenses:Uncategorized\n2025-04-01 open Income:Uncategorized\n\n\
+             2025-04-01 * \"Lunch \\\"Da Mario\\\"\" #trip-lisbon #work\n  id: \"1\"\n    Expenses:Eating-Out  12.50 EUR\n    Assets:Cash\n\n\
+             2025-04-01 * \"Taxi\"\n  id: \"2\"\n    Expenses:Uncategorized  20.00 USD\n    Assets:Cash\n\n\
+             2025-04-01 * \"Salary\"\n  id: \"3\"\n    Income:Uncategorized  -2000.00 USD\n    Assets:Cash\n\n\
+             2025-04-01 * \"Savings\"\n  id: \"4\"\n    Assets:Transfers  500.00 USD\n    Assets:Cash\n\n\
+             2025-04-01 * \"Pay off card\"\n  id: \"5\"\n    Assets:Credit-Card  300.00 USD\n    Assets:Checking\n\n"
+        );
+    }
+}
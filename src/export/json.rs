@@ -0,0 +1,55 @@
+use super::ExportRow;
+use std::io::{self, Write};
+
+/// Writes the transactions as a pretty-printed JSON array of records.
+/// The fields are those of the CSV export; a transaction without a category has
+/// `null` as `category_id` and `category`, and one without an account `null` as `account`.
+pub fn write_json(mut out: impl Write, rows: &[ExportRow]) -> io::Result<()> {
+    let records: Vec<_> = rows.iter().map(ExportRow::record).collect();
+    serde_json::to_writer_pretty(&mut out, &records)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+/// Writes the transactions as newline-delimited JSON, one record per line,
+/// so the output can be streamed and processed line by line.
+pub fn write_ndjson(mut out: impl Write, rows: &[ExportRow]) -> io::Result<()> {
+    for row in rows {
+        serde_json::to_writer(&mut out, &row.record())?;
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Category, Transaction};
+
+    #[test]
+    fn test_write_ndjson() {
+        let mut lunch = Transaction::new(1, "12.5".parse().unwrap(), "EUR".parse().unwrap(), "Lunch".to_string(), Some(Category::new(3, "Food".to_string())));
+        lunch.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-01T12:30:00Z").unwrap().to_utc());
+        let taxi = Transaction::new(2, "20".parse().unwrap(), "USD".parse().unwrap(), "Taxi".to_string(), None);
+        let rows = [
+            ExportRow { transaction: &lunch, base_amount: "13.6".parse().unwrap() },
+            ExportRow { transaction: &taxi, base_amount: "20".parse().unwrap() },
+        ];
+        let mut out = vec![];
+        write_ndjson(&mut out, &rows).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"id":1,"description":"Lunch","amount":"12.5","currency":"EUR","base_amount":"13.6","datetime":"2025-04-01T12:30:00Z","category_id":3,"category":"Food","kind":"expense","account":null,"to_account":null,"tags":""}"#
+        );
+        let taxi: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(taxi["category"], serde_json::Value::Null);
+
+        let mut out = vec![];
+        write_json(&mut out, &rows).unwrap();
+        let records: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(records.as_array().map(Vec::len), Some(2));
+    }
+}
@@ -0,0 +1,73 @@
+//! Writers for the formats transactions can be exported to.
+//! Every writer takes the transactions together with their amounts in the base currency,
+//! so conversion errors are reported before anything is written.
+
+pub mod csv;
+pub mod journal;
+pub mod json;
+pub mod parquet;
+pub mod qif;
+pub mod xlsx;
+
+use crate::currency::ExchangeRates;
+use crate::error::StoreError;
+use crate::models::{CategoryId, Money, Transaction, TransactionId, TransactionKind};
+use chrono::SecondsFormat;
+use serde::Serialize;
+
+/// A transaction to export, with its amount converted into the base currency.
+#[derive(Debug, Clone, Copy)]
+pub struct ExportRow<'a> {
+    pub transaction: &'a Transaction,
+    pub base_amount: Money,
+}
+
+impl ExportRow<'_> {
+    /// Returns the flat record written by the CSV and JSON writers.
+    pub fn record(&self) -> ExportRecord {
+        let transaction = self.transaction;
+        let category = transaction.category();
+        ExportRecord {
+            id: transaction.id(),
+            description: transaction.description().to_string(),
+            amount: transaction.amount(),
+            currency: transaction.currency().to_string(),
+            base_amount: self.base_amount,
+            datetime: transaction.datetime().to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            category_id: category.as_ref().map(|category| category.id()),
+            category: category.map(|category| category.name().to_string()),
+            kind: transaction.kind(),
+            account: transaction.account().map(|account| account.name().to_string()),
+            to_account: transaction.to_account().map(|account| account.name().to_string()),
+            tags: transaction.tags().iter().cloned().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// A transaction as written by the CSV and JSON writers. Amounts are decimal strings,
+/// so no precision is lost, and the datetime is an ISO 8601 timestamp in UTC.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRecord {
+    pub id: TransactionId,
+    pub description: String,
+    pub amount: Money,
+    pub currency: String,
+    pub base_amount: Money,
+    pub datetime: String,
+    pub category_id: Option<CategoryId>,
+    pub category: Option<String>,
+    pub kind: TransactionKind,
+    pub account: Option<String>,
+    pub to_account: Option<String>,
+    /// Tags of the transaction, separated by spaces.
+    pub tags: String,
+}
+
+/// Converts the transactions into export rows.
+/// It fails if an exchange rate is missing for any of them.
+pub fn export_rows<'a>(transactions: Vec<&'a Transaction>, rates: &ExchangeRates) -> Result<Vec<ExportRow<'a>>, StoreError> {
+    transactions
+        .into_iter()
+        .map(|transaction| Ok(ExportRow { transaction, base_amount: rates.to_base(transaction)? }))
+        .collect()
+}
@@ -0,0 +1,186 @@
+use super::ExportRow;
+use crate::error::StoreError;
+use crate::models::Money;
+use arrow_array::builder::{Decimal128Builder, ListBuilder, StringBuilder, TimestampMicrosecondBuilder, UInt32Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// Precision of the decimal columns, the largest a 128-bit decimal allows.
+const DECIMAL_PRECISION: u8 = 38;
+
+/// Scale of the decimal columns, enough for fractions of cents, e.g. fuel prices.
+/// It is fixed, so every export has the same schema and several exports can be read together.
+const DECIMAL_SCALE: i8 = 4;
+
+/// Writes the transactions as an Apache Parquet file with a typed schema:
+/// `id` (uint32), `description` (string), `amount` and `base_amount` (decimal(38, 4)),
+/// `currency` (string), `datetime` (timestamp in microseconds, UTC), `category_id` (uint32),
+/// `category`, `kind`, `account`, `to_account` and `external_ref` (strings), and `tags` (list of strings).
+/// `category_id`, `category`, the account columns and `external_ref` are null where a transaction
+/// has no value; `tags` is an empty list instead.
+/// The decimal columns have a fixed scale of 4 decimal places; it fails if an amount has more,
+/// rather than losing digits. [`check_scale`] reports such amounts before anything is written.
+pub fn write_parquet(out: impl Write + Send, rows: &[ExportRow]) -> io::Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt32, false),
+        Field::new("description", DataType::Utf8, false),
+        Field::new("amount", DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE), false),
+        Field::new("currency", DataType::Utf8, false),
+        Field::new("base_amount", DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE), false),
+        Field::new("datetime", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), false),
+        Field::new("category_id", DataType::UInt32, true),
+        Field::new("category", DataType::Utf8, true),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("account", DataType::Utf8, true),
+        Field::new("to_account", DataType::Utf8, true),
+        Field::new("external_ref", DataType::Utf8, true),
+        Field::new("tags", DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))), false),
+    ]));
+
+    let mut ids = UInt32Builder::new();
+    let mut descriptions = StringBuilder::new();
+    let mut amounts = Decimal128Builder::new();
+    let mut currencies = StringBuilder::new();
+    let mut base_amounts = Decimal128Builder::new();
+    let mut datetimes = TimestampMicrosecondBuilder::new();
+    let mut category_ids = UInt32Builder::new();
+    let mut categories = StringBuilder::new();
+    let mut kinds = StringBuilder::new();
+    let mut accounts = StringBuilder::new();
+    let mut to_accounts = StringBuilder::new();
+    let mut external_refs = StringBuilder::new();
+    let mut tags = ListBuilder::new(StringBuilder::new());
+    for row in rows {
+        let transaction = row.transaction;
+        let category = transaction.category();
+        ids.append_value(transaction.id());
+        descriptions.append_value(transaction.description());
+        amounts.append_value(mantissa(transaction.amount()).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, scale_error(row)))?);
+        currencies.append_value(transaction.currency().to_string());
+        base_amounts.append_value(mantissa(row.base_amount).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, scale_error(row)))?);
+        datetimes.append_value(transaction.datetime().timestamp_micros());
+        category_ids.append_option(category.as_ref().map(|category| category.id()));
+        categories.append_option(category.as_ref().map(|category| category.name()));
+        kinds.append_value(transaction.kind().to_string());
+        accounts.append_option(transaction.account().map(|account| account.name().to_string()));
+        to_accounts.append_option(transaction.to_account().map(|account| account.name().to_string()));
+        external_refs.append_option(transaction.external_ref());
+        tags.append_value(transaction.tags().iter().map(Some));
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(ids.finish()),
+        Arc::new(descriptions.finish()),
+        Arc::new(amounts.finish().with_precision_and_scale(DECIMAL_PRECISION, DECIMAL_SCALE).map_err(io::Error::other)?),
+        Arc::new(currencies.finish()),
+        Arc::new(base_amounts.finish().with_precision_and_scale(DECIMAL_PRECISION, DECIMAL_SCALE).map_err(io::Error::other)?),
+        Arc::new(datetimes.finish().with_timezone("UTC")),
+        Arc::new(category_ids.finish()),
+        Arc::new(categories.finish()),
+        Arc::new(kinds.finish()),
+        Arc::new(accounts.finish()),
+        Arc::new(to_accounts.finish()),
+        Arc::new(external_refs.finish()),
+        Arc::new(tags.finish()),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns).map_err(io::Error::other)?;
+
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(out, schema, Some(properties)).map_err(io::Error::other)?;
+    writer.write(&batch).map_err(io::Error::other)?;
+    writer.close().map_err(io::Error::other)?;
+    Ok(())
+}
+
+/// Checks that the amounts of every row fit the scale of the decimal columns.
+/// It fails with the ID of the first transaction whose amount has more than 4 decimal places.
+pub fn check_scale(rows: &[ExportRow]) -> Result<(), StoreError> {
+    match rows.iter().find(|row| mantissa(row.transaction.amount()).is_none() || mantissa(row.base_amount).is_none()) {
+        Some(row) => Err(StoreError::InvalidInput(scale_error(row))),
+        None => Ok(()),
+    }
+}
+
+fn scale_error(row: &ExportRow) -> String {
+    format!(
+        "transaction {} has an amount with more than {} decimal places, which Parquet exports can't hold",
+        row.transaction.id(),
+        DECIMAL_SCALE
+    )
+}
+
+/// Returns the unscaled value of the amount at the decimal scale, e.g. `12.5` is `125000`,
+/// or `None` if the amount has more decimal places than the scale.
+fn mantissa(amount: Money) -> Option<i128> {
+    let mut decimal = amount.as_decimal().normalize();
+    if decimal.scale() > DECIMAL_SCALE as u32 {
+        return None;
+    }
+    decimal.rescale(DECIMAL_SCALE as u32);
+    Some(decimal.mantissa())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, Category, Transaction};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Decimal128Type, TimestampMicrosecondType, UInt32Type};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_write_parquet() {
+        let mut lunch = Transaction::new(1, "12.5".parse().unwrap(), "EUR".parse().unwrap(), "Lunch".to_string(), Some(Category::new(3, "Food".to_string())));
+        lunch.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-01T12:30:00Z").unwrap().to_utc());
+        lunch.set_external_ref(Some("ofx:123:1".to_string()));
+        lunch.set_account(Some(Account::new(1, "Card".to_string())));
+        lunch.set_tags(["work".to_string()].into());
+        let taxi = Transaction::new(2, "20".parse().unwrap(), "USD".parse().unwrap(), "Taxi".to_string(), None);
+        let rows = [
+            ExportRow { transaction: &lunch, base_amount: "13.625".parse().unwrap() },
+            ExportRow { transaction: &taxi, base_amount: "20".parse().unwrap() },
+        ];
+        let temp_file = "test_data_parquet_1.parquet";
+        write_parquet(File::create(temp_file).unwrap(), &rows).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(temp_file).unwrap()).unwrap().build().unwrap();
+        let batches: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        fs::remove_file(temp_file).expect("Unable to remove file");
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field_with_name("amount").unwrap().data_type(), &DataType::Decimal128(38, 4));
+        assert_eq!(batch.schema().field_with_name("base_amount").unwrap().data_type(), &DataType::Decimal128(38, 4));
+        let column = |name: &str| batch.column_by_name(name).unwrap();
+        assert_eq!(column("amount").as_primitive::<Decimal128Type>().value(0), 125000);
+        assert_eq!(column("base_amount").as_primitive::<Decimal128Type>().value(0), 136250);
+        assert_eq!(column("datetime").as_primitive::<TimestampMicrosecondType>().value(0), lunch.datetime().timestamp_micros());
+        assert_eq!(column("category_id").as_primitive::<UInt32Type>().value(0), 3);
+        assert!(column("category_id").is_null(1));
+        assert_eq!(column("category").as_string::<i32>().value(0), "Food");
+        assert_eq!(column("kind").as_string::<i32>().value(0), "expense");
+        assert_eq!(column("account").as_string::<i32>().value(0), "Card");
+        assert!(column("to_account").is_null(0));
+        assert_eq!(column("external_ref").as_string::<i32>().value(0), "ofx:123:1");
+        assert_eq!(column("tags").as_list::<i32>().value(0).as_string::<i32>().value(0), "work");
+        assert!(column("tags").as_list::<i32>().value(1).is_empty());
+    }
+
+    #[test]
+    fn test_write_parquet_rejects_amounts_beyond_the_scale() {
+        let transaction = Transaction::new(7, "0.00001".parse().unwrap(), "BTC".parse().unwrap(), "Coffee".to_string(), None);
+        let rows = [ExportRow { transaction: &transaction, base_amount: "0.5".parse().unwrap() }];
+        let error = write_parquet(vec![], &rows).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        match check_scale(&rows) {
+            Err(StoreError::InvalidInput(message)) => assert!(message.starts_with("transaction 7 "), "{}", message),
+            other => panic!("expected an invalid input error, got {:?}", other),
+        }
+        let transaction = Transaction::new(8, "0.0001".parse().unwrap(), "BTC".parse().unwrap(), "Coffee".to_string(), None);
+        assert!(check_scale(&[ExportRow { transaction: &transaction, base_amount: "0.5".parse().unwrap() }]).is_ok());
+    }
+}
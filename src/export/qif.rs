@@ -0,0 +1,90 @@
+use super::ExportRow;
+use crate::models::TransactionKind;
+use std::io::{self, Write};
+
+/// Writes the transactions as a QIF bank account (`!Type:Bank`).
+/// Expenses and transfers are written as payments, i.e. negative amounts in the base currency,
+/// since QIF has no currencies, and income as deposits. The description becomes the payee (`P`)
+/// as a whole; no memo (`M`) is written, since imports join payee and memo into the description
+/// (`payee - memo`) and a description already holds both. The category becomes an `L` line;
+/// transfers go to the `[Transfers]` account instead.
+/// Dates are written in the US format Quicken expects.
+pub fn write_qif(mut out: impl Write, rows: &[ExportRow]) -> io::Result<()> {
+    writeln!(out, "!Type:Bank")?;
+    for row in rows {
+        let transaction = row.transaction;
+        writeln!(out, "D{}", transaction.datetime().format("%m/%d/%Y"))?;
+        let amount = match transaction.kind() {
+            TransactionKind::Income => row.base_amount,
+            TransactionKind::Expense | TransactionKind::Transfer => -row.base_amount,
+        };
+        writeln!(out, "T{}", amount)?;
+        writeln!(out, "P{}", single_line(transaction.description()))?;
+        if transaction.kind() == TransactionKind::Transfer {
+            writeln!(out, "L[Transfers]")?;
+        } else if let Some(category) = transaction.category() {
+            writeln!(out, "L{}", single_line(category.name()))?;
+        }
+        writeln!(out, "^")?;
+    }
+    out.flush()
+}
+
+/// QIF fields can't span lines.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+    use crate::import::Parsed;
+    use crate::import::qif::read_qif;
+    use crate::models::{Category, Transaction};
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_write_qif() {
+        let mut transaction = Transaction::new(1, "12.5".parse().unwrap(), Currency::default(), "Lunch".to_string(), Some(Category::new(1, "Food".to_string())));
+        transaction.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-01T12:00:00Z").unwrap().to_utc());
+        let rows = [ExportRow { transaction: &transaction, base_amount: transaction.amount() }];
+        let mut out = vec![];
+        write_qif(&mut out, &rows).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "!Type:Bank\nD04/01/2025\nT-12.50\nPLunch\nLFood\n^\n");
+        transaction.set_kind(TransactionKind::Income);
+        let rows = [ExportRow { transaction: &transaction, base_amount: transaction.amount() }];
+        let mut out = vec![];
+        write_qif(&mut out, &rows).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("\nT12.50\n"));
+    }
+
+    #[test]
+    fn test_write_qif_round_trip() {
+        let mut shopping = Transaction::new(1, "45.2".parse().unwrap(), Currency::default(), "Supermarket - Weekly shopping".to_string(), Some(Category::new(1, "Food".to_string())));
+        shopping.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-01T00:00:00Z").unwrap().to_utc());
+        let mut salary = Transaction::new(2, "2000".parse().unwrap(), Currency::default(), "Salary".to_string(), None);
+        salary.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-02T00:00:00Z").unwrap().to_utc());
+        salary.set_kind(TransactionKind::Income);
+        let mut savings = Transaction::new(3, "100".parse().unwrap(), Currency::default(), "Savings".to_string(), None);
+        savings.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-04-03T00:00:00Z").unwrap().to_utc());
+        savings.set_kind(TransactionKind::Transfer);
+        let transactions = [shopping, salary, savings];
+        let rows: Vec<ExportRow> = transactions.iter().map(|transaction| ExportRow { transaction, base_amount: transaction.amount() }).collect();
+
+        let temp_file = "test_data_qif_export_1.qif";
+        write_qif(File::create(temp_file).unwrap(), &rows).unwrap();
+        let imported = read_qif(temp_file).unwrap();
+        fs::remove_file(temp_file).expect("Unable to remove file");
+
+        assert_eq!(imported.len(), transactions.len());
+        for (row, transaction) in imported.iter().zip(&transactions) {
+            let Parsed::Transaction(imported) = &row.parsed else { panic!("expected a transaction") };
+            assert_eq!(imported.description, transaction.description());
+            assert_eq!(imported.amount, transaction.amount());
+            assert_eq!(imported.datetime, transaction.datetime());
+            assert_eq!(imported.kind, transaction.kind());
+            assert_eq!(imported.category, transaction.category().map(|category| category.name().to_string()));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use super::ExportRow;
+use crate::currency::{Currency, ExchangeRates};
+use crate::error::StoreError;
+use crate::models::{Category, Money};
+use crate::summary::summary;
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Write};
+
+/// Writes the transactions as an Excel workbook with three sheets:
+/// - `Transactions`: one row per transaction, with its amount in its own currency and in the base currency,
+///   its kind, its account, the account a transfer goes to and its tags;
+/// - `Monthly summary`: the total expenses of each month in the base currency, as computed by `summary`;
+/// - `Categories`: the total of each category in the base currency and its share of all expenses.
+///
+/// Dates are date cells and amounts are number cells formatted with their currency,
+/// so the sheets can be sorted, filtered and summed in the spreadsheet.
+pub fn write_xlsx(mut out: impl Write, rows: &[ExportRow], rates: &ExchangeRates) -> io::Result<()> {
+    // The exchange rates were already checked when the rows were converted, so this can't fail.
+    let months = monthly_totals(rows, rates).map_err(io::Error::other)?;
+    let categories = category_totals(rows, rates).map_err(io::Error::other)?;
+
+    let base_currency = rates.base();
+    let mut workbook = Workbook::new();
+    write_transactions(workbook.add_worksheet(), rows, base_currency).map_err(io::Error::other)?;
+    write_months(workbook.add_worksheet(), &months, base_currency).map_err(io::Error::other)?;
+    write_categories(workbook.add_worksheet(), &categories, base_currency).map_err(io::Error::other)?;
+    let buffer = workbook.save_to_buffer().map_err(io::Error::other)?;
+    out.write_all(&buffer)?;
+    out.flush()
+}
+
+/// Returns the total of each month in the base currency, by the first day of the month.
+fn monthly_totals(rows: &[ExportRow], rates: &ExchangeRates) -> Result<Vec<(NaiveDate, Money)>, StoreError> {
+    let transactions: Vec<_> = rows.iter().map(|row| row.transaction).collect();
+    let months: BTreeSet<String> = transactions
+        .iter()
+        .map(|transaction| transaction.datetime().format("%Y-%m").to_string())
+        .collect();
+    months
+        .into_iter()
+        .map(|month| {
+            let first_day = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+                .map_err(|_| StoreError::InvalidMonth(month.clone()))?;
+            let (total, _by_day) = summary(transactions.clone(), Some(month), None, rates)?;
+            Ok((first_day, total))
+        })
+        .collect()
+}
+
+/// Returns the total of each category in the base currency, sorted by name.
+/// Transactions without a category are totalled under `None`, last.
+fn category_totals(rows: &[ExportRow], rates: &ExchangeRates) -> Result<Vec<(Option<String>, Money)>, StoreError> {
+    let transactions: Vec<_> = rows.iter().map(|row| row.transaction).collect();
+    let categories: BTreeMap<String, Category> = transactions
+        .iter()
+        .filter_map(|transaction| transaction.category())
+        .map(|category| (category.name().to_string(), category))
+        .collect();
+    let mut totals = vec![];
+    for (name, category) in categories {
+        let (total, _by_day) = summary(transactions.clone(), None, Some(&category), rates)?;
+        totals.push((Some(name), total));
+    }
+    let (overall, _by_day) = summary(transactions, None, None, rates)?;
+    let uncategorized = overall - totals.iter().map(|(_, total)| *total).sum();
+    if !uncategorized.is_zero() {
+        totals.push((None, uncategorized));
+    }
+    Ok(totals)
+}
+
+/// Number format showing an amount with its currency code, e.g. `1,234.50 EUR`.
+fn currency_format(currency: &Currency) -> Format {
+    Format::new().set_num_format(format!("#,##0.00 \"{}\"", currency))
+}
+
+fn number(amount: Money) -> f64 {
+    amount.as_decimal().to_f64().unwrap_or_default()
+}
+
+fn write_header(sheet: &mut Worksheet, headers: &[&str]) -> Result<(), XlsxError> {
+    let bold = Format::new().set_bold();
+    for (col, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *header, &bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+fn write_transactions(sheet: &mut Worksheet, rows: &[ExportRow], base_currency: &Currency) -> Result<(), XlsxError> {
+    sheet.set_name("Transactions")?;
+    write_header(sheet, &["ID", "Date", "Description", "Category", "Amount", "Currency", &format!("Amount ({})", base_currency), "Kind", "Account", "To account", "Tags"])?;
+    let date_format = Format::new().set_num_format("yyyy-mm-dd hh:mm");
+    let base_format = currency_format(base_currency);
+    let mut formats: HashMap<&Currency, Format> = HashMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        let transaction = row.transaction;
+        let r = i as u32 + 1;
+        let amount_format = formats
+            .entry(transaction.currency())
+            .or_insert_with(|| currency_format(transaction.currency()));
+        sheet.write_number(r, 0, transaction.id())?;
+        sheet.write_datetime_with_format(r, 1, transaction.datetime().naive_utc(), &date_format)?;
+        sheet.write_string(r, 2, transaction.description())?;
+        if let Some(category) = transaction.category() {
+            sheet.write_string(r, 3, category.name())?;
+        }
+        sheet.write_number_with_format(r, 4, number(transaction.amount()), amount_format)?;
+        sheet.write_string(r, 5, transaction.currency().to_string())?;
+        sheet.write_number_with_format(r, 6, number(row.base_amount), &base_format)?;
+        sheet.write_string(r, 7, transaction.kind().to_string())?;
+        if let Some(account) = transaction.account() {
+            sheet.write_string(r, 8, account.name())?;
+        }
+        if let Some(to_account) = transaction.to_account() {
+            sheet.write_string(r, 9, to_account.name())?;
+        }
+        if !transaction.tags().is_empty() {
+            sheet.write_string(r, 10, transaction.tags().iter().cloned().collect::<Vec<_>>().join(" "))?;
+        }
+    }
+    if !rows.is_empty() {
+        sheet.autofilter(0, 0, rows.len() as u32, 10)?;
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+fn write_months(sheet: &mut Worksheet, months: &[(NaiveDate, Money)], base_currency: &Currency) -> Result<(), XlsxError> {
+    sheet.set_name("Monthly summary")?;
+    write_header(sheet, &["Month", &format!("Total ({})", base_currency)])?;
+    let month_format = Format::new().set_num_format("yyyy-mm");
+    let total_format = currency_format(base_currency);
+    for (i, (month, total)) in months.iter().enumerate() {
+        let r = i as u32 + 1;
+        sheet.write_datetime_with_format(r, 0, month, &month_format)?;
+        sheet.write_number_with_format(r, 1, number(*total), &total_format)?;
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+fn write_categories(sheet: &mut Worksheet, categories: &[(Option<String>, Money)], base_currency: &Currency) -> Result<(), XlsxError> {
+    sheet.set_name("Categories")?;
+    write_header(sheet, &["Category", &format!("Total ({})", base_currency), "Share"])?;
+    let total_format = currency_format(base_currency);
+    let share_format = Format::new().set_num_format("0.0%");
+    let overall: Money = categories.iter().map(|(_, total)| *total).sum();
+    for (i, (name, total)) in categories.iter().enumerate() {
+        let r = i as u32 + 1;
+        sheet.write_string(r, 0, name.as_deref().unwrap_or("None"))?;
+        sheet.write_number_with_format(r, 1, number(*total), &total_format)?;
+        if !overall.is_zero() {
+            sheet.write_number_with_format(r, 2, number(*total) / number(overall), &share_format)?;
+        }
+    }
+    sheet.autofit();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::ExchangeRate;
+    use crate::models::Transaction;
+
+    fn transactions() -> Vec<Transaction> {
+        let food = Category::new(1, "Food".to_string());
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().to_utc();
+        let mut lunch = Transaction::new(1, "10".parse().unwrap(), "EUR".parse().unwrap(), "Lunch".to_string(), Some(food.clone()));
+        lunch.set_datetime(at("2025-03-31T12:00:00Z"));
+        let mut dinner = Transaction::new(2, "30".parse().unwrap(), "USD".parse().unwrap(), "Dinner".to_string(), Some(food));
+        dinner.set_datetime(at("2025-04-01T19:00:00Z"));
+        let mut taxi = Transaction::new(3, "20".parse().unwrap(), "USD".parse().unwrap(), "Taxi".to_string(), None);
+        taxi.set_datetime(at("2025-04-02T08:00:00Z"));
+        vec![lunch, dinner, taxi]
+    }
+
+    fn rates() -> ExchangeRates {
+        let mut rates = ExchangeRates::default();
+        rates.set_base("USD".parse().unwrap());
+        rates.add(ExchangeRate::new("2025-01-01".parse().unwrap(), "EUR".parse().unwrap(), "USD".parse().unwrap(), "1.1".parse().unwrap()).unwrap());
+        rates
+    }
+
+    #[test]
+    fn test_totals() {
+        let transactions = transactions();
+        let rates = rates();
+        let rows = super::super::export_rows(transactions.iter().collect(), &rates).unwrap();
+        let months = monthly_totals(&rows, &rates).unwrap();
+        assert_eq!(
+            months,
+            vec![
+                ("2025-03-01".parse().unwrap(), "11.0".parse().unwrap()),
+                ("2025-04-01".parse().unwrap(), "50".parse().unwrap()),
+            ]
+        );
+        let categories = category_totals(&rows, &rates).unwrap();
+        assert_eq!(categories, vec![(Some("Food".to_string()), "41.0".parse().unwrap()), (None, "20".parse().unwrap())]);
+    }
+
+    #[test]
+    fn test_write_xlsx() {
+        let transactions = transactions();
+        let rates = rates();
+        let rows = super::super::export_rows(transactions.iter().collect(), &rates).unwrap();
+        let mut out = vec![];
+        write_xlsx(&mut out, &rows, &rates).unwrap();
+        // An xlsx file is a zip archive.
+        assert!(out.starts_with(b"PK"));
+    }
+}
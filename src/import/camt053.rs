@@ -0,0 +1,211 @@
+use super::{ImportedTransaction, Parsed, ParsedRow};
+use crate::currency::Currency;
+use crate::error::StoreError;
+use crate::models::{Money, TransactionKind};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use roxmltree::{Document, Node};
+use std::fs;
+
+/// Reads the entries (`Ntry`) of all statements in an ISO 20022 camt.053 bank-to-customer statement.
+/// Each entry is dated with its value date (the booking date if there is none) and described by
+/// its booking text, counterparty and remittance information. The bank's reference
+/// (`AcctSvcrRef`, or `NtryRef`) qualified with the account becomes the external reference,
+/// so entries that were already imported are skipped.
+/// Debits are imported as expenses and credits as income; entries that are not booked yet are skipped.
+pub fn read_camt053(path: &str) -> Result<Vec<ParsedRow>, StoreError> {
+    let content = fs::read_to_string(path).map_err(|e| StoreError::io(path, e))?;
+    let invalid = |message: String| StoreError::InvalidInput(format!("`{}`: {}", path, message));
+    let document = Document::parse(&content).map_err(|e| invalid(e.to_string()))?;
+    let statements: Vec<Node> = document
+        .descendants()
+        .filter(|node| node.has_tag_name("Stmt"))
+        .collect();
+    if !document.root_element().has_tag_name("Document") || statements.is_empty() {
+        return Err(invalid("not a camt.053 statement".to_string()));
+    }
+
+    let mut rows = vec![];
+    for statement in statements {
+        let account = child(statement, &["Acct", "Id", "IBAN"])
+            .or_else(|| child(statement, &["Acct", "Id", "Othr", "Id"]))
+            .and_then(|node| node.text())
+            .map(str::trim);
+        let account_currency = child(statement, &["Acct", "Ccy"]).and_then(|node| node.text());
+        for entry in statement.children().filter(|node| node.has_tag_name("Ntry")) {
+            let line = document.text_pos_at(entry.range().start).row as usize;
+            let parsed = parse_entry(entry, account, account_currency).unwrap_or_else(Parsed::Rejected);
+            rows.push(ParsedRow { line, parsed });
+        }
+    }
+    Ok(rows)
+}
+
+fn parse_entry(entry: Node, account: Option<&str>, account_currency: Option<&str>) -> Result<Parsed, String> {
+    let text = |path: &[&str]| child(entry, path).and_then(|node| node.text()).map(str::trim).filter(|text| !text.is_empty());
+
+    // The status is a plain code up to version 7 and a `Cd` element since version 8.
+    let status = text(&["Sts"]).or_else(|| text(&["Sts", "Cd"]));
+    if let Some(status) = status.filter(|status| *status != "BOOK") {
+        return Ok(Parsed::Skipped(format!("entry has status {}, not booked", status)));
+    }
+    let amount_node = child(entry, &["Amt"]).ok_or("missing Amt")?;
+    let amount_text = amount_node.text().unwrap_or("").trim();
+    let amount: Money = amount_text.parse().map_err(|_| format!("invalid amount `{}`", amount_text))?;
+    let kind = match text(&["CdtDbtInd"]) {
+        Some("DBIT") => TransactionKind::Expense,
+        Some("CRDT") => TransactionKind::Income,
+        other => return Err(format!("invalid credit/debit indicator `{}`", other.unwrap_or(""))),
+    };
+    if amount.is_zero() {
+        return Ok(Parsed::Skipped("zero amount".to_string()));
+    }
+    let currency = amount_node
+        .attribute("Ccy")
+        .or(account_currency)
+        .map(str::parse::<Currency>)
+        .transpose()?;
+
+    let date = text(&["ValDt", "Dt"])
+        .or_else(|| text(&["ValDt", "DtTm"]))
+        .or_else(|| text(&["BookgDt", "Dt"]))
+        .or_else(|| text(&["BookgDt", "DtTm"]))
+        .ok_or("missing ValDt and BookgDt")?;
+    let datetime = parse_camt_date(date)?;
+
+    let details = child(entry, &["NtryDtls", "TxDtls"]);
+    let detail = |path: &[&str]| {
+        details.and_then(|node| child(node, path)).and_then(|node| node.text()).map(str::trim).filter(|text| !text.is_empty())
+    };
+    let booking_text = text(&["AddtlNtryInf"]).or_else(|| detail(&["AddtlTxInf"]));
+    let counterparty = detail(&["RltdPties", "Cdtr", "Nm"]).or_else(|| detail(&["RltdPties", "Cdtr", "Pty", "Nm"]));
+    let remittance: Vec<&str> = details
+        .and_then(|node| child(node, &["RmtInf"]))
+        .map(|node| {
+            node.children()
+                .filter(|node| node.has_tag_name("Ustrd"))
+                .filter_map(|node| node.text())
+                .map(str::trim)
+                .collect()
+        })
+        .unwrap_or_default();
+    let remittance = remittance.join(" ");
+    let mut parts: Vec<&str> = vec![];
+    for part in [booking_text, counterparty, Some(remittance.as_str())].into_iter().flatten() {
+        if !part.is_empty() && !parts.contains(&part) {
+            parts.push(part);
+        }
+    }
+    if parts.is_empty() {
+        return Err("missing booking text and remittance information".to_string());
+    }
+
+    let reference = text(&["AcctSvcrRef"])
+        .or_else(|| text(&["NtryRef"]))
+        .or_else(|| detail(&["Refs", "AcctSvcrRef"]));
+    let external_ref = reference.map(|reference| match account {
+        Some(account) => format!("camt053:{}:{}", account, reference),
+        None => format!("camt053:{}", reference),
+    });
+
+    Ok(Parsed::Transaction(ImportedTransaction {
+        description: parts.join(" - "),
+        amount,
+        currency,
+        datetime,
+        category: None,
+        external_ref,
+        kind,
+    }))
+}
+
+/// Follows a path of child element names, ignoring namespaces.
+fn child<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter()
+        .try_fold(node, |node, name| node.children().find(|child| child.has_tag_name(*name)))
+}
+
+/// Parses an ISO date (`2025-04-01`), placed at midnight UTC, or an ISO datetime.
+fn parse_camt_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.to_utc());
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Ok(datetime.and_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(Default::default()).and_utc())
+        .map_err(|_| format!("invalid date `{}`", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id><Ccy>EUR</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="EUR">42.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2025-04-02</Dt></BookgDt>
+        <ValDt><Dt>2025-04-01</Dt></ValDt>
+        <AcctSvcrRef>REF-001</AcctSvcrRef>
+        <NtryDtls><TxDtls>
+          <RltdPties><Cdtr><Nm>Stadtwerke</Nm></Cdtr></RltdPties>
+          <RmtInf><Ustrd>Power April</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+        <AddtlNtryInf>SEPA DIRECT DEBIT</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">1000.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <ValDt><Dt>2025-04-03</Dt></ValDt>
+        <AcctSvcrRef>REF-002</AcctSvcrRef>
+        <AddtlNtryInf>SALARY</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">5.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>PDNG</Sts>
+        <ValDt><Dt>2025-04-04</Dt></ValDt>
+        <AddtlNtryInf>CARD</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+
+    #[test]
+    fn test_read_camt053() {
+        let temp_file = "test_data_camt053_1.xml";
+        fs::write(temp_file, STATEMENT).expect("Unable to write file");
+        let rows = read_camt053(temp_file);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        let rows = rows.unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].line, 7);
+        let Parsed::Transaction(power) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!(power.description, "SEPA DIRECT DEBIT - Stadtwerke - Power April");
+        assert_eq!(power.amount, "42.50".parse().unwrap());
+        assert_eq!(power.currency, Some("EUR".parse().unwrap()));
+        assert_eq!(power.datetime.to_rfc3339(), "2025-04-01T00:00:00+00:00");
+        assert_eq!(power.external_ref.as_deref(), Some("camt053:DE89370400440532013000:REF-001"));
+        let Parsed::Transaction(credit) = &rows[1].parsed else { panic!("expected a transaction") };
+        assert_eq!(credit.kind, TransactionKind::Income);
+        assert_eq!(power.kind, TransactionKind::Expense);
+        assert_eq!(rows[2].parsed, Parsed::Skipped("entry has status PDNG, not booked".to_string()));
+    }
+
+    #[test]
+    fn test_other_xml_is_an_error() {
+        let temp_file = "test_data_camt053_2.xml";
+        fs::write(temp_file, "<OFX></OFX>").expect("Unable to write file");
+        let result = read_camt053(temp_file);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        assert!(matches!(result, Err(StoreError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,353 @@
+use super::{ImportedTransaction, Parsed, ParsedRow};
+use crate::currency::csv_error;
+use crate::error::StoreError;
+use crate::models::{Money, TransactionKind};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::str::FromStr;
+
+/// Reference to a CSV column, either by its header name or by its zero-based position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+/// Column references given on the command line are positions if they are numeric,
+/// header names otherwise.
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim().parse() {
+            Ok(index) => Column::Index(index),
+            Err(_) => Column::Name(s.trim().to_string()),
+        })
+    }
+}
+
+/// How the amounts of a statement are signed.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignConvention {
+    /// Expenses are positive, as in the files written by `export`. Negative rows are income.
+    #[default]
+    ExpensesPositive,
+    /// Expenses are negative, as in most bank statements. Positive rows (credits) are income.
+    ExpensesNegative,
+}
+
+/// Mapping of the columns of a CSV file onto transactions.
+/// Profiles are kept as JSON files, so a mapping only needs to be written once per bank.
+/// The default profile reads the files written by `export`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CsvProfile {
+    pub delimiter: char,
+    /// Whether the first row holds column names rather than a transaction.
+    pub has_headers: bool,
+    pub date_column: Column,
+    /// `chrono` format of the date column, e.g. `%d.%m.%Y`.
+    /// Without a format, RFC 3339, `YYYY-MM-DD HH:MM[:SS]` and `YYYY-MM-DD` are accepted.
+    pub date_format: Option<String>,
+    pub amount_column: Column,
+    pub sign: SignConvention,
+    /// Whether amounts use a decimal comma, e.g. `1.234,56`.
+    pub decimal_comma: bool,
+    pub description_column: Column,
+    pub category_column: Option<Column>,
+    /// Column with the currency code. Without it, amounts are in the base currency.
+    pub currency_column: Option<Column>,
+    /// Column with the kind of the transaction, `expense`, `income` or `transfer`, as written by `export`.
+    /// It overrides the kind implied by the sign of the amount. A column referenced by name
+    /// may be missing, so files exported before kinds existed still import.
+    pub kind_column: Option<Column>,
+}
+
+impl Default for CsvProfile {
+    fn default() -> Self {
+        CsvProfile {
+            delimiter: ',',
+            has_headers: true,
+            date_column: Column::Name("datetime".to_string()),
+            date_format: None,
+            amount_column: Column::Name("amount".to_string()),
+            sign: SignConvention::ExpensesPositive,
+            decimal_comma: false,
+            description_column: Column::Name("description".to_string()),
+            category_column: Some(Column::Name("category".to_string())),
+            currency_column: Some(Column::Name("currency".to_string())),
+            kind_column: Some(Column::Name("kind".to_string())),
+        }
+    }
+}
+
+impl CsvProfile {
+    /// Reads a profile from a JSON file. Missing fields take their default values.
+    pub fn load(path: &str) -> Result<Self, StoreError> {
+        let json = fs::read_to_string(path).map_err(|e| StoreError::io(path, e))?;
+        serde_json::from_str(&json).map_err(|e| StoreError::InvalidInput(format!("`{}`: {}", path, e)))
+    }
+}
+
+/// Resolved positions of the mapped columns.
+struct Positions {
+    date: usize,
+    amount: usize,
+    description: usize,
+    category: Option<usize>,
+    currency: Option<usize>,
+    kind: Option<usize>,
+}
+
+/// Reads a CSV statement with the given profile.
+/// Rows that can't be parsed are rejected with the reason; only problems with the file
+/// as a whole, like a mapped column missing from the header, are returned as errors.
+pub fn read_csv(path: &str, profile: &CsvProfile) -> Result<Vec<ParsedRow>, StoreError> {
+    if !profile.delimiter.is_ascii() {
+        return Err(StoreError::InvalidInput(format!("delimiter `{}` must be an ASCII character", profile.delimiter)));
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(profile.delimiter as u8)
+        .has_headers(profile.has_headers)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| csv_error(path, e))?;
+    let headers = if profile.has_headers {
+        Some(reader.headers().map_err(|e| csv_error(path, e))?.clone())
+    } else {
+        None
+    };
+    let position = |column: &Column| match (column, &headers) {
+        (Column::Index(index), _) => Ok(*index),
+        (Column::Name(name), Some(headers)) => headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| StoreError::InvalidInput(format!("`{}`: no `{}` column", path, name))),
+        (Column::Name(name), None) => Err(StoreError::InvalidInput(format!(
+            "column `{}` is referenced by name, but the profile says the file has no header row",
+            name
+        ))),
+    };
+    let has_column = |name: &str| headers.as_ref().is_some_and(|headers| headers.iter().any(|h| h.trim().eq_ignore_ascii_case(name)));
+    let positions = Positions {
+        date: position(&profile.date_column)?,
+        amount: position(&profile.amount_column)?,
+        description: position(&profile.description_column)?,
+        category: profile.category_column.as_ref().map(position).transpose()?,
+        currency: profile.currency_column.as_ref().map(position).transpose()?,
+        kind: match &profile.kind_column {
+            Some(Column::Name(name)) if !has_column(name) => None,
+            column => column.as_ref().map(position).transpose()?,
+        },
+    };
+
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| csv_error(path, e))?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let parsed = parse_record(&record, &positions, profile)
+            .unwrap_or_else(Parsed::Rejected);
+        rows.push(ParsedRow { line, parsed });
+    }
+    Ok(rows)
+}
+
+fn parse_record(record: &csv::StringRecord, positions: &Positions, profile: &CsvProfile) -> Result<Parsed, String> {
+    let field = |position: usize| {
+        record.get(position).map(str::trim).ok_or_else(|| format!("missing column {}", position))
+    };
+    let optional = |position: Option<usize>| position.and_then(|p| record.get(p)).map(str::trim).filter(|v| !v.is_empty());
+
+    let datetime = parse_datetime(field(positions.date)?, profile.date_format.as_deref())?;
+    let amount = parse_amount(field(positions.amount)?, profile.decimal_comma)?;
+    let amount = match profile.sign {
+        SignConvention::ExpensesPositive => amount,
+        SignConvention::ExpensesNegative => -amount,
+    };
+    if amount.is_zero() {
+        return Ok(Parsed::Skipped("zero amount".to_string()));
+    }
+    let kind = match optional(positions.kind) {
+        Some(kind) => parse_kind(kind)?,
+        None if amount.is_negative() => TransactionKind::Income,
+        None => TransactionKind::Expense,
+    };
+    let description = field(positions.description)?;
+    if description.is_empty() {
+        return Err("empty description".to_string());
+    }
+    let currency = optional(positions.currency).map(str::parse).transpose()?;
+    // Older versions of `export` wrote `None` for transactions without a category.
+    let category = optional(positions.category).filter(|name| *name != "None").map(str::to_string);
+
+    Ok(Parsed::Transaction(ImportedTransaction {
+        description: description.to_string(),
+        amount: amount.abs(),
+        currency,
+        datetime,
+        category,
+        external_ref: None,
+        kind,
+    }))
+}
+
+fn parse_kind(value: &str) -> Result<TransactionKind, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "expense" => Ok(TransactionKind::Expense),
+        "income" => Ok(TransactionKind::Income),
+        "transfer" => Ok(TransactionKind::Transfer),
+        _ => Err(format!("invalid kind `{}`, expected expense, income or transfer", value)),
+    }
+}
+
+/// Parses a date, or a date and time, in UTC.
+/// Dates without a time are placed at midnight, so re-importing a statement
+/// yields the same transactions.
+fn parse_datetime(value: &str, format: Option<&str>) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("invalid date `{}`", value);
+    if let Some(format) = format {
+        return NaiveDateTime::parse_from_str(value, format)
+            .or_else(|_| NaiveDate::parse_from_str(value, format).map(|date| date.and_time(Default::default())))
+            .map(|datetime| datetime.and_utc())
+            .map_err(|_| format!("invalid date `{}`, expected format `{}`", value, format));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    // Older versions of `export` wrote datetimes like `2025-04-01 10:00:00.123 UTC`.
+    let value_utc = value.strip_suffix(" UTC").unwrap_or(value);
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value_utc, format) {
+            return Ok(datetime.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(Default::default()).and_utc())
+        .map_err(|_| invalid())
+}
+
+/// Parses an amount, ignoring thousands separators and spaces.
+/// Amounts in parentheses, as used in accounting, are negative.
+fn parse_amount(value: &str, decimal_comma: bool) -> Result<Money, String> {
+    let invalid = || format!("invalid amount `{}`", value);
+    let (negative, digits) = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(inner) => (true, inner),
+        None => (false, value),
+    };
+    let mut digits: String = digits.chars().filter(|c| !c.is_whitespace() && *c != '\'').collect();
+    if decimal_comma {
+        digits = digits.replace('.', "").replace(',', ".");
+    } else {
+        digits = digits.replace(',', "");
+    }
+    let amount: Money = digits.parse().map_err(|_| invalid())?;
+    Ok(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(row: &ParsedRow) -> &ImportedTransaction {
+        match &row.parsed {
+            Parsed::Transaction(transaction) => transaction,
+            other => panic!("expected a transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_bank_statement() {
+        let temp_file = "test_data_import_csv_1.csv";
+        fs::write(
+            temp_file,
+            "Booking date;Text;Amount;Category\n\
+             01.04.2025;Supermarket;-1.234,50;Food\n\
+             02.04.2025;Salary;2.000,00;\n\
+             03.04.2025;Cinema;abc;Fun\n\
+             ;;;\n\
+             04.04.2025;Bakery;(3,20);\n",
+        )
+        .expect("Unable to write file");
+        let profile = CsvProfile {
+            delimiter: ';',
+            has_headers: true,
+            date_column: Column::Name("booking date".to_string()),
+            date_format: Some("%d.%m.%Y".to_string()),
+            amount_column: Column::Name("Amount".to_string()),
+            sign: SignConvention::ExpensesNegative,
+            decimal_comma: true,
+            description_column: Column::Name("Text".to_string()),
+            category_column: Some(Column::Name("Category".to_string())),
+            currency_column: None,
+            kind_column: None,
+        };
+        let rows = read_csv(temp_file, &profile).unwrap();
+        assert_eq!(rows.len(), 4);
+        let first = transaction(&rows[0]);
+        assert_eq!(first.amount, "1234.50".parse().unwrap());
+        assert_eq!(first.datetime.to_rfc3339(), "2025-04-01T00:00:00+00:00");
+        assert_eq!(first.category.as_deref(), Some("Food"));
+        let salary = transaction(&rows[1]);
+        assert_eq!(salary.amount, "2000".parse().unwrap());
+        assert_eq!(salary.kind, TransactionKind::Income);
+        assert_eq!(rows[2], ParsedRow { line: 4, parsed: Parsed::Rejected("invalid amount `abc`".to_string()) });
+        assert_eq!(rows[3].line, 6);
+        assert_eq!(transaction(&rows[3]).amount, "3.20".parse().unwrap());
+        fs::remove_file(temp_file).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_read_exported_file_with_default_profile() {
+        let temp_file = "test_data_import_csv_2.csv";
+        fs::write(
+            temp_file,
+            "id,description,amount,currency,base_amount,datetime,category_id,category\n\
+             1,Lunch,12.5,EUR,13.6,2025-04-01T12:30:00.500Z,3,Food\n\
+             2,Taxi,20,USD,20,2025-04-02T08:00:00Z,,\n",
+        )
+        .expect("Unable to write file");
+        let rows = read_csv(temp_file, &CsvProfile::default()).unwrap();
+        let lunch = transaction(&rows[0]);
+        assert_eq!(lunch.kind, TransactionKind::Expense);
+        assert_eq!(lunch.currency, Some("EUR".parse().unwrap()));
+        assert_eq!(lunch.datetime.to_rfc3339(), "2025-04-01T12:30:00.500+00:00");
+        assert_eq!(transaction(&rows[1]).category, None);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_read_kind_column() {
+        let temp_file = "test_data_import_csv_4.csv";
+        fs::write(
+            temp_file,
+            "description,amount,datetime,kind\n\
+             Salary,2000,2025-04-01,income\n\
+             Savings,500,2025-04-02,Transfer\n\
+             Lunch,12.5,2025-04-03,\n\
+             Taxi,20,2025-04-04,gift\n",
+        )
+        .expect("Unable to write file");
+        let profile = CsvProfile { currency_column: None, category_column: None, ..CsvProfile::default() };
+        let rows = read_csv(temp_file, &profile).unwrap();
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        assert_eq!(transaction(&rows[0]).kind, TransactionKind::Income);
+        assert_eq!(transaction(&rows[1]).kind, TransactionKind::Transfer);
+        assert_eq!(transaction(&rows[2]).kind, TransactionKind::Expense);
+        assert_eq!(rows[3].parsed, Parsed::Rejected("invalid kind `gift`, expected expense, income or transfer".to_string()));
+    }
+
+    #[test]
+    fn test_missing_column_is_an_error() {
+        let temp_file = "test_data_import_csv_3.csv";
+        fs::write(temp_file, "date,text\n2025-04-01,Lunch\n").expect("Unable to write file");
+        let profile = CsvProfile { date_column: Column::Name("booking date".to_string()), ..CsvProfile::default() };
+        assert!(matches!(read_csv(temp_file, &profile), Err(StoreError::InvalidInput(_))));
+        fs::remove_file(temp_file).expect("Unable to remove file");
+    }
+}
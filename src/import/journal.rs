@@ -0,0 +1,419 @@
+use super::{ImportedTransaction, Parsed, ParsedRow};
+use crate::currency::Currency;
+use crate::error::StoreError;
+use crate::models::{Money, TransactionKind};
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+use std::fs;
+
+/// Directives that only declare accounts, commodities, payees or options. They record
+/// nothing that could be imported, so they are ignored rather than reported.
+const DECLARATIONS: &[&str] = &[
+    "account", "alias", "apply", "commodity", "end", "option", "payee", "plugin", "tag", "year", "Y",
+    "open", "close", "pushtag", "poptag", "pushmeta", "popmeta", "decimal-mark",
+];
+
+/// Beancount directives that follow a date like transactions do.
+const DATED_DIRECTIVES: &[&str] = &[
+    "open", "close", "commodity", "balance", "pad", "note", "document", "event", "query", "price", "custom",
+];
+
+/// A journal entry being read: the line of its header, its date and description, and its postings.
+struct Entry {
+    line: usize,
+    date: NaiveDate,
+    description: String,
+    postings: Vec<Posting>,
+    error: Option<String>,
+}
+
+/// A posting of an entry. The amount is `None` if it was left out to be inferred.
+struct Posting {
+    account: String,
+    amount: Option<(Money, String)>,
+}
+
+/// Reads the entries of a ledger, hledger or beancount journal.
+/// Every posting to an account below `expense_prefix` (e.g. `Expenses:Food:Groceries`) becomes
+/// a transaction, described by the payee and note (or narration) of its entry, with the rest of the
+/// account as its category (`Food:Groceries`); `Uncategorized` means no category.
+/// Entries without expense postings and negative expense postings (refunds) are skipped.
+/// Declarations of accounts, commodities and options are ignored; other directives, like prices,
+/// balance assertions or periodic transactions, are reported as skipped.
+pub fn read_journal(path: &str, expense_prefix: &str) -> Result<Vec<ParsedRow>, StoreError> {
+    let content = fs::read_to_string(path).map_err(|e| StoreError::io(path, e))?;
+    let expense_prefix = expense_prefix.trim_end_matches(':');
+
+    let mut rows = vec![];
+    let mut entry: Option<Entry> = None;
+    let mut in_comment = false;
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let trimmed = line.trim();
+        if in_comment {
+            in_comment = !trimmed.starts_with("end comment") && !trimmed.starts_with("end test");
+            continue;
+        }
+        if line.starts_with([' ', '\t']) && !trimmed.is_empty() {
+            // Postings and comments of the current entry; lines of directives that aren't entries are ignored.
+            if let Some(entry) = entry.as_mut().filter(|entry| entry.error.is_none()) {
+                match parse_posting(trimmed) {
+                    Ok(Some(posting)) => entry.postings.push(posting),
+                    Ok(None) => {}
+                    Err(reason) => entry.error = Some(reason),
+                }
+            }
+            continue;
+        }
+
+        if let Some(entry) = entry.take() {
+            rows.extend(entry_rows(entry, expense_prefix));
+        }
+        if trimmed.is_empty() || trimmed.starts_with([';', '#', '%', '|', '*']) {
+            continue;
+        }
+        if trimmed == "comment" || trimmed == "test" {
+            in_comment = true;
+            continue;
+        }
+        let (first, rest) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+        let rest = rest.trim();
+        let directive = match parse_date(first) {
+            Some(date) => {
+                let keyword = rest.split_whitespace().next().unwrap_or("");
+                if DATED_DIRECTIVES.contains(&keyword) {
+                    keyword
+                } else {
+                    entry = Some(Entry { line: line_number, date, description: description(rest), postings: vec![], error: None });
+                    continue;
+                }
+            }
+            None => first,
+        };
+        if !DECLARATIONS.contains(&directive) {
+            rows.push(ParsedRow { line: line_number, parsed: Parsed::Skipped(format!("unsupported directive `{}`", directive)) });
+        }
+    }
+    if let Some(entry) = entry.take() {
+        rows.extend(entry_rows(entry, expense_prefix));
+    }
+    Ok(rows)
+}
+
+/// Parses the date of an entry: `2025-04-01`, `2025/04/01` or `2025.04.01`,
+/// possibly followed by a secondary date (`2025-04-01=2025-04-03`), which is ignored.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let primary = value.split('=').next().unwrap_or(value).replace(['/', '.'], "-");
+    NaiveDate::parse_from_str(&primary, "%Y-%m-%d").ok()
+}
+
+/// Builds the description from the rest of an entry header. The status mark, code and
+/// comment are dropped; hledger's payee and note (`payee | note`) and beancount's
+/// payee and narration (`"payee" "narration"`) are joined with ` - `.
+fn description(header: &str) -> String {
+    let header = header.strip_prefix("txn").unwrap_or(header).trim_start();
+    let header = header.trim_start_matches(['*', '!']).trim_start();
+    let parts = if header.starts_with('"') {
+        quoted_strings(header)
+    } else {
+        let header = match header.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+            Some((_code, rest)) => rest,
+            None => header,
+        };
+        let header = header.split(" ;").next().unwrap_or(header).split("\t;").next().unwrap_or(header);
+        header.split('|').map(str::to_string).collect()
+    };
+    parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" - ")
+}
+
+/// Returns the double-quoted strings in a beancount header, with `\"` and `\\` unescaped.
+fn quoted_strings(value: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => string.extend(chars.next()),
+                '"' => break,
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// Parses a posting, e.g. `Expenses:Food  12.50 EUR`. Comments and beancount metadata
+/// (`id: "1"`) are not postings and give `None`.
+fn parse_posting(line: &str) -> Result<Option<Posting>, String> {
+    let line = line.split(';').next().unwrap_or(line).trim();
+    let line = line.trim_start_matches(['*', '!']).trim_start();
+    let first = line.split_whitespace().next().unwrap_or("");
+    let is_metadata_key = |key: &str| key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if first.is_empty() || first.strip_suffix(':').is_some_and(is_metadata_key) {
+        return Ok(None);
+    }
+    // Ledger and hledger end account names with two spaces or a tab, as they may contain single spaces.
+    // Beancount account names have no spaces, so a single space may separate the amount.
+    let (account, amount) = match line.find("  ").into_iter().chain(line.find('\t')).min() {
+        Some(end) => (&line[..end], Some(line[end..].trim())),
+        None => match line.split_once(' ') {
+            Some((account, amount)) if parse_amount(amount).is_ok() => (account, Some(amount)),
+            _ => (line, None),
+        },
+    };
+    // Virtual postings are written in parentheses or brackets.
+    let account = account.trim_matches(['(', ')', '[', ']']).to_string();
+    let amount = amount.filter(|amount| !amount.is_empty()).map(parse_amount).transpose()?;
+    Ok(Some(Posting { account, amount }))
+}
+
+/// Parses an amount with its commodity, e.g. `12.50 EUR`, `EUR -12,50`, `$1,234.50` or `-$5`.
+/// Amounts without a commodity, like `12.50`, have an empty commodity.
+/// Costs, prices (`@ 1.10 USD`, `{1.10 USD}`) and balance assertions (`= 100 EUR`) are ignored.
+fn parse_amount(value: &str) -> Result<(Money, String), String> {
+    let invalid = || format!("invalid amount `{}`", value);
+    let amount = value.split(['@', '{', '=']).next().unwrap_or(value).trim();
+    let start = amount.find(|c: char| c.is_ascii_digit()).ok_or_else(invalid)?;
+    let end = amount[start..]
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .map_or(amount.len(), |end| start + end);
+    let outside = format!("{} {}", &amount[..start], &amount[end..]);
+    let negative = outside.contains('-');
+    let commodity = outside.replace(['-', '+', '"'], "").trim().to_string();
+    if commodity.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    let money: Money = normalize_number(&amount[start..end]).parse().map_err(|_| invalid())?;
+    let commodity = match commodity.as_str() {
+        "$" => "USD".to_string(),
+        "€" => "EUR".to_string(),
+        "£" => "GBP".to_string(),
+        "¥" => "JPY".to_string(),
+        _ => commodity,
+    };
+    Ok((if negative { -money } else { money }, commodity))
+}
+
+/// Removes digit group separators from a number. The last separator is the decimal mark
+/// if both `.` and `,` are used, or if a `,` is not followed by exactly three digits.
+fn normalize_number(number: &str) -> String {
+    let decimal_comma = match (number.rfind(','), number.rfind('.')) {
+        (Some(comma), Some(dot)) => comma > dot,
+        (Some(comma), None) => number.len() - comma - 1 != 3,
+        (None, _) => false,
+    };
+    if decimal_comma {
+        number.replace('.', "").replace(',', ".")
+    } else {
+        number.replace(',', "")
+    }
+}
+
+/// Turns the expense postings of an entry into rows. A single posting without an amount
+/// balances the others, as in ledger, hledger and beancount.
+fn entry_rows(entry: Entry, expense_prefix: &str) -> Vec<ParsedRow> {
+    let line = entry.line;
+    if let Some(reason) = entry.error {
+        return vec![ParsedRow { line, parsed: Parsed::Rejected(reason) }];
+    }
+    // Only needed if an expense posting has no amount; otherwise the other postings may use several commodities.
+    let inferred = infer_amount(&entry.postings);
+    let is_expense = |account: &str| {
+        account == expense_prefix || account.strip_prefix(expense_prefix).is_some_and(|rest| rest.starts_with(':'))
+    };
+    let expenses: Vec<&Posting> = entry.postings.iter().filter(|posting| is_expense(&posting.account)).collect();
+    if expenses.is_empty() {
+        return vec![ParsedRow { line, parsed: Parsed::Skipped("no expense postings".to_string()) }];
+    }
+    expenses
+        .into_iter()
+        .map(|posting| {
+            let parsed = match posting.amount.clone().map(Ok).unwrap_or_else(|| inferred.clone()) {
+                Ok(amount) => parse_expense(&entry.description, entry.date, posting, amount, expense_prefix),
+                Err(reason) => Err(reason),
+            };
+            let parsed = parsed.unwrap_or_else(Parsed::Rejected);
+            ParsedRow { line, parsed }
+        })
+        .collect()
+}
+
+/// Returns the amount of the posting without one, which balances the others.
+/// Amounts without a commodity are taken to be in the commodity of the others.
+/// Fails if several postings have no amount, or the others are in several commodities.
+fn infer_amount(postings: &[Posting]) -> Result<(Money, String), String> {
+    let elided = postings.iter().filter(|posting| posting.amount.is_none()).count();
+    if elided > 1 {
+        return Err("more than one posting without an amount".to_string());
+    }
+    let amounts: Vec<&(Money, String)> = postings.iter().filter_map(|posting| posting.amount.as_ref()).collect();
+    if amounts.is_empty() {
+        return Err("no posting with an amount".to_string());
+    }
+    let commodities: BTreeSet<&str> = amounts.iter().map(|(_, commodity)| commodity.as_str()).filter(|commodity| !commodity.is_empty()).collect();
+    if commodities.len() > 1 {
+        return Err("can't infer an amount from postings in several commodities".to_string());
+    }
+    let commodity = commodities.first().map_or(String::new(), |commodity| commodity.to_string());
+    let sum: Money = amounts.iter().map(|(amount, _)| *amount).sum();
+    Ok((-sum, commodity))
+}
+
+fn parse_expense(
+    description: &str,
+    date: NaiveDate,
+    posting: &Posting,
+    (amount, commodity): (Money, String),
+    expense_prefix: &str,
+) -> Result<Parsed, String> {
+    if amount.is_zero() {
+        return Ok(Parsed::Skipped("zero amount".to_string()));
+    }
+    if amount.is_negative() {
+        return Ok(Parsed::Skipped(format!("refund of {} to `{}`, not an expense", -amount, posting.account)));
+    }
+    if description.is_empty() {
+        return Err("missing description".to_string());
+    }
+    // Amounts without a commodity are in the base currency of the store.
+    let currency: Option<Currency> = Some(commodity)
+        .filter(|commodity| !commodity.is_empty())
+        .map(|commodity| commodity.parse().map_err(|_| format!("unsupported commodity `{}`", commodity)))
+        .transpose()?;
+    let category = posting.account[expense_prefix.len()..].trim_start_matches(':');
+    let category = Some(category)
+        .filter(|category| !category.is_empty() && *category != "Uncategorized")
+        .map(str::to_string);
+
+    Ok(Parsed::Transaction(ImportedTransaction {
+        description: description.to_string(),
+        amount,
+        currency,
+        datetime: date.and_time(Default::default()).and_utc(),
+        category,
+        external_ref: None,
+        kind: TransactionKind::Expense,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_hledger() {
+        let temp_file = "test_data_journal_1.journal";
+        fs::write(
+            temp_file,
+            "account Expenses:Food\n\
+             P 2025-01-01 EUR 1.10 USD\n\
+             \n\
+             2025/04/01 * (42) Supermarket | weekly shopping  ; receipt\n    \
+                 Expenses:Food:Groceries     $1,045.20\n    \
+                 Expenses:Household          EUR 10,00  ; soap\n    \
+                 Assets:Checking\n\
+             \n\
+             2025-04-02 Salary\n    \
+                 Assets:Checking  2000.00 USD\n    \
+                 Income:Salary\n\
+             \n\
+             2025-04-03 Taxi\n    \
+                 Assets:Cash  -20 USD\n    \
+                 Expenses:Uncategorized\n\
+             ~ monthly\n    \
+                 Expenses:Rent  1000 USD\n    \
+                 Assets:Checking\n",
+        )
+        .expect("Unable to write file");
+        let rows = read_journal(temp_file, "Expenses");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        let rows = rows.unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0].parsed, Parsed::Skipped("unsupported directive `P`".to_string()));
+        let Parsed::Transaction(groceries) = &rows[1].parsed else { panic!("expected a transaction") };
+        assert_eq!(rows[1].line, 4);
+        assert_eq!(groceries.description, "Supermarket - weekly shopping");
+        assert_eq!(groceries.amount, "1045.20".parse().unwrap());
+        assert_eq!(groceries.currency, Some("USD".parse().unwrap()));
+        assert_eq!(groceries.category.as_deref(), Some("Food:Groceries"));
+        assert_eq!(groceries.datetime.to_rfc3339(), "2025-04-01T00:00:00+00:00");
+        let Parsed::Transaction(soap) = &rows[2].parsed else { panic!("expected a transaction") };
+        assert_eq!(soap.amount, "10".parse().unwrap());
+        assert_eq!(soap.currency, Some("EUR".parse().unwrap()));
+        assert_eq!(rows[3].parsed, Parsed::Skipped("no expense postings".to_string()));
+        let Parsed::Transaction(taxi) = &rows[4].parsed else { panic!("expected a transaction") };
+        assert_eq!(taxi.amount, "20".parse().unwrap());
+        assert_eq!(taxi.category, None);
+        assert_eq!(rows[5].parsed, Parsed::Skipped("unsupported directive `~`".to_string()));
+    }
+
+    #[test]
+    fn test_read_beancount() {
+        let temp_file = "test_data_journal_2.beancount";
+        fs::write(
+            temp_file,
+            "option \"title\" \"Household\"\n\
+             2025-01-01 open Expenses:Eating-Out\n\
+             2025-04-01 * \"Da Mario\" \"Lunch \\\"special\\\"\"\n  \
+                 id: \"1\"\n  \
+                 Expenses:Eating-Out 12.50 EUR\n  \
+                 Assets:Cash\n\
+             2025-04-02 txn \"Refund\"\n  \
+                 Expenses:Eating-Out -2.50 EUR\n  \
+                 Assets:Cash 2.50 EUR\n\
+             2025-04-02 balance Assets:Cash -10.00 EUR\n",
+        )
+        .expect("Unable to write file");
+        let rows = read_journal(temp_file, "Expenses");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        let rows = rows.unwrap();
+        assert_eq!(rows.len(), 3);
+        let Parsed::Transaction(lunch) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!(rows[0].line, 3);
+        assert_eq!(lunch.description, "Da Mario - Lunch \"special\"");
+        assert_eq!(lunch.amount, "12.50".parse().unwrap());
+        assert_eq!(lunch.category.as_deref(), Some("Eating-Out"));
+        assert!(matches!(rows[1].parsed, Parsed::Skipped(_)));
+        assert_eq!(rows[2].parsed, Parsed::Skipped("unsupported directive `balance`".to_string()));
+    }
+
+    #[test]
+    fn test_read_amounts_without_commodity() {
+        let temp_file = "test_data_journal_3.journal";
+        fs::write(
+            temp_file,
+            "2025-04-01 Lunch\n    \
+                 Expenses:Food  12.50\n    \
+                 Assets:Cash\n\
+             \n\
+             2025-04-02 Dinner\n    \
+                 Expenses:Food\n    \
+                 Assets:Cash  -30\n\
+             \n\
+             2025-04-03 Taxi\n    \
+                 Expenses:Transport\n    \
+                 Assets:Cash  -12.50 USD\n    \
+                 Assets:Wallet  -2.50\n",
+        )
+        .expect("Unable to write file");
+        let rows = read_journal(temp_file, "Expenses");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        let rows = rows.unwrap();
+        let Parsed::Transaction(lunch) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!((lunch.amount, lunch.currency.clone()), ("12.50".parse().unwrap(), None));
+        let Parsed::Transaction(dinner) = &rows[1].parsed else { panic!("expected a transaction") };
+        assert_eq!((dinner.amount, dinner.currency.clone()), ("30".parse().unwrap(), None));
+        let Parsed::Transaction(taxi) = &rows[2].parsed else { panic!("expected a transaction") };
+        assert_eq!((taxi.amount, taxi.currency.clone()), ("15".parse().unwrap(), Some("USD".parse().unwrap())));
+    }
+}
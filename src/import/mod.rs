@@ -0,0 +1,226 @@
+//! Importers for bank statements and other files with transactions.
+//! Each importer parses a file into `ParsedRow`s; `import_rows` then adds them to the store
+//! and reports what happened to every row.
+
+pub mod camt053;
+pub mod csv;
+pub mod journal;
+pub mod mt940;
+pub mod ofx;
+pub mod qif;
+
+use crate::currency::Currency;
+use crate::error::StoreError;
+use crate::models::{Account, Money, NewTransaction, TransactionId, TransactionKind};
+use crate::store::Store;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+
+/// A transaction read from an import file, before it is added to the store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedTransaction {
+    pub description: String,
+    pub amount: Money,
+    /// Defaults to the base currency of the store.
+    pub currency: Option<Currency>,
+    pub datetime: DateTime<Utc>,
+    /// Name of the category; it is created if the store does not have it yet.
+    pub category: Option<String>,
+    /// Identifier of the transaction in the import file, if the format has one.
+    /// Rows with a reference that is already in the store are skipped.
+    pub external_ref: Option<String>,
+    /// Credits are imported as income.
+    pub kind: TransactionKind,
+}
+
+/// Outcome of parsing a single row of an import file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parsed {
+    Transaction(ImportedTransaction),
+    /// The row is valid, but deliberately not imported, e.g. an entry that is not booked yet.
+    Skipped(String),
+    /// The row can't be understood.
+    Rejected(String),
+}
+
+/// A parsed row, together with its line number in the import file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedRow {
+    pub line: usize,
+    pub parsed: Parsed,
+}
+
+/// A row that was not imported, with the reason why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowIssue {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Summary of an import: the IDs of the created transactions,
+/// and the rows that were skipped or rejected.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub imported: Vec<TransactionId>,
+    pub created_categories: Vec<String>,
+    pub skipped: Vec<RowIssue>,
+    pub rejected: Vec<RowIssue>,
+}
+
+/// Adds parsed rows to the store.
+/// Missing categories are created on demand. A row whose external reference is already in
+/// the store, or, for formats without references, a row identical to a transaction that was
+/// already in the store (same datetime, amount, currency and description) is skipped as a
+/// duplicate, so importing an overlapping statement twice does not double the expenses.
+/// The transactions are assigned to the given account, e.g. the one the statement belongs to.
+/// All transactions are persisted in a single write.
+pub fn import_rows(store: &mut Store, rows: Vec<ParsedRow>, account: Option<Account>) -> Result<ImportReport, StoreError> {
+    let mut report = ImportReport::default();
+    let existing: Vec<_> = store
+        .list_transactions(None)
+        .into_iter()
+        .map(|t| (t.id(), t.datetime(), t.amount(), t.currency().clone(), t.description().to_string()))
+        .collect();
+    let mut external_refs: HashMap<String, Option<TransactionId>> = store
+        .list_transactions(None)
+        .into_iter()
+        .filter_map(|t| t.external_ref().map(|external_ref| (external_ref.to_string(), Some(t.id()))))
+        .collect();
+    let base = store.base_currency().clone();
+
+    let mut new = vec![];
+    for row in rows {
+        let transaction = match row.parsed {
+            Parsed::Transaction(transaction) => transaction,
+            Parsed::Skipped(reason) => {
+                report.skipped.push(RowIssue { line: row.line, reason });
+                continue;
+            }
+            Parsed::Rejected(reason) => {
+                report.rejected.push(RowIssue { line: row.line, reason });
+                continue;
+            }
+        };
+        if let Some(external_ref) = &transaction.external_ref {
+            if let Some(id) = external_refs.get(external_ref) {
+                let reason = match id {
+                    Some(id) => format!("already imported as transaction {}", id),
+                    None => format!("`{}` appears earlier in the file", external_ref),
+                };
+                report.skipped.push(RowIssue { line: row.line, reason });
+                continue;
+            }
+            external_refs.insert(external_ref.clone(), None);
+        }
+        let currency = transaction.currency.clone().unwrap_or_else(|| base.clone());
+        if transaction.external_ref.is_none() {
+            let duplicate = existing.iter().find(|(_, datetime, amount, existing_currency, description)| {
+                *datetime == transaction.datetime
+                    && *amount == transaction.amount
+                    && *existing_currency == currency
+                    && *description == transaction.description
+            });
+            if let Some((id, ..)) = duplicate {
+                report.skipped.push(RowIssue { line: row.line, reason: format!("duplicate of transaction {}", id) });
+                continue;
+            }
+        }
+        let category = match transaction.category.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => match store.find_category(name) {
+                Some(category) => Some(category),
+                None => {
+                    let id = store.add_category(name)?;
+                    report.created_categories.push(name.to_string());
+                    Some(store.get_category(id)?)
+                }
+            },
+            _ => None,
+        };
+        new.push(NewTransaction {
+            description: transaction.description,
+            amount: transaction.amount,
+            currency: Some(currency),
+            category,
+            datetime: Some(transaction.datetime),
+            external_ref: transaction.external_ref,
+            kind: transaction.kind,
+            account: account.clone(),
+            to_account: None,
+            tags: BTreeSet::new(),
+        });
+    }
+    report.imported = store.add_transactions(new)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+
+    fn row(line: usize, description: &str, amount: &str, category: Option<&str>) -> ParsedRow {
+        ParsedRow {
+            line,
+            parsed: Parsed::Transaction(ImportedTransaction {
+                description: description.to_string(),
+                amount: amount.parse().unwrap(),
+                currency: None,
+                datetime: DateTime::parse_from_rfc3339("2025-04-01T00:00:00Z").unwrap().with_timezone(&Utc),
+                category: category.map(str::to_string),
+                external_ref: None,
+                kind: TransactionKind::Expense,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_import_rows() {
+        let mut store = Store::with_backend(Box::new(MemoryBackend::default())).unwrap();
+        store.add_category("Food").unwrap();
+        let account = store.add_account("Joint card").unwrap();
+        let rows = vec![
+            row(2, "Lunch", "12.5", Some("food")),
+            row(3, "Taxi", "20", Some("Transport")),
+            ParsedRow { line: 4, parsed: Parsed::Skipped("credit".to_string()) },
+            ParsedRow { line: 5, parsed: Parsed::Rejected("invalid amount".to_string()) },
+        ];
+        let account = store.get_account(account).ok();
+        let report = import_rows(&mut store, rows, account).unwrap();
+        assert_eq!(report.imported, vec![1, 2]);
+        assert_eq!(report.created_categories, vec!["Transport".to_string()]);
+        assert_eq!(report.skipped, vec![RowIssue { line: 4, reason: "credit".to_string() }]);
+        assert_eq!(report.rejected.len(), 1);
+        let transactions = store.list_transactions(None);
+        assert_eq!(transactions[0].category().unwrap().name(), "Food");
+        assert_eq!(transactions[1].account().unwrap().name(), "Joint card");
+        assert_eq!(store.list_categories().len(), 2);
+    }
+
+    #[test]
+    fn test_import_rows_skips_known_external_refs() {
+        let mut store = Store::with_backend(Box::new(MemoryBackend::default())).unwrap();
+        let with_ref = |line, external_ref: &str| {
+            let mut row = row(line, "Lunch", "12.5", None);
+            if let Parsed::Transaction(transaction) = &mut row.parsed {
+                transaction.external_ref = Some(external_ref.to_string());
+            }
+            row
+        };
+        let report = import_rows(&mut store, vec![with_ref(2, "ofx:1"), with_ref(3, "ofx:2"), with_ref(4, "ofx:1")], None).unwrap();
+        assert_eq!(report.imported, vec![1, 2]);
+        assert_eq!(report.skipped[0].reason, "`ofx:1` appears earlier in the file");
+        let report = import_rows(&mut store, vec![with_ref(2, "ofx:2")], None).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.skipped[0].reason, "already imported as transaction 2");
+        assert_eq!(store.list_transactions(None)[1].external_ref(), Some("ofx:2"));
+    }
+
+    #[test]
+    fn test_import_rows_skips_duplicates() {
+        let mut store = Store::with_backend(Box::new(MemoryBackend::default())).unwrap();
+        import_rows(&mut store, vec![row(2, "Lunch", "12.5", None)], None).unwrap();
+        let report = import_rows(&mut store, vec![row(2, "Lunch", "12.50", None), row(3, "Dinner", "30", None)], None).unwrap();
+        assert_eq!(report.imported, vec![2]);
+        assert_eq!(report.skipped[0].reason, "duplicate of transaction 1");
+    }
+}
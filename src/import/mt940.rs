@@ -0,0 +1,213 @@
+use super::{ImportedTransaction, Parsed, ParsedRow};
+use crate::currency::Currency;
+use crate::error::StoreError;
+use crate::models::{Money, TransactionKind};
+use chrono::NaiveDate;
+use std::fs;
+
+/// A tagged field of an MT940 message, e.g. `:61:`, with the line it starts on.
+struct Field {
+    tag: String,
+    value: String,
+    line: usize,
+}
+
+/// Splits an MT940 file into its fields. Continuation lines are appended to the field
+/// they belong to; SWIFT block headers (`{1:...}`) and message trailers (`-}`) are dropped.
+fn fields(content: &str) -> Vec<Field> {
+    let mut fields: Vec<Field> = vec![];
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| !tag.is_empty() && tag.len() <= 3 && tag.chars().all(|c| c.is_ascii_alphanumeric()));
+        if let Some((tag, value)) = tag {
+            fields.push(Field { tag: tag.to_string(), value: value.to_string(), line: i + 1 });
+        } else if line.starts_with('{') || line == "-}" || line == "-" {
+            continue;
+        } else if let Some(field) = fields.last_mut() {
+            field.value.push('\n');
+            field.value.push_str(line);
+        }
+    }
+    fields
+}
+
+/// Reads the statement lines (`:61:`) of an MT940 file, with the information to the account
+/// owner (`:86:`) that follows each of them.
+/// Transactions are dated with their value date, described by the booking text, counterparty
+/// and purpose, and use the bank reference (or the customer reference) qualified with the
+/// account as external reference, so lines that were already imported are skipped.
+/// Debits are imported as expenses in the currency of the opening balance, credits as income.
+pub fn read_mt940(path: &str) -> Result<Vec<ParsedRow>, StoreError> {
+    let content = fs::read(path).map_err(|e| StoreError::io(path, e))?;
+    // MT940 files are usually Latin-1 rather than UTF-8.
+    let content = String::from_utf8_lossy(&content);
+    let fields = fields(&content);
+    if !fields.iter().any(|field| field.tag == "61" || field.tag.starts_with("60")) {
+        return Err(StoreError::InvalidInput(format!("`{}` is not an MT940 statement", path)));
+    }
+
+    let mut rows = vec![];
+    let mut account: Option<String> = None;
+    let mut currency: Option<String> = None;
+    let mut fields = fields.into_iter().peekable();
+    while let Some(field) = fields.next() {
+        match field.tag.as_str() {
+            "25" => account = Some(field.value.trim().to_string()),
+            // Opening balance, e.g. `C250331EUR1234,56`: the currency follows the mark and the date.
+            "60F" | "60M" => currency = field.value.get(7..10).map(str::to_string),
+            "61" => {
+                let information = match fields.peek() {
+                    Some(next) if next.tag == "86" => fields.next().map(|next| next.value),
+                    _ => None,
+                };
+                let parsed = parse_statement_line(&field.value, information.as_deref(), account.as_deref(), currency.as_deref())
+                    .unwrap_or_else(Parsed::Rejected);
+                rows.push(ParsedRow { line: field.line, parsed });
+            }
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
+
+/// Parses a statement line, `YYMMDD[MMDD]<mark>[funds code]<amount><type><customer ref>[//<bank ref>]`,
+/// e.g. `2504010401DR12,50NMSCNONREF//BANKREF123`.
+fn parse_statement_line(
+    value: &str,
+    information: Option<&str>,
+    account: Option<&str>,
+    currency: Option<&str>,
+) -> Result<Parsed, String> {
+    let (value, _supplementary) = value.split_once('\n').unwrap_or((value, ""));
+    let invalid = || format!("invalid statement line `{}`", value);
+    let value_date = value.get(..6).ok_or_else(invalid)?;
+    let value_date = NaiveDate::parse_from_str(value_date, "%y%m%d").map_err(|_| format!("invalid value date `{}`", value_date))?;
+    let mut rest = &value[6..];
+    // The optional entry date has four digits, the mark that follows does not.
+    if rest.get(..4).is_some_and(|date| date.chars().all(|c| c.is_ascii_digit())) {
+        rest = &rest[4..];
+    }
+    let (mark, after_mark) = ["RC", "RD", "C", "D"]
+        .iter()
+        .find_map(|mark| rest.strip_prefix(mark).map(|after| (*mark, after)))
+        .ok_or_else(invalid)?;
+    rest = after_mark;
+    // Optional third letter of the currency code (funds code).
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        rest = &rest[1..];
+    }
+    let amount_end = rest.find(|c: char| !c.is_ascii_digit() && c != ',').ok_or_else(invalid)?;
+    let amount_text = &rest[..amount_end];
+    let amount: Money = amount_text
+        .replace(',', ".")
+        .trim_end_matches('.')
+        .parse()
+        .map_err(|_| format!("invalid amount `{}`", amount_text))?;
+    // Transaction type: `N`, `F` or `S` followed by three characters.
+    let references = rest.get(amount_end + 4..).ok_or_else(invalid)?;
+    let (customer_ref, bank_ref) = match references.split_once("//") {
+        Some((customer_ref, bank_ref)) => (customer_ref.trim(), Some(bank_ref.trim())),
+        None => (references.trim(), None),
+    };
+
+    if amount.is_zero() {
+        return Ok(Parsed::Skipped("zero amount".to_string()));
+    }
+
+    // A reversal of a credit is a debit, and vice versa.
+    let kind = if mark == "C" || mark == "RD" { TransactionKind::Income } else { TransactionKind::Expense };
+    let description = information.map(describe).filter(|description| !description.is_empty());
+    let description = description.ok_or("missing information to account owner (:86:)")?;
+    let reference = bank_ref
+        .filter(|reference| !reference.is_empty())
+        .or(Some(customer_ref).filter(|reference| !reference.is_empty() && *reference != "NONREF"));
+    let external_ref = reference.map(|reference| match account {
+        Some(account) => format!("mt940:{}:{}", account, reference),
+        None => format!("mt940:{}", reference),
+    });
+
+    Ok(Parsed::Transaction(ImportedTransaction {
+        description,
+        amount,
+        currency: currency.map(str::parse::<Currency>).transpose()?,
+        datetime: value_date.and_time(Default::default()).and_utc(),
+        category: None,
+        external_ref,
+        kind,
+    }))
+}
+
+/// Builds the description from the information to the account owner.
+/// Structured information, as used by German banks (`166?00booking text?20purpose...?32name`),
+/// is reduced to the booking text, counterparty and purpose; anything else is used as it is.
+fn describe(information: &str) -> String {
+    if !information.contains("?00") && !information.contains("?20") {
+        return information.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    // Subfields are cut at a fixed width, so their lines are joined without a space.
+    let information = information.replace('\n', "");
+    let mut booking_text = String::new();
+    let mut purpose = String::new();
+    let mut name = String::new();
+    for subfield in information.split('?').skip(1) {
+        let (code, value) = (subfield.get(..2).unwrap_or(""), subfield.get(2..).unwrap_or(""));
+        match code {
+            "00" => booking_text.push_str(value),
+            "20" | "21" | "22" | "23" | "24" | "25" | "26" | "27" | "28" | "29" => purpose.push_str(value),
+            "32" | "33" => name.push_str(value),
+            _ => {}
+        }
+    }
+    [booking_text, name, purpose]
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" - ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = "{1:F01BANKDEFFXXXX0000000000}{2:O9401200250401BANKDEFFXXXX00000000002504011200N}{4:\n\
+        :20:STARTUMS\n\
+        :25:37040044/0532013000\n\
+        :28C:00001/001\n\
+        :60F:C250331EUR1234,56\n\
+        :61:2504010401DR42,50NDDTNONREF//BANKREF1\n\
+        :86:105?00SEPA-LASTSCHRIFT?20Strom April?21Kunde 123?32Stadtwerke\n\
+        :61:2504030403CR1000,NTRFNONREF\n\
+        :86:SALARY APRIL\n\
+        :61:250404D5,NMSCREF42\n\
+        :86:Card payment\n\
+        bakery\n\
+        :62F:C250404EUR2187,06\n\
+        -}";
+
+    #[test]
+    fn test_read_mt940() {
+        let temp_file = "test_data_mt940_1.sta";
+        fs::write(temp_file, STATEMENT).expect("Unable to write file");
+        let rows = read_mt940(temp_file);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        let rows = rows.unwrap();
+        assert_eq!(rows.len(), 3);
+        let Parsed::Transaction(power) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!(rows[0].line, 6);
+        assert_eq!(power.description, "SEPA-LASTSCHRIFT - Stadtwerke - Strom AprilKunde 123");
+        assert_eq!(power.amount, "42.50".parse().unwrap());
+        assert_eq!(power.currency, Some("EUR".parse().unwrap()));
+        assert_eq!(power.datetime.to_rfc3339(), "2025-04-01T00:00:00+00:00");
+        assert_eq!(power.external_ref.as_deref(), Some("mt940:37040044/0532013000:BANKREF1"));
+        let Parsed::Transaction(credit) = &rows[1].parsed else { panic!("expected a transaction") };
+        assert_eq!(credit.kind, TransactionKind::Income);
+        assert_eq!(power.kind, TransactionKind::Expense);
+        let Parsed::Transaction(bakery) = &rows[2].parsed else { panic!("expected a transaction") };
+        assert_eq!(bakery.description, "Card payment bakery");
+        assert_eq!(bakery.external_ref.as_deref(), Some("mt940:37040044/0532013000:REF42"));
+    }
+}
@@ -0,0 +1,266 @@
+use super::{ImportedTransaction, Parsed, ParsedRow};
+use crate::currency::Currency;
+use crate::error::StoreError;
+use crate::models::{Money, TransactionKind};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use std::collections::HashMap;
+use std::fs;
+
+/// Which fields of an OFX transaction make up the description.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OfxDescription {
+    /// The payee (`NAME`), or the memo if there is no payee.
+    #[default]
+    Name,
+    /// The memo (`MEMO`), or the payee if there is no memo.
+    Memo,
+    /// The payee and the memo, separated by ` - `.
+    NameMemo,
+}
+
+/// A token of an OFX document: an opening tag, a closing tag or the text after a tag,
+/// with the line it starts on.
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Open(&'a str, usize),
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Splits an OFX document into tags and text.
+/// SGML (OFX 1.x) leaves out the closing tags of elements with a value, XML (OFX 2.x) does not;
+/// both produce the same sequence of opening tags followed by their text, so the parser only
+/// needs to look at those. Headers, processing instructions and comments are dropped.
+fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = content;
+    let mut line = 1;
+    while let Some(start) = rest.find('<') {
+        let text = rest[..start].trim();
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        line += rest[..start].matches('\n').count();
+        let Some(end) = rest[start..].find('>') else { break };
+        let tag = rest[start + 1..start + end].trim();
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(name.trim()));
+        } else if !tag.starts_with('?') && !tag.starts_with('!') {
+            let name = tag.split_whitespace().next().unwrap_or("").trim_end_matches('/');
+            tokens.push(Token::Open(name, line));
+        }
+        line += tag.matches('\n').count();
+        rest = &rest[start + end + 1..];
+    }
+    tokens
+}
+
+/// Reads the transactions (`STMTTRN`) of all bank and credit card statements in an OFX file.
+/// The FITID of each transaction, qualified with the account ID, becomes its external reference,
+/// so importing the same file twice adds nothing the second time.
+/// Amounts are signed as in the statement: debits are imported as expenses, credits as income.
+pub fn read_ofx(path: &str, description: OfxDescription) -> Result<Vec<ParsedRow>, StoreError> {
+    let content = fs::read(path).map_err(|e| StoreError::io(path, e))?;
+    // SGML files are often in a Windows code page; anything that isn't UTF-8 is read lossily.
+    let content = String::from_utf8_lossy(&content);
+    let tokens = tokenize(&content);
+    if !tokens.iter().any(|token| matches!(token, Token::Open(name, _) if name.eq_ignore_ascii_case("OFX"))) {
+        return Err(StoreError::InvalidInput(format!("`{}` is not an OFX file", path)));
+    }
+
+    let mut rows = vec![];
+    let mut currency: Option<String> = None;
+    let mut account: Option<String> = None;
+    let mut transaction: Option<(usize, HashMap<String, String>)> = None;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Open(name, line) if name.eq_ignore_ascii_case("STMTTRN") => {
+                transaction = Some((line, HashMap::new()));
+            }
+            Token::Close(name) if name.eq_ignore_ascii_case("STMTTRN") => {
+                if let Some((line, fields)) = transaction.take() {
+                    let parsed = parse_transaction(&fields, currency.as_deref(), account.as_deref(), description)
+                        .unwrap_or_else(Parsed::Rejected);
+                    rows.push(ParsedRow { line, parsed });
+                }
+            }
+            Token::Open(name, _) => {
+                let Some(Token::Text(value)) = tokens.peek() else { continue };
+                let name = name.to_ascii_uppercase();
+                let value = decode_entities(value);
+                tokens.next();
+                match &mut transaction {
+                    Some((_, fields)) => {
+                        fields.entry(name).or_insert(value);
+                    }
+                    None if name == "CURDEF" => currency = Some(value),
+                    None if name == "ACCTID" => account = Some(value),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
+
+fn parse_transaction(
+    fields: &HashMap<String, String>,
+    default_currency: Option<&str>,
+    account: Option<&str>,
+    description: OfxDescription,
+) -> Result<Parsed, String> {
+    let field = |name: &str| fields.get(name).map(|v| v.trim()).filter(|v| !v.is_empty());
+    let fitid = field("FITID").ok_or("missing FITID")?;
+    let datetime = parse_ofx_datetime(field("DTPOSTED").ok_or("missing DTPOSTED")?)?;
+    let amount = field("TRNAMT").ok_or("missing TRNAMT")?;
+    let amount: Money = amount.replace(',', ".").parse().map_err(|_| format!("invalid amount `{}`", amount))?;
+    if amount.is_zero() {
+        return Ok(Parsed::Skipped("zero amount".to_string()));
+    }
+    let kind = if amount.is_negative() { TransactionKind::Expense } else { TransactionKind::Income };
+
+    let name = field("NAME").or(field("PAYEEID"));
+    let memo = field("MEMO");
+    let description = match description {
+        OfxDescription::Name => name.or(memo).map(str::to_string),
+        OfxDescription::Memo => memo.or(name).map(str::to_string),
+        OfxDescription::NameMemo => match (name, memo) {
+            (Some(name), Some(memo)) if name != memo => Some(format!("{} - {}", name, memo)),
+            (name, memo) => name.or(memo).map(str::to_string),
+        },
+    };
+    let description = description
+        .or(field("TRNTYPE").map(str::to_string))
+        .ok_or("missing NAME and MEMO")?;
+
+    // A `CURRENCY` aggregate on the transaction overrides the statement's default currency.
+    let currency = field("CURSYM")
+        .or(default_currency)
+        .map(str::parse::<Currency>)
+        .transpose()?;
+    let external_ref = match account {
+        Some(account) => format!("ofx:{}:{}", account, fitid),
+        None => format!("ofx:{}", fitid),
+    };
+    Ok(Parsed::Transaction(ImportedTransaction {
+        description,
+        amount: amount.abs(),
+        currency,
+        datetime,
+        category: None,
+        external_ref: Some(external_ref),
+        kind,
+    }))
+}
+
+/// Parses an OFX datetime, `YYYYMMDD[HHMMSS[.XXX]][[offset:TZ]]`, e.g. `20250401123000.000[-5:EST]`.
+/// The offset is in hours and may be fractional; without it, the time is in UTC.
+fn parse_ofx_datetime(value: &str) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("invalid date `{}`", value);
+    let (local, zone) = match value.split_once('[') {
+        Some((local, zone)) => (local, Some(zone.trim_end_matches(']'))),
+        None => (value, None),
+    };
+    let local = local.split('.').next().unwrap_or(local);
+    if local.len() < 8 || !local.is_char_boundary(8) {
+        return Err(invalid());
+    }
+    let date = NaiveDate::parse_from_str(&local[..8], "%Y%m%d").map_err(|_| invalid())?;
+    let time = match &local[8..] {
+        "" => NaiveTime::MIN,
+        time if time.len() == 4 => NaiveTime::parse_from_str(time, "%H%M").map_err(|_| invalid())?,
+        time => NaiveTime::parse_from_str(time, "%H%M%S").map_err(|_| invalid())?,
+    };
+    let offset = match zone {
+        Some(zone) => {
+            let hours: f64 = zone.split(':').next().unwrap_or("").parse().map_err(|_| invalid())?;
+            FixedOffset::east_opt((hours * 3600.0).round() as i32).ok_or_else(invalid)?
+        }
+        None => FixedOffset::east_opt(0).expect("zero offset is valid"),
+    };
+    NaiveDateTime::new(date, time)
+        .and_local_timezone(offset)
+        .single()
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or_else(invalid)
+}
+
+/// Replaces the character entities used in OFX values.
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SGML: &str = "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\n\n<OFX>\n<BANKMSGSRSV1><STMTTRNRS><STMTRS>\n\
+        <CURDEF>EUR\n<BANKACCTFROM><BANKID>123<ACCTID>987654</BANKACCTFROM>\n<BANKTRANLIST>\n\
+        <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20250401123000.000[-5:EST]\n<TRNAMT>-12.50\n<FITID>A1\n<NAME>Bakery &amp; Co\n<MEMO>Card payment\n</STMTTRN>\n\
+        <STMTTRN>\n<TRNTYPE>CREDIT\n<DTPOSTED>20250402\n<TRNAMT>1000.00\n<FITID>A2\n<NAME>Salary\n</STMTTRN>\n\
+        <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>2025-04-03\n<TRNAMT>-5\n<FITID>A3\n<NAME>Cinema\n</STMTTRN>\n\
+        </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1>\n</OFX>\n";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="220"?>
+<OFX>
+  <CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS>
+    <CURDEF>USD</CURDEF>
+    <CCACCTFROM><ACCTID>4111</ACCTID></CCACCTFROM>
+    <BANKTRANLIST>
+      <STMTTRN>
+        <TRNTYPE>DEBIT</TRNTYPE>
+        <DTPOSTED>20250405</DTPOSTED>
+        <TRNAMT>-42.00</TRNAMT>
+        <FITID>X9</FITID>
+        <PAYEE><NAME>Hotel</NAME></PAYEE>
+        <MEMO>Booking 42</MEMO>
+        <CURRENCY><CURRATE>1.1</CURRATE><CURSYM>GBP</CURSYM></CURRENCY>
+      </STMTTRN>
+    </BANKTRANLIST>
+  </CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>
+</OFX>"#;
+
+    fn read(content: &str, description: OfxDescription) -> Vec<ParsedRow> {
+        let temp_file = format!("test_data_ofx_{:?}.ofx", description);
+        fs::write(&temp_file, content).expect("Unable to write file");
+        let rows = read_ofx(&temp_file, description);
+        fs::remove_file(&temp_file).expect("Unable to remove file");
+        rows.unwrap()
+    }
+
+    #[test]
+    fn test_read_sgml() {
+        let rows = read(SGML, OfxDescription::Name);
+        assert_eq!(rows.len(), 3);
+        let Parsed::Transaction(bakery) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!(rows[0].line, 10);
+        assert_eq!(bakery.description, "Bakery & Co");
+        assert_eq!(bakery.amount, "12.50".parse().unwrap());
+        assert_eq!(bakery.currency, Some("EUR".parse().unwrap()));
+        assert_eq!(bakery.datetime.to_rfc3339(), "2025-04-01T17:30:00+00:00");
+        assert_eq!(bakery.external_ref.as_deref(), Some("ofx:987654:A1"));
+        let Parsed::Transaction(credit) = &rows[1].parsed else { panic!("expected a transaction") };
+        assert_eq!(credit.kind, TransactionKind::Income);
+        assert!(!credit.amount.is_negative());
+        assert_eq!(rows[2].parsed, Parsed::Rejected("invalid date `2025-04-03`".to_string()));
+    }
+
+    #[test]
+    fn test_read_xml() {
+        let rows = read(XML, OfxDescription::NameMemo);
+        let Parsed::Transaction(hotel) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!(hotel.description, "Hotel - Booking 42");
+        assert_eq!(hotel.currency, Some("GBP".parse().unwrap()));
+        assert_eq!(hotel.external_ref.as_deref(), Some("ofx:4111:X9"));
+        assert_eq!(hotel.datetime.to_rfc3339(), "2025-04-05T00:00:00+00:00");
+    }
+}
@@ -0,0 +1,155 @@
+use super::{ImportedTransaction, Parsed, ParsedRow};
+use crate::error::StoreError;
+use crate::models::{Money, TransactionKind};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fs;
+
+/// Reads the transactions of a QIF file.
+/// Each record is a list of lines starting with a field code, ending with `^`:
+/// `D` date, `T` (or `U`) amount, `P` payee, `M` memo and `L` category.
+/// Payments (negative amounts) are imported as expenses and deposits as income.
+/// Split lines are ignored and the total of the record is used; records with a category in
+/// brackets are transfers to other accounts, and the account is not imported as a category.
+/// Amounts are in the base currency, as QIF files have no currencies.
+pub fn read_qif(path: &str) -> Result<Vec<ParsedRow>, StoreError> {
+    let content = fs::read(path).map_err(|e| StoreError::io(path, e))?;
+    let content = String::from_utf8_lossy(&content);
+
+    let mut rows = vec![];
+    let mut record: Vec<(char, &str)> = vec![];
+    let mut start = 0;
+    let mut skip_section = false;
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim_end();
+        let Some(code) = line.chars().next() else { continue };
+        let value = line[code.len_utf8()..].trim();
+        match code {
+            '!' => {
+                // Only transaction lists are imported, not lists of accounts, categories or classes.
+                // Option headers like `!Option:AutoSwitch` don't start a new section.
+                let header = value.to_ascii_lowercase();
+                if header == "account" {
+                    skip_section = true;
+                } else if header.starts_with("type:") {
+                    skip_section = matches!(header.as_str(), "type:cat" | "type:class" | "type:memorized");
+                }
+                record.clear();
+            }
+            '^' => {
+                if !skip_section && !record.is_empty() {
+                    rows.push(ParsedRow { line: start, parsed: parse_record(&record).unwrap_or_else(Parsed::Rejected) });
+                }
+                record.clear();
+            }
+            _ => {
+                if record.is_empty() {
+                    start = line_number;
+                }
+                record.push((code, value));
+            }
+        }
+    }
+    if !skip_section && !record.is_empty() {
+        rows.push(ParsedRow { line: start, parsed: Parsed::Rejected("record is not terminated with `^`".to_string()) });
+    }
+    Ok(rows)
+}
+
+fn parse_record(record: &[(char, &str)]) -> Result<Parsed, String> {
+    // Split lines (`S`, `E`, `$`) come after the main fields, so the first value of a code wins.
+    let field = |code: char| {
+        record.iter().find(|(c, _)| *c == code).map(|(_, value)| *value).filter(|value| !value.is_empty())
+    };
+    let datetime = parse_qif_date(field('D').ok_or("missing date")?)?;
+    let amount = field('T').or(field('U')).ok_or("missing amount")?;
+    let amount: Money = amount
+        .replace(',', "")
+        .parse()
+        .map_err(|_| format!("invalid amount `{}`", amount))?;
+    if amount.is_zero() {
+        return Ok(Parsed::Skipped("zero amount".to_string()));
+    }
+    let description = match (field('P'), field('M')) {
+        (Some(payee), Some(memo)) if payee != memo => format!("{} - {}", payee, memo),
+        (payee, memo) => payee.or(memo).ok_or("missing payee and memo")?.to_string(),
+    };
+    // A category in brackets is the other account of a transfer, e.g. `[Savings]`.
+    let kind = match field('L') {
+        Some(category) if category.starts_with('[') => TransactionKind::Transfer,
+        _ if amount.is_negative() => TransactionKind::Expense,
+        _ => TransactionKind::Income,
+    };
+    let category = field('L')
+        .filter(|category| !category.starts_with('['))
+        // A class may follow the category after a slash, e.g. `Food:Groceries/Business`.
+        .map(|category| category.split('/').next().unwrap_or(category).to_string());
+
+    Ok(Parsed::Transaction(ImportedTransaction {
+        description,
+        amount: amount.abs(),
+        currency: None,
+        datetime,
+        category,
+        external_ref: None,
+        kind,
+    }))
+}
+
+/// Parses a QIF date. Quicken writes US dates, `MM/DD/YYYY` or `MM/DD'YY` with two-digit years
+/// after 2000; ISO dates (`YYYY-MM-DD`) are accepted as well.
+/// Dates are placed at midnight UTC.
+fn parse_qif_date(value: &str) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("invalid date `{}`", value);
+    let normalized: String = value.replace(['\'', '-'], "/").replace(' ', "");
+    let parts: Vec<&str> = normalized.split('/').collect();
+    let [a, b, c] = parts[..] else { return Err(invalid()) };
+    let number = |part: &str| part.parse::<u32>().map_err(|_| invalid());
+    let (year, month, day) = if a.len() == 4 {
+        (number(a)? as i32, number(b)?, number(c)?)
+    } else {
+        let year = number(c)? as i32;
+        let year = if c.len() <= 2 { 2000 + year } else { year };
+        (year, number(a)?, number(b)?)
+    };
+    NaiveDate::from_ymd_opt(year, month, day)
+        .map(|date| date.and_time(Default::default()).and_utc())
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_qif() {
+        let temp_file = "test_data_qif_1.qif";
+        fs::write(
+            temp_file,
+            "!Type:Cat\nNFood\n^\n\
+             !Type:Bank\n\
+             D04/01/2025\nT-1,234.50\nPSupermarket\nMWeekly shopping\nLFood:Groceries/Home\n^\n\
+             D4/2'25\nT2000.00\nPSalary\n^\n\
+             D2025-04-03\nU-20.00\nPSavings\nL[Savings account]\nSFood\n$-5.00\n^\n\
+             Dtomorrow\nT-1\nPBroken\n^\n",
+        )
+        .expect("Unable to write file");
+        let rows = read_qif(temp_file).unwrap();
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        assert_eq!(rows.len(), 4);
+        let Parsed::Transaction(shopping) = &rows[0].parsed else { panic!("expected a transaction") };
+        assert_eq!(rows[0].line, 5);
+        assert_eq!(shopping.description, "Supermarket - Weekly shopping");
+        assert_eq!(shopping.amount, "1234.50".parse().unwrap());
+        assert_eq!(shopping.datetime.to_rfc3339(), "2025-04-01T00:00:00+00:00");
+        assert_eq!(shopping.category.as_deref(), Some("Food:Groceries"));
+        let Parsed::Transaction(salary) = &rows[1].parsed else { panic!("expected a transaction") };
+        assert_eq!(salary.kind, TransactionKind::Income);
+        assert_eq!(salary.amount, "2000".parse().unwrap());
+        let Parsed::Transaction(transfer) = &rows[2].parsed else { panic!("expected a transaction") };
+        assert_eq!(transfer.kind, TransactionKind::Transfer);
+        assert_eq!(transfer.amount, "20".parse().unwrap());
+        assert_eq!(transfer.category, None);
+        assert_eq!(rows[3].parsed, Parsed::Rejected("invalid date `tomorrow`".to_string()));
+    }
+}
@@ -0,0 +1,16 @@
+//! Expense tracker library.
+//! It exposes the models, budgets, recurring transactions, the store with its pluggable storage backends, the importers,
+//! the exporters and the summary functions, so the tracker can be embedded in other tools.
+//! The `expense-tracker` binary is a thin command-line layer on top of it.
+
+pub mod budget;
+pub mod currency;
+pub mod dates;
+pub mod error;
+pub mod export;
+pub mod import;
+pub mod models;
+pub mod recurring;
+pub mod storage;
+pub mod store;
+pub mod summary;
@@ -1,66 +1,152 @@
 mod cli;
-mod models;
-mod store;
-mod summary;
 
+use std::fs::{self, File};
+use std::io::BufWriter;
 use std::iter::once;
+use std::process;
 use std::collections::HashMap;
-use cli::CLI;
+use cli::Cli;
 use clap::Parser;
-use store::Store;
-use models::{Transaction, Category};
-use summary::{summary, check_limit};
+use roadmap_sh_expense_tracker::store::Store;
+use roadmap_sh_expense_tracker::storage::{JsonFileBackend, SqliteBackend, StorageBackend};
+use roadmap_sh_expense_tracker::models::{split_tags, Transaction, TransactionKind, NewTransaction, TransactionUpdate, Account, Category, Money};
+use roadmap_sh_expense_tracker::currency::{Currency, ExchangeRate, ExchangeRates, read_rates_csv};
+use roadmap_sh_expense_tracker::summary::{summary, account_balances, cash_flow, check_limit, limit_report, parse_month, running_balance, BalanceEntry, CashFlow, MonthlyLimit};
+use roadmap_sh_expense_tracker::budget::{budget_status, envelope_report, BudgetPeriod, BudgetStatus, Envelope, NewBudget};
+use roadmap_sh_expense_tracker::error::{AppError, StoreError};
+use roadmap_sh_expense_tracker::import::{import_rows, ImportReport};
+use roadmap_sh_expense_tracker::import::camt053::read_camt053;
+use roadmap_sh_expense_tracker::import::csv::read_csv;
+use roadmap_sh_expense_tracker::import::journal::read_journal;
+use roadmap_sh_expense_tracker::import::mt940::read_mt940;
+use roadmap_sh_expense_tracker::import::ofx::read_ofx;
+use roadmap_sh_expense_tracker::import::qif::read_qif;
+use roadmap_sh_expense_tracker::export::export_rows;
+use roadmap_sh_expense_tracker::export::csv::write_csv;
+use roadmap_sh_expense_tracker::export::journal::{write_journal, Dialect, JournalAccounts};
+use roadmap_sh_expense_tracker::export::json::{write_json, write_ndjson};
+use roadmap_sh_expense_tracker::export::parquet::{check_scale, write_parquet};
+use roadmap_sh_expense_tracker::export::qif::write_qif;
+use roadmap_sh_expense_tracker::export::xlsx::write_xlsx;
+use roadmap_sh_expense_tracker::recurring::{Frequency, NewRecurring, RecurringTransaction};
+use chrono::Utc;
 use tabled::{builder::Builder, settings::Style};
-use csv::Writer;
 
 fn main() {
-    let cli = CLI::parse();
-    let mut store = Store::new(None);
+    let cli = Cli::parse();
+    if let Err(error) = run(cli) {
+        eprintln!("Error: {}", error);
+        process::exit(error.exit_code());
+    }
+}
 
-    match cli.commands {
-        cli::Commands::Add { description, amount, category } => {
-            let category = if let Some(category_id) = category {
-                Some(store.get_category(category_id).expect("Category not found"))
-            } else {
-                None
-            };
+/// Runs the command given on the command line.
+fn run(cli: Cli) -> Result<(), AppError> {
+    let command = match cli.commands {
+        cli::Commands::Migrate { from, to } => return migrate(&from, &to),
+        cli::Commands::Store(command) => *command,
+    };
+
+    let path = cli.data.unwrap_or_else(|| cli.storage.default_path().to_string());
+    let backend: Box<dyn StorageBackend> = match cli.storage {
+        cli::Storage::Json => Box::new(JsonFileBackend::open(&path)?),
+        cli::Storage::Sqlite => Box::new(SqliteBackend::open(&path)?),
+    };
+    let mut store = Store::with_backend(backend)?;
+
+    match command {
+        cli::StoreCommands::Add { description, amount, category, currency, date, kind, account, tags } => {
+            let category = category.map(|category_id| store.get_category(category_id)).transpose()?;
+            let account = account.map(|account_id| store.get_account(account_id)).transpose()?;
+            let (description, mut description_tags) = split_tags(&description);
+            description_tags.extend(tags);
 
-            let id = store.add_transaction(description, amount, category);
+            let budget = category.as_ref().and_then(|category| store.budget(category.id())).cloned();
+            let kind = TransactionKind::from(kind);
+            let new = NewTransaction { description, amount, currency, category, datetime: date, kind, account, tags: description_tags, ..Default::default() };
+            let id = store.add_transaction(new)?;
             println!("Added transaction with ID: {:?})", id);
-            let limit = store.limit();
-            if let Some(limit) = limit {
-                let remaining = check_limit(store.list_transactions(None), limit);
-                if remaining < 0.0 {
-                    println!("Spending limit of {:?} exceeded by {:?}!", limit, remaining.abs());
+            // Income and transfers are not spending, so they can't exceed a limit or a budget.
+            if kind != TransactionKind::Expense {
+                return Ok(());
+            }
+            // The limit is the one of the month the new transaction falls in, which may be in the past.
+            let month = date.unwrap_or_else(Utc::now).format("%Y-%m").to_string();
+            if let Some(limit) = store.limit_for(&month)? {
+                let remaining = check_limit(store.list_transactions(None), &month, limit, store.exchange_rates())?;
+                if remaining.is_negative() {
+                    let base = store.base_currency();
+                    println!("Spending limit of {} {} for {} exceeded by {} {}!", limit, base, month, remaining.abs(), base);
+                }
+            }
+            if let Some(budget) = budget {
+                let day = date.unwrap_or_else(Utc::now).date_naive();
+                let status = budget_status(store.list_transactions(None), &[budget], store.budget_transfers(), day, store.exchange_rates())?.remove(0);
+                if status.is_exceeded() {
+                    let base = store.base_currency();
+                    println!(
+                        "Budget of {} {} per {} for category {:?} exceeded by {} {}!",
+                        status.allowance,
+                        base,
+                        status.budget.period(),
+                        status.budget.category().name(),
+                        status.remaining().abs(),
+                        base
+                    );
                 }
             }
         }
-        cli::Commands::Delete { id } => {
-            store.delete_transaction(id);
+        cli::StoreCommands::Delete { id } => {
+            store.delete_transaction(id)?;
             println!("Deleted transaction with ID: {}", id);
         }
-        cli::Commands::List { category } => {
-            let category = if let Some(category_id) = category {
-                Some(store.get_category(category_id).expect("Category not found"))
+        cli::StoreCommands::Edit { id, description, amount, category, no_category, date, kind, account, no_account, to_account, tags, untags } => {
+            let category = if no_category {
+                Some(None)
+            } else {
+                category.map(|category_id| store.get_category(category_id).map(Some)).transpose()?
+            };
+            let account = if no_account {
+                Some(None)
             } else {
-                None
+                account.map(|account_id| store.get_account(account_id).map(Some)).transpose()?
             };
+            let to_account = to_account.map(|account_id| store.get_account(account_id).map(Some)).transpose()?;
+            let update = TransactionUpdate {
+                description,
+                amount,
+                category,
+                date,
+                kind: kind.map(Into::into),
+                account,
+                to_account,
+                add_tags: tags.into_iter().collect(),
+                remove_tags: untags.into_iter().collect(),
+            };
+            match store.update_transaction(id, update)? {
+                Some((before, after)) => {
+                    println!("Updated transaction with ID: {}", id);
+                    println!("{}", create_table_edit(&before, &after));
+                }
+                None => println!("Transaction with ID {} is unchanged", id),
+            }
+        }
+        cli::StoreCommands::List { category, filter } => {
+            let filter = filter.filter(category);
+            let category = category.map(|category_id| store.get_category(category_id)).transpose()?;
             if let Some(ref cat) = category {
                 println!("Showing transactions for category: {:?}", cat.name());
             } else {
                 println!("Showing all transactions");
             }
-            let transactions = store.list_transactions(category);
+            let transactions = store.filter_transactions(&filter);
             let table = create_table_transactions(transactions);
             println!("{}", table);
         }
-        cli::Commands::Summary { month, category} => {
-            let category = if let Some(category_id) = category {
-                Some(store.get_category(category_id).expect("Category not found"))
-            } else {
-                None
-            };
-            let (total, by_day) = summary(store.list_transactions(None), Some(month.clone()), category.as_ref());
+        cli::StoreCommands::Summary { month, category, filter } => {
+            let filter = filter.filter(category);
+            let category = category.map(|category_id| store.get_category(category_id)).transpose()?;
+            let (total, by_day) = summary(store.filter_transactions(&filter), Some(month.clone()), None, store.exchange_rates())?;
             if month == "overall" {
                 println!("Showing summary for:");
                 println!("\t- any month");
@@ -78,39 +164,129 @@ fn main() {
                     println!("\t- any category");
                 }
             }
-            let table = create_table_by_day(by_day, total);
+            if !filter.tags.is_empty() {
+                println!("\t- tags: {:?}", filter.tags);
+            }
+            let table = create_table_by_day(by_day, total, store.base_currency());
             println!("{}", table);
         }
-        cli::Commands::Limit { amount } => {
-            store.set_limit(amount);
-            if amount == 0.0 {
-                println!("Removed spending limit.");
+        cli::StoreCommands::Cashflow { category, filter } => {
+            let filter = filter.filter(category);
+            let report = cash_flow(store.filter_transactions(&filter), store.exchange_rates())?;
+            let table = create_table_cash_flow(&report, store.base_currency());
+            println!("{}", table);
+        }
+        cli::StoreCommands::Limit { amount: Some(amount), month } => {
+            let scope = match month {
+                Some(month) => {
+                    store.set_month_limit(&month, amount)?;
+                    format!("for {}", parse_month(&month)?)
+                }
+                None => {
+                    store.set_limit(amount)?;
+                    format!("from {} on", Utc::now().format("%Y-%m"))
+                }
+            };
+            if amount.is_zero() {
+                println!("Removed spending limit {}.", scope);
             } else {
-                println!("Set spending limit to: {:?}", amount);
+                println!("Set spending limit {} to: {} {}", scope, amount, store.base_currency());
             };
         }
-        cli::Commands::Export { filename } => {
-            let transactions = store.list_transactions(None);
-            let mut wtr = Writer::from_path(filename.clone()).expect("Unable to create CSV writer");
-            for transaction in transactions {
-                wtr.write_record(&[
-                    transaction.id().to_string(),
-                    transaction.description().to_string(),
-                    transaction.amount().to_string(),
-                    transaction.datetime().to_string(),
-                    transaction.category().map_or("None".to_string(), |cat| cat.name().to_string()),
-                ]).expect("Unable to write record");
+        cli::StoreCommands::Limit { amount: None, .. } => {
+            let month = Utc::now().format("%Y-%m").to_string();
+            let report = limit_report(store.list_transactions(None), store.limits(), &month, store.exchange_rates())?;
+            let table = create_table_limits(&report, store.base_currency());
+            println!("{}", table);
+        }
+        cli::StoreCommands::Budget { budget_subcommand } => match budget_subcommand {
+            cli::BudgetSubcommand::Set { category, amount, month: Some(month), .. } => {
+                store.set_month_budget(category, &month, amount)?;
+                let name = store.get_category(category)?.name().to_string();
+                println!("Set budget of category {:?} for {} to: {} {}", name, parse_month(&month)?, amount, store.base_currency());
+            },
+            cli::BudgetSubcommand::Set { category, amount, period, rollover, since, month: None } => {
+                let new = NewBudget { rollover: rollover.into(), since, ..NewBudget::new(amount, period.into()) };
+                store.set_budget(category, new)?;
+                let name = store.get_category(category)?.name().to_string();
+                if amount.is_zero() {
+                    println!("Removed budget of category: {:?}", name);
+                } else {
+                    println!("Set budget of category {:?} to: {} {} per {}", name, amount, store.base_currency(), BudgetPeriod::from(period));
+                }
+            },
+            cli::BudgetSubcommand::Status { date } => {
+                let date = date.unwrap_or_else(|| Utc::now().date_naive());
+                let status = budget_status(store.list_transactions(None), store.budgets(), store.budget_transfers(), date, store.exchange_rates())?;
+                let table = create_table_budgets(&status, store.base_currency());
+                println!("{}", table);
+            },
+            cli::BudgetSubcommand::Transfer { from, to, amount, month } => {
+                let month = month.unwrap_or_else(|| Utc::now().format("%Y-%m").to_string());
+                let transfer = store.transfer_budget(&month, from, to, amount)?;
+                println!(
+                    "Transferred {} {} of budget from {:?} to {:?} for {}",
+                    amount,
+                    store.base_currency(),
+                    transfer.from.name(),
+                    transfer.to.name(),
+                    transfer.month
+                );
+            },
+            cli::BudgetSubcommand::Envelopes { month } => {
+                let month = month.unwrap_or_else(|| Utc::now().format("%Y-%m").to_string());
+                let envelopes = envelope_report(store.list_transactions(None), store.budgets(), store.budget_transfers(), &month, store.exchange_rates())?;
+                println!("Showing envelopes for: {}", parse_month(&month)?);
+                let table = create_table_envelopes(&envelopes, store.base_currency());
+                println!("{}", table);
+            },
+        },
+        cli::StoreCommands::Export { filename, category, format, account_prefix, funding_account, income_prefix, transfer_account, asset_prefix, filter } => {
+            let format = format.unwrap_or_else(|| cli::ExportFormat::from_path(&filename));
+            if let Some(category_id) = category {
+                store.get_category(category_id)?;
+            }
+            let rows = export_rows(store.filter_transactions(&filter.filter(category)), store.exchange_rates())?;
+            if format == cli::ExportFormat::Parquet {
+                check_scale(&rows)?;
             }
-            wtr.flush().expect("Unable to flush CSV writer");
+            let output_error = |source| AppError::Output { path: filename.clone(), source };
+            let accounts = JournalAccounts { expense_prefix: account_prefix, funding_account, income_prefix, transfer_account, asset_prefix };
+            let file = BufWriter::new(File::create(&filename).map_err(output_error)?);
+            match format {
+                cli::ExportFormat::Csv => write_csv(file, &rows),
+                cli::ExportFormat::Json => write_json(file, &rows),
+                cli::ExportFormat::Ndjson => write_ndjson(file, &rows),
+                cli::ExportFormat::Qif => write_qif(file, &rows),
+                cli::ExportFormat::Ledger => write_journal(file, &rows, Dialect::Ledger, &accounts),
+                cli::ExportFormat::Hledger => write_journal(file, &rows, Dialect::Hledger, &accounts),
+                cli::ExportFormat::Beancount => write_journal(file, &rows, Dialect::Beancount, &accounts),
+                cli::ExportFormat::Xlsx => write_xlsx(file, &rows, store.exchange_rates()),
+                cli::ExportFormat::Parquet => write_parquet(file, &rows),
+            }
+            .map_err(output_error)?;
             println!("Exporting transactions to: {}", filename);
         }
-        cli::Commands::Category { category_subcommand } => match category_subcommand {
+        cli::StoreCommands::Import { filename, format, csv, ofx_description, account_prefix, account } => {
+            let account = account.map(|account_id| store.get_account(account_id)).transpose()?;
+            let rows = match format.unwrap_or_else(|| cli::ImportFormat::from_path(&filename)) {
+                cli::ImportFormat::Csv => read_csv(&filename, &csv.profile()?)?,
+                cli::ImportFormat::Ofx => read_ofx(&filename, ofx_description.into())?,
+                cli::ImportFormat::Qif => read_qif(&filename)?,
+                cli::ImportFormat::Camt053 => read_camt053(&filename)?,
+                cli::ImportFormat::Mt940 => read_mt940(&filename)?,
+                cli::ImportFormat::Journal => read_journal(&filename, &account_prefix)?,
+            };
+            let report = import_rows(&mut store, rows, account)?;
+            print_import_report(&filename, &report);
+        }
+        cli::StoreCommands::Category { category_subcommand } => match category_subcommand {
             cli::CategorySubcommand::Add { name } => {
-                let id = store.add_category(&name);
+                let id = store.add_category(&name)?;
                 println!("Added category with ID: {:?})", id);
             },
             cli::CategorySubcommand::Delete { id } => {
-                store.delete_category(id);
+                store.delete_category(id)?;
                 println!("Deleted category with ID: {}", id);
             },
             cli::CategorySubcommand::List => {  
@@ -119,7 +295,156 @@ fn main() {
                 println!("{}", table);
             },
         },
-        
+        cli::StoreCommands::Tag { tag_subcommand } => match tag_subcommand {
+            cli::TagSubcommand::List => {
+                let table = create_table_tags(&store.list_tags());
+                println!("{}", table);
+            },
+            cli::TagSubcommand::Rename { from, to } => {
+                let count = store.rename_tag(&from, &to)?;
+                println!("Renamed tag {:?} to {:?} on {} transaction(s)", from, to, count);
+            },
+        },
+        cli::StoreCommands::Account { account_subcommand } => match account_subcommand {
+            cli::AccountSubcommand::Add { name } => {
+                let id = store.add_account(&name)?;
+                println!("Added account with ID: {}", id);
+            },
+            cli::AccountSubcommand::Delete { id } => {
+                store.delete_account(id)?;
+                println!("Deleted account with ID: {}", id);
+            },
+            cli::AccountSubcommand::List => {
+                let balances = account_balances(store.list_transactions(None), store.list_accounts(), store.exchange_rates())?;
+                let table = create_table_accounts(&balances, store.base_currency());
+                println!("{}", table);
+            },
+            cli::AccountSubcommand::Transfer { from, to, amount, description, currency, date } => {
+                let account = Some(store.get_account(from)?);
+                let to_account = Some(store.get_account(to)?);
+                let kind = TransactionKind::Transfer;
+                let new = NewTransaction { description, amount, currency, datetime: date, kind, account, to_account, ..Default::default() };
+                let id = store.add_transaction(new)?;
+                println!("Added transfer with ID: {}", id);
+            },
+            cli::AccountSubcommand::Balance { id } => {
+                let account = store.get_account(id)?;
+                let entries = running_balance(store.list_transactions(None), account.id(), store.exchange_rates())?;
+                println!("Showing balance of account: {:?}", account.name());
+                let table = create_table_balance(&entries, store.base_currency());
+                println!("{}", table);
+            },
+        },
+        cli::StoreCommands::Recurring { recurring_subcommand } => match recurring_subcommand {
+            cli::RecurringSubcommand::Add { description, amount, category, every, interval, start, end, day, currency, kind, account, to_account } => {
+                let category = category.map(|category_id| store.get_category(category_id)).transpose()?;
+                let account = account.map(|account_id| store.get_account(account_id)).transpose()?;
+                let to_account = to_account.map(|account_id| store.get_account(account_id)).transpose()?;
+                let start = start.unwrap_or_else(|| Utc::now().date_naive());
+                let new = NewRecurring {
+                    currency,
+                    category,
+                    kind: kind.into(),
+                    account,
+                    to_account,
+                    interval,
+                    end,
+                    day_of_month: day,
+                    ..NewRecurring::new(description, amount, every.into(), start)
+                };
+                let id = store.add_recurring(new)?;
+                println!("Added recurring transaction with ID: {}", id);
+            },
+            cli::RecurringSubcommand::Delete { id } => {
+                store.delete_recurring(id)?;
+                println!("Deleted recurring transaction with ID: {}", id);
+            },
+            cli::RecurringSubcommand::List => {
+                let table = create_table_recurring(store.list_recurring());
+                println!("{}", table);
+            },
+            cli::RecurringSubcommand::Run { until } => {
+                let until = until.unwrap_or_else(|| Utc::now().date_naive());
+                let ids = store.materialize_recurring(until)?;
+                println!("Created {} transactions from recurring transactions due by {}", ids.len(), until);
+                if !ids.is_empty() {
+                    let transactions = store.list_transactions(None).into_iter().filter(|transaction| ids.contains(&transaction.id())).collect();
+                    println!("{}", create_table_transactions(transactions));
+                }
+            },
+        },
+        cli::StoreCommands::Currency { currency_subcommand } => match currency_subcommand {
+            cli::CurrencySubcommand::Base { currency } => {
+                if let Some(currency) = currency {
+                    store.set_base_currency(currency)?;
+                    println!("Set base currency to: {}", store.base_currency());
+                } else {
+                    println!("Base currency: {}", store.base_currency());
+                }
+            },
+            cli::CurrencySubcommand::Rate { date, currency, rate, to } => {
+                let to = to.unwrap_or_else(|| store.base_currency().clone());
+                let rate = ExchangeRate::new(date, currency, to, rate)?;
+                store.add_exchange_rates(vec![rate.clone()])?;
+                println!("Added exchange rate: 1 {} = {} {} from {}", rate.from(), rate.rate(), rate.to(), rate.date());
+            },
+            cli::CurrencySubcommand::Import { filename } => {
+                let rates = read_rates_csv(&filename, store.base_currency())?;
+                let count = store.add_exchange_rates(rates)?;
+                println!("Imported {} exchange rates from: {}", count, filename);
+            },
+            cli::CurrencySubcommand::Rates => {
+                let table = create_table_rates(store.exchange_rates());
+                println!("{}", table);
+            },
+        },
+    }
+    Ok(())
+}
+
+/// Imports a JSON data file into an empty SQLite database.
+fn migrate(from: &str, to: &str) -> Result<(), AppError> {
+    if !fs::exists(from).map_err(|e| StoreError::io(from, e))? {
+        return Err(AppError::Usage(format!("JSON data file `{}` does not exist", from)));
+    }
+    // The JSON backend holds the lock on the file until the database is written,
+    // so no other command can change the file while it is being migrated.
+    let mut json = JsonFileBackend::open(from)?;
+    let data = json.load()?;
+    let mut sqlite = SqliteBackend::open(to)?;
+    if !sqlite.is_empty()? {
+        return Err(AppError::Usage(format!("SQLite database `{}` already contains data", to)));
+    }
+    sqlite.import(&data)?;
+    drop(json);
+    println!(
+        "Migrated {} transactions and {} categories from {} to {}",
+        data.transactions.len(),
+        data.categories.len(),
+        from,
+        to
+    );
+    Ok(())
+}
+
+/// Prints how many rows of an import file were imported, skipped or rejected,
+/// with the reason for every row that was not imported.
+fn print_import_report(filename: &str, report: &ImportReport) {
+    println!(
+        "Imported {} transactions from: {} ({} skipped, {} rejected)",
+        report.imported.len(),
+        filename,
+        report.skipped.len(),
+        report.rejected.len()
+    );
+    for name in &report.created_categories {
+        println!("Created category: {}", name);
+    }
+    for issue in &report.skipped {
+        println!("Skipped line {}: {}", issue.line, issue.reason);
+    }
+    for issue in &report.rejected {
+        println!("Rejected line {}: {}", issue.line, issue.reason);
     }
 }
 
@@ -131,18 +456,103 @@ fn create_table_transactions(transactions: Vec<&Transaction>) -> String {
         builder.push_record(vec![
             transaction.id().to_string(),
             transaction.description().to_string(),
-            transaction.amount().to_string(),
+            format!("{} {}", transaction.amount(), transaction.currency()),
             transaction.datetime().to_string(),
             transaction.category().map_or("None".to_string(), |cat| cat.name().to_string()),
+            transaction.kind().to_string(),
+            account_label(transaction),
+            tags_label(transaction),
         ]);
     }
     let headers = once(String::new()).chain(
-        ["Description", "Amount", "Datetime", "Category"].map(|i| i.to_string())
+        ["Description", "Amount", "Datetime", "Category", "Kind", "Account", "Tags"].map(|i| i.to_string())
     );
     builder.insert_record(0, headers);
     builder.build().with(Style::modern()).to_string()
 }
 
+/// Helper function to create table for an edited transaction
+/// It takes the transaction before and after the edit and returns a string representation
+/// of the table, with the changed fields only.
+fn create_table_edit(before: &Transaction, after: &Transaction) -> String {
+    let fields = |transaction: &Transaction| [
+        ("Description", transaction.description().to_string()),
+        ("Amount", format!("{} {}", transaction.amount(), transaction.currency())),
+        ("Datetime", transaction.datetime().to_string()),
+        ("Category", transaction.category().map_or("None".to_string(), |cat| cat.name().to_string())),
+        ("Kind", transaction.kind().to_string()),
+        ("Account", account_label(transaction)),
+        ("Tags", tags_label(transaction)),
+    ];
+    let mut builder = Builder::default();
+    for ((field, old), (_, new)) in fields(before).into_iter().zip(fields(after)) {
+        if old != new {
+            builder.push_record(vec![field.to_string(), old, new]);
+        }
+    }
+    builder.insert_record(0, ["", "Before", "After"].map(|i| i.to_string()));
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Returns the account of a transaction for display; transfers show both accounts, e.g. `Card -> Cash`.
+fn account_label(transaction: &Transaction) -> String {
+    let name = |account: Option<Account>| account.map_or("None".to_string(), |account| account.name().to_string());
+    match transaction.to_account() {
+        Some(to_account) => format!("{} -> {}", name(transaction.account()), to_account.name()),
+        None => name(transaction.account()),
+    }
+}
+
+/// Returns the tags of a transaction for display, e.g. `#trip-lisbon #work`.
+fn tags_label(transaction: &Transaction) -> String {
+    if transaction.tags().is_empty() {
+        return "None".to_string();
+    }
+    transaction.tags().iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" ")
+}
+
+/// Helper function to create table for accounts
+/// It takes each account with its balance and the base currency, and returns a string representation of the table.
+fn create_table_accounts(balances: &[(Account, Money)], currency: &Currency) -> String {
+    let mut builder = Builder::default();
+    for (account, balance) in balances {
+        builder.push_record(vec![
+            account.id().to_string(),
+            account.name().to_string(),
+            balance.to_string(),
+        ]);
+    }
+    builder.insert_record(0, [String::new(), "Name".to_string(), format!("Balance ({})", currency)]);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for the running balance of an account
+/// It takes the transactions of the account with the balance after each and the base currency,
+/// and returns a string representation of the table.
+fn create_table_balance(entries: &[BalanceEntry], currency: &Currency) -> String {
+    let mut builder = Builder::default();
+    for entry in entries {
+        let transaction = entry.transaction;
+        builder.push_record(vec![
+            transaction.id().to_string(),
+            transaction.datetime().date_naive().to_string(),
+            transaction.description().to_string(),
+            transaction.kind().to_string(),
+            entry.change.to_string(),
+            entry.balance.to_string(),
+        ]);
+    }
+    builder.insert_record(0, [
+        String::new(),
+        "Date".to_string(),
+        "Description".to_string(),
+        "Kind".to_string(),
+        format!("Change ({})", currency),
+        format!("Balance ({})", currency),
+    ]);
+    builder.build().with(Style::modern()).to_string()
+}
+
 /// Helper function to create table for categories
 /// It takes a vector of categories and returns a string representation of the table.
 fn create_table_categories(categories: Vec<&Category>) -> String {
@@ -160,10 +570,160 @@ fn create_table_categories(categories: Vec<&Category>) -> String {
     builder.build().with(Style::modern()).to_string()
 }
 
+/// Helper function to create table for tags
+/// It takes each tag with the number of transactions carrying it, and returns a string representation of the table.
+fn create_table_tags(tags: &[(&str, usize)]) -> String {
+    let mut builder = Builder::default();
+    for (tag, count) in tags {
+        builder.push_record(vec![format!("#{}", tag), count.to_string()]);
+    }
+    builder.insert_record(0, ["Tag", "Transactions"]);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for recurring transactions
+/// It takes a vector of recurring transactions and returns a string representation of the table,
+/// with the schedule and the date of the next transaction of each.
+fn create_table_recurring(recurring: Vec<&RecurringTransaction>) -> String {
+    let mut builder = Builder::default();
+    for recurring in recurring {
+        let mut schedule = match (recurring.frequency(), recurring.interval()) {
+            (frequency, 1) => frequency.to_string(),
+            (Frequency::Daily, interval) => format!("every {} days", interval),
+            (Frequency::Weekly, interval) => format!("every {} weeks", interval),
+            (Frequency::Monthly, interval) => format!("every {} months", interval),
+            (Frequency::Yearly, interval) => format!("every {} years", interval),
+        };
+        if let Some(day) = recurring.day_of_month() {
+            schedule = format!("{}, day {}", schedule, day);
+        }
+        builder.push_record(vec![
+            recurring.id().to_string(),
+            recurring.description().to_string(),
+            format!("{} {}", recurring.amount(), recurring.currency()),
+            schedule,
+            recurring.start().to_string(),
+            recurring.end().map_or("None".to_string(), |end| end.to_string()),
+            recurring.next_occurrence().map_or("None".to_string(), |next| next.to_string()),
+            recurring.category().map_or("None".to_string(), |cat| cat.name().to_string()),
+            recurring.kind().to_string(),
+        ]);
+    }
+    let headers = once(String::new()).chain(
+        ["Description", "Amount", "Schedule", "Start", "End", "Next", "Category", "Kind"].map(|i| i.to_string())
+    );
+    builder.insert_record(0, headers);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for monthly limits
+/// It takes the limit and spending of each month and the base currency, and returns a string representation of the table.
+fn create_table_limits(report: &[MonthlyLimit], currency: &Currency) -> String {
+    let mut builder = Builder::default();
+    for month in report {
+        builder.push_record(vec![
+            month.month.clone(),
+            month.limit.map_or("None".to_string(), |limit| limit.to_string()),
+            month.spent.to_string(),
+            month.remaining().map_or("None".to_string(), |remaining| remaining.to_string()),
+        ]);
+    }
+    builder.insert_record(0, [
+        "Month".to_string(),
+        format!("Limit ({})", currency),
+        format!("Spent ({})", currency),
+        format!("Remaining ({})", currency),
+    ]);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for the cash flow
+/// It takes the income and expenses of each month and the base currency, and returns a string representation of the table.
+fn create_table_cash_flow(report: &[CashFlow], currency: &Currency) -> String {
+    let mut builder = Builder::default();
+    for month in report {
+        builder.push_record(vec![
+            month.month.clone(),
+            month.income.to_string(),
+            month.expenses.to_string(),
+            month.net().to_string(),
+        ]);
+    }
+    builder.insert_record(0, [
+        "Month".to_string(),
+        format!("Income ({})", currency),
+        format!("Expenses ({})", currency),
+        format!("Net ({})", currency),
+    ]);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for budget envelopes
+/// It takes the envelope of each monthly budget and the base currency, and returns a string representation of the table.
+fn create_table_envelopes(envelopes: &[Envelope], currency: &Currency) -> String {
+    let mut builder = Builder::default();
+    for envelope in envelopes {
+        builder.push_record(vec![
+            envelope.category.name().to_string(),
+            envelope.opening.to_string(),
+            envelope.allocated.to_string(),
+            envelope.spent.to_string(),
+            envelope.closing().to_string(),
+        ]);
+    }
+    builder.insert_record(0, [
+        "Category".to_string(),
+        format!("Opening ({})", currency),
+        format!("Allocated ({})", currency),
+        format!("Spent ({})", currency),
+        format!("Closing ({})", currency),
+    ]);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for budget status
+/// It takes the status of each budget and the base currency, and returns a string representation of the table.
+fn create_table_budgets(status: &[BudgetStatus], currency: &Currency) -> String {
+    let mut builder = Builder::default();
+    for status in status {
+        builder.push_record(vec![
+            status.budget.category().name().to_string(),
+            format!("{} to {}", status.from, status.to),
+            status.spent.to_string(),
+            status.allowance.to_string(),
+            status.remaining().to_string(),
+        ]);
+    }
+    builder.insert_record(0, [
+        "Category".to_string(),
+        "Period".to_string(),
+        format!("Spent ({})", currency),
+        format!("Limit ({})", currency),
+        format!("Remaining ({})", currency),
+    ]);
+    builder.build().with(Style::modern()).to_string()
+}
+
+/// Helper function to create table for exchange rates
+/// It takes the exchange rate table and returns a string representation of the table.
+fn create_table_rates(rates: &ExchangeRates) -> String {
+    let mut builder = Builder::default();
+    for rate in rates.list() {
+        builder.push_record(vec![
+            rate.date().to_string(),
+            rate.from().to_string(),
+            rate.to().to_string(),
+            rate.rate().to_string(),
+        ]);
+    }
+    builder.insert_record(0, ["Date", "From", "To", "Rate"].map(|i| i.to_string()));
+    builder.build().with(Style::modern()).to_string()
+}
+
 /// Helper function to create table for summary by day
-/// It takes a HashMap of days and their corresponding totals, and the overall total.
+/// It takes a HashMap of days and their corresponding totals, the overall total and the currency of the totals.
 /// It returns a string representation of the table, with the days sorted in ascending order and the overall total at the end.
-fn create_table_by_day(by_day: HashMap<String, f64>, total: f64) -> String {
+fn create_table_by_day(by_day: HashMap<String, Money>, total: Money, currency: &Currency) -> String {
     let mut builder = Builder::default();
 
     let mut by_day_vec: Vec<_> = by_day.iter().collect();
@@ -176,7 +736,7 @@ fn create_table_by_day(by_day: HashMap<String, f64>, total: f64) -> String {
         ]);
     }
     let headers = once(String::new()).chain(
-        ["Amount"].map(|i| i.to_string())
+        [format!("Amount ({})", currency)]
     );
     builder.insert_record(0, headers);
     builder.push_record(vec![
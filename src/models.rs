@@ -1,27 +1,240 @@
-use chrono::prelude::{DateTime, Utc};
-use serde::{Serialize, Deserialize};
+use super::currency::Currency;
+use chrono::prelude::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::{self, Visitor};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
 
 pub type TransactionId = u32;
 pub type CategoryId = u32;
-pub type Limit = f64;
+pub type AccountId = u32;
+pub type Limit = Money;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Exact decimal amount of money.
+/// Amounts are kept as decimals instead of floating point numbers,
+/// so sums and differences never accumulate rounding errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money(Decimal);
+
+impl Money {
+    pub const ZERO: Money = Money(Decimal::ZERO);
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0.is_sign_negative() && !self.0.is_zero()
+    }
+
+    pub fn abs(&self) -> Money {
+        Money(self.0.abs())
+    }
+
+    pub fn as_decimal(&self) -> Decimal {
+        self.0
+    }
+}
+
+impl From<Decimal> for Money {
+    fn from(value: Decimal) -> Self {
+        Money(value)
+    }
+}
+
+/// Error returned when a string can't be parsed into `Money`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseMoneyError(String);
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Decimal::from_str_exact(s.trim())
+            .map(Money)
+            .map_err(|_| ParseMoneyError(s.to_string()))
+    }
+}
+
+/// Amounts are displayed with at least two decimal places, e.g. `30.00` or `0.125`.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0.normalize();
+        if value.scale() < 2 {
+            value.rescale(2);
+        }
+        write!(f, "{}", value)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + m)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + *m)
+    }
+}
+
+/// Money is persisted as a decimal string, so no precision is lost in the JSON file.
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+/// Money is read either from a decimal string or from a plain JSON number.
+/// Numbers come from data files written before amounts were stored as strings.
+/// They are converted through their shortest textual representation, which is
+/// exactly what was written to the file, so `0.1` becomes `0.1` and not `0.1000000000000000055`.
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a decimal amount as a string or a number")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Money, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                Ok(Money(Decimal::from(v)))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                Ok(Money(Decimal::from(v)))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                if !v.is_finite() {
+                    return Err(E::custom(format!("invalid amount `{}`", v)));
+                }
+                v.to_string().parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+/// Whether a transaction is money spent, money received, or money moved between own accounts.
+/// Summaries, limits and budgets only count expenses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionKind {
+    #[default]
+    Expense,
+    Income,
+    /// Money moved between own accounts, e.g. to savings. It is neither spent nor received.
+    Transfer,
+}
+
+impl fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionKind::Expense => write!(f, "expense"),
+            TransactionKind::Income => write!(f, "income"),
+            TransactionKind::Transfer => write!(f, "transfer"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Transaction {
     id: TransactionId,
-    amount: f64,
+    amount: Money,
+    #[serde(default)]
+    currency: Currency,
     description: String,
     datetime: DateTime<Utc>,
-    category: Option<Category>
+    category: Option<Category>,
+    /// Transactions written before kinds existed are expenses.
+    #[serde(default)]
+    kind: TransactionKind,
+    /// Account the transaction is paid from, or paid into for income.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    account: Option<Account>,
+    /// Account a transfer goes to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    to_account: Option<Account>,
+    /// Identifier of the transaction in the file it was imported from, e.g. `ofx:12345:FITID`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    external_ref: Option<String>,
+    /// Free-form labels, like `trip-lisbon` or `work`, in the form returned by `parse_tag`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    tags: BTreeSet<String>,
 }
 
 impl Transaction {
-    pub fn new(id: TransactionId, amount: f64, description: String, category: Option<Category>) -> Self {
+    pub fn new(id: TransactionId, amount: Money, currency: Currency, description: String, category: Option<Category>) -> Self {
         Transaction {
             id,
             amount,
+            currency,
             description,
             datetime: Utc::now(),
-            category
+            category,
+            kind: TransactionKind::Expense,
+            account: None,
+            to_account: None,
+            external_ref: None,
+            tags: BTreeSet::new(),
         }
     }
 
@@ -33,6 +246,10 @@ impl Transaction {
         self.category.clone()
     }
 
+    pub fn set_category(&mut self, category: Option<Category>) {
+        self.category = category;
+    }
+
     pub fn remove_category(&mut self) {
         self.category = None;
     }
@@ -41,13 +258,200 @@ impl Transaction {
         self.datetime
     }
 
-    pub fn amount(&self) -> f64 {
+    pub fn set_datetime(&mut self, datetime: DateTime<Utc>) {
+        self.datetime = datetime;
+    }
+
+    pub fn amount(&self) -> Money {
         self.amount
     }
 
+    pub fn set_amount(&mut self, amount: Money) {
+        self.amount = amount;
+    }
+
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
     pub fn description(&self) -> &str {
         &self.description
     }
+
+    pub fn set_description(&mut self, description: String) {
+        self.description = description;
+    }
+
+    pub fn kind(&self) -> TransactionKind {
+        self.kind
+    }
+
+    pub fn set_kind(&mut self, kind: TransactionKind) {
+        self.kind = kind;
+    }
+
+    pub fn is_expense(&self) -> bool {
+        self.kind == TransactionKind::Expense
+    }
+
+    pub fn account(&self) -> Option<Account> {
+        self.account.clone()
+    }
+
+    pub fn set_account(&mut self, account: Option<Account>) {
+        self.account = account;
+    }
+
+    pub fn to_account(&self) -> Option<Account> {
+        self.to_account.clone()
+    }
+
+    pub fn set_to_account(&mut self, to_account: Option<Account>) {
+        self.to_account = to_account;
+    }
+
+    pub fn external_ref(&self) -> Option<&str> {
+        self.external_ref.as_deref()
+    }
+
+    pub fn set_external_ref(&mut self, external_ref: Option<String>) {
+        self.external_ref = external_ref;
+    }
+
+    pub fn tags(&self) -> &BTreeSet<String> {
+        &self.tags
+    }
+
+    pub fn set_tags(&mut self, tags: BTreeSet<String>) {
+        self.tags = tags;
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+}
+
+/// Details of a new transaction to add to the store, which assigns its ID.
+/// Optional fields fall back to the defaults of the store.
+#[derive(Debug, Clone, Default)]
+pub struct NewTransaction {
+    pub description: String,
+    pub amount: Money,
+    /// Defaults to the base currency of the store.
+    pub currency: Option<Currency>,
+    pub category: Option<Category>,
+    /// Date and time of the transaction, which may be in the past. Defaults to now.
+    pub datetime: Option<DateTime<Utc>>,
+    /// Identifier of the transaction in the file it was imported from.
+    pub external_ref: Option<String>,
+    /// Defaults to an expense.
+    pub kind: TransactionKind,
+    pub account: Option<Account>,
+    /// Account a transfer goes to; only transfers may have one.
+    pub to_account: Option<Account>,
+    pub tags: BTreeSet<String>,
+}
+
+impl NewTransaction {
+    pub fn new(description: String, amount: Money) -> Self {
+        NewTransaction { description, amount, ..Default::default() }
+    }
+}
+
+/// Changes to apply to an existing transaction.
+/// Fields left as `None` are kept as they are.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionUpdate {
+    pub description: Option<String>,
+    pub amount: Option<Money>,
+    /// `Some(None)` removes the category from the transaction.
+    pub category: Option<Option<Category>>,
+    /// New date of the transaction; the time of day is kept.
+    pub date: Option<NaiveDate>,
+    /// A transaction that is no longer a transfer loses its destination account.
+    pub kind: Option<TransactionKind>,
+    /// `Some(None)` removes the account from the transaction.
+    pub account: Option<Option<Account>>,
+    pub to_account: Option<Option<Account>>,
+    /// Tags to add to the transaction.
+    pub add_tags: BTreeSet<String>,
+    /// Tags to remove from the transaction; tags it doesn't have are ignored.
+    pub remove_tags: BTreeSet<String>,
+}
+
+/// Criteria transactions are selected by. Criteria left as `None` match every transaction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionFilter {
+    /// First day of the date range, inclusive.
+    pub from: Option<NaiveDate>,
+    /// Last day of the date range, inclusive.
+    pub to: Option<NaiveDate>,
+    pub category: Option<CategoryId>,
+    /// Smallest amount, in the currency of the transaction, inclusive.
+    pub min_amount: Option<Money>,
+    /// Largest amount, in the currency of the transaction, inclusive.
+    pub max_amount: Option<Money>,
+    pub kind: Option<TransactionKind>,
+    /// Transactions paid from or into the account, including transfers to it.
+    pub account: Option<AccountId>,
+    /// Transactions carrying all of these tags.
+    pub tags: BTreeSet<String>,
+}
+
+impl TransactionFilter {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let date = transaction.datetime().date_naive();
+        self.from.is_none_or(|from| date >= from)
+            && self.to.is_none_or(|to| date <= to)
+            && self.category.is_none_or(|id| transaction.category().is_some_and(|category| category.id() == id))
+            && self.min_amount.is_none_or(|min| transaction.amount() >= min)
+            && self.max_amount.is_none_or(|max| transaction.amount() <= max)
+            && self.kind.is_none_or(|kind| transaction.kind() == kind)
+            && self.account.is_none_or(|id| {
+                transaction.account().is_some_and(|account| account.id() == id)
+                    || transaction.to_account().is_some_and(|account| account.id() == id)
+            })
+            && self.tags.is_subset(transaction.tags())
+    }
+}
+
+/// Parses a tag, with or without a leading `#`.
+/// Tags are case-insensitive and kept in lowercase. They start with a letter, followed by
+/// letters, digits, `-`, `_` or `/`, so `trip-lisbon` and `work/travel` are tags, but `#1` isn't.
+pub fn parse_tag(value: &str) -> Result<String, String> {
+    let tag = value.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+    let valid = tag.starts_with(char::is_alphabetic)
+        && tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'));
+    if valid {
+        Ok(tag)
+    } else {
+        Err(format!("invalid tag `{}`, tags start with a letter followed by letters, digits, `-`, `_` or `/`", value.trim()))
+    }
+}
+
+/// Splits the `#tag` words off a description, e.g. `Dinner #trip-lisbon #work` into
+/// `Dinner` and the tags `trip-lisbon` and `work`.
+/// Words that aren't valid tags, like `#1`, stay in the description, and a description made
+/// only of tags is kept as it is, so it is never empty.
+pub fn split_tags(description: &str) -> (String, BTreeSet<String>) {
+    let mut words = vec![];
+    let mut tags = BTreeSet::new();
+    for word in description.split_whitespace() {
+        match word.strip_prefix('#').map(parse_tag) {
+            Some(Ok(tag)) => {
+                tags.insert(tag);
+            }
+            _ => words.push(word),
+        }
+    }
+    if tags.is_empty() {
+        (description.to_string(), tags)
+    } else if words.is_empty() {
+        (description.trim().to_string(), tags)
+    } else {
+        (words.join(" "), tags)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -70,4 +474,40 @@ impl Category {
     }
 }
 
+/// An account transactions are paid from or into, like a bank card or a cash wallet.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Account {
+    id: AccountId,
+    name: String,
+}
+
+impl Account {
+    pub fn new(id: AccountId, name: String) -> Self {
+        Account { id, name }
+    }
+
+    pub fn id(&self) -> AccountId {
+        self.id
+    }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_tags() {
+        let (description, tags) = split_tags("Dinner #Trip-Lisbon with Ana #work #1");
+        assert_eq!(description, "Dinner with Ana #1");
+        assert_eq!(tags, BTreeSet::from(["trip-lisbon".to_string(), "work".to_string()]));
+        assert_eq!(split_tags("  #work "), ("#work".to_string(), BTreeSet::from(["work".to_string()])));
+        assert_eq!(split_tags("Lunch  for two").0, "Lunch  for two");
+        assert_eq!(parse_tag("#work/travel"), Ok("work/travel".to_string()));
+        assert!(parse_tag("two words").is_err());
+        assert!(parse_tag("#").is_err());
+    }
+}
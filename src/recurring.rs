@@ -0,0 +1,337 @@
+use super::currency::Currency;
+use super::models::{Account, AccountId, Category, Money, TransactionKind};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub type RecurringId = u32;
+
+/// How often a recurring transaction occurs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frequency::Daily => write!(f, "daily"),
+            Frequency::Weekly => write!(f, "weekly"),
+            Frequency::Monthly => write!(f, "monthly"),
+            Frequency::Yearly => write!(f, "yearly"),
+        }
+    }
+}
+
+/// Day of the month on which a monthly or yearly transaction occurs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayOfMonth {
+    /// A fixed day, 1 to 31. In shorter months the transaction occurs on the last day instead,
+    /// e.g. rent due on the 31st is booked on April 30th.
+    Day(u32),
+    /// The last day of each month.
+    Last,
+}
+
+impl DayOfMonth {
+    /// Returns the date of this day in the given month.
+    fn in_month(self, year: i32, month: u32) -> Option<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let last = first.checked_add_months(Months::new(1))?.pred_opt()?.day();
+        let day = match self {
+            DayOfMonth::Day(day) => day.min(last),
+            DayOfMonth::Last => last,
+        };
+        first.with_day(day)
+    }
+}
+
+/// Days are given as a number from 1 to 31, or `last`.
+impl FromStr for DayOfMonth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("last") {
+            return Ok(DayOfMonth::Last);
+        }
+        match s.parse() {
+            Ok(day @ 1..=31) => Ok(DayOfMonth::Day(day)),
+            _ => Err(format!("invalid day of month `{}`, expected 1 to 31 or `last`", s)),
+        }
+    }
+}
+
+impl fmt::Display for DayOfMonth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DayOfMonth::Day(day) => write!(f, "{}", day),
+            DayOfMonth::Last => write!(f, "last"),
+        }
+    }
+}
+
+/// Definition of a transaction that repeats on a schedule, like rent or a subscription.
+/// Its occurrences are turned into real transactions by `Store::materialize_recurring`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringTransaction {
+    id: RecurringId,
+    description: String,
+    amount: Money,
+    currency: Currency,
+    category: Option<Category>,
+    /// Recurring transactions defined before kinds existed are expenses.
+    #[serde(default)]
+    kind: TransactionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    account: Option<Account>,
+    /// Account a recurring transfer goes to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    to_account: Option<Account>,
+    frequency: Frequency,
+    /// Number of periods between two occurrences, e.g. 3 for quarterly with `Monthly`.
+    interval: u32,
+    /// Date of the first occurrence.
+    start: NaiveDate,
+    /// Last date an occurrence may fall on, if the schedule ends.
+    end: Option<NaiveDate>,
+    /// Day of the month of monthly and yearly occurrences. Defaults to the day of `start`.
+    day_of_month: Option<DayOfMonth>,
+    /// Date of the latest occurrence that was turned into a transaction.
+    last_occurrence: Option<NaiveDate>,
+}
+
+impl RecurringTransaction {
+    pub fn new(id: RecurringId, new: NewRecurring, currency: Currency) -> Self {
+        RecurringTransaction {
+            id,
+            description: new.description,
+            amount: new.amount,
+            currency,
+            category: new.category,
+            kind: new.kind,
+            account: new.account,
+            to_account: new.to_account,
+            frequency: new.frequency,
+            interval: new.interval.max(1),
+            start: new.start,
+            end: new.end,
+            day_of_month: new.day_of_month,
+            last_occurrence: None,
+        }
+    }
+
+    pub fn id(&self) -> RecurringId {
+        self.id
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn amount(&self) -> Money {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn category(&self) -> Option<Category> {
+        self.category.clone()
+    }
+
+    pub fn remove_category(&mut self) {
+        self.category = None;
+    }
+
+    pub fn kind(&self) -> TransactionKind {
+        self.kind
+    }
+
+    pub fn account(&self) -> Option<Account> {
+        self.account.clone()
+    }
+
+    pub fn to_account(&self) -> Option<Account> {
+        self.to_account.clone()
+    }
+
+    /// Removes the account from both sides of the recurring transaction.
+    pub fn remove_account(&mut self, id: AccountId) {
+        if self.account.as_ref().is_some_and(|account| account.id() == id) {
+            self.account = None;
+        }
+        if self.to_account.as_ref().is_some_and(|account| account.id() == id) {
+            self.to_account = None;
+        }
+    }
+
+    pub fn frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<NaiveDate> {
+        self.end
+    }
+
+    pub fn day_of_month(&self) -> Option<DayOfMonth> {
+        self.day_of_month
+    }
+
+    pub fn last_occurrence(&self) -> Option<NaiveDate> {
+        self.last_occurrence
+    }
+
+    pub fn set_last_occurrence(&mut self, date: NaiveDate) {
+        self.last_occurrence = Some(date);
+    }
+
+    /// Returns the date of the occurrence with the given index, the first being 0.
+    /// Returns None if the date is out of range.
+    fn occurrence(&self, index: u32) -> Option<NaiveDate> {
+        let periods = index.checked_mul(self.interval)?;
+        let day = self.day_of_month.unwrap_or(DayOfMonth::Day(self.start.day()));
+        match self.frequency {
+            Frequency::Daily => self.start.checked_add_days(Days::new(periods.into())),
+            Frequency::Weekly => self.start.checked_add_days(Days::new(u64::from(periods) * 7)),
+            Frequency::Monthly => {
+                let month = self.start.with_day(1)?.checked_add_months(Months::new(periods))?;
+                day.in_month(month.year(), month.month())
+            }
+            Frequency::Yearly => {
+                let year = self.start.year().checked_add(i32::try_from(periods).ok()?)?;
+                day.in_month(year, self.start.month())
+            }
+        }
+    }
+
+    /// Returns the dates of the occurrences that are due on or before `until` and that
+    /// have not been turned into transactions yet, in order.
+    /// With a day of month before the start day, the first occurrence is in the following period.
+    pub fn due_occurrences(&self, until: NaiveDate) -> Vec<NaiveDate> {
+        let until = self.end.map_or(until, |end| end.min(until));
+        let mut dates = vec![];
+        for index in 0.. {
+            let Some(date) = self.occurrence(index) else { break };
+            if date > until {
+                break;
+            }
+            if date >= self.start && self.last_occurrence.is_none_or(|last| date > last) {
+                dates.push(date);
+            }
+        }
+        dates
+    }
+
+    /// Returns the date of the next occurrence after the latest transaction, if the schedule hasn't ended.
+    pub fn next_occurrence(&self) -> Option<NaiveDate> {
+        (0..)
+            .map_while(|index| self.occurrence(index))
+            .take_while(|date| self.end.is_none_or(|end| *date <= end))
+            .find(|date| *date >= self.start && self.last_occurrence.is_none_or(|last| *date > last))
+    }
+}
+
+/// A recurring transaction to add to the store.
+#[derive(Debug, Clone)]
+pub struct NewRecurring {
+    pub description: String,
+    pub amount: Money,
+    /// Defaults to the base currency of the store.
+    pub currency: Option<Currency>,
+    pub category: Option<Category>,
+    pub kind: TransactionKind,
+    pub account: Option<Account>,
+    /// Account a recurring transfer goes to; only transfers may have one.
+    pub to_account: Option<Account>,
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+    pub day_of_month: Option<DayOfMonth>,
+}
+
+impl NewRecurring {
+    pub fn new(description: String, amount: Money, frequency: Frequency, start: NaiveDate) -> Self {
+        NewRecurring {
+            description,
+            amount,
+            currency: None,
+            category: None,
+            kind: TransactionKind::Expense,
+            account: None,
+            to_account: None,
+            frequency,
+            interval: 1,
+            start,
+            end: None,
+            day_of_month: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn recurring(new: NewRecurring) -> RecurringTransaction {
+        RecurringTransaction::new(1, new, Currency::default())
+    }
+
+    fn rent() -> NewRecurring {
+        NewRecurring::new("Rent".to_string(), "1000".parse().unwrap(), Frequency::Monthly, date("2025-01-31"))
+    }
+
+    #[test]
+    fn test_monthly_occurrences_are_clamped_to_the_month() {
+        let rent = recurring(rent());
+        assert_eq!(
+            rent.due_occurrences(date("2025-04-30")),
+            vec![date("2025-01-31"), date("2025-02-28"), date("2025-03-31"), date("2025-04-30")]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_after_the_last_one_and_before_the_end() {
+        let mut gym = recurring(NewRecurring {
+            end: Some(date("2025-02-01")),
+            ..NewRecurring::new("Gym".to_string(), "30".parse().unwrap(), Frequency::Weekly, date("2025-01-06"))
+        });
+        gym.set_last_occurrence(date("2025-01-13"));
+        assert_eq!(gym.due_occurrences(date("2025-12-31")), vec![date("2025-01-20"), date("2025-01-27")]);
+        gym.set_last_occurrence(date("2025-01-27"));
+        assert_eq!(gym.next_occurrence(), None);
+    }
+
+    #[test]
+    fn test_day_of_month_rules() {
+        let last = recurring(NewRecurring { day_of_month: Some(DayOfMonth::Last), interval: 3, ..rent() });
+        assert_eq!(last.due_occurrences(date("2025-12-31")), vec![date("2025-01-31"), date("2025-04-30"), date("2025-07-31"), date("2025-10-31")]);
+        // The 15th is before the start day, so the first occurrence is in February.
+        let mid = recurring(NewRecurring { day_of_month: Some(DayOfMonth::Day(15)), ..rent() });
+        assert_eq!(mid.next_occurrence(), Some(date("2025-02-15")));
+        let leap = recurring(NewRecurring::new("Domain".to_string(), "12".parse().unwrap(), Frequency::Yearly, date("2024-02-29")));
+        assert_eq!(leap.due_occurrences(date("2025-03-01")), vec![date("2024-02-29"), date("2025-02-28")]);
+        assert_eq!("last".parse(), Ok(DayOfMonth::Last));
+        assert!("32".parse::<DayOfMonth>().is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use super::{select, Change, StorageBackend, StoreData};
+use crate::error::StoreError;
+use super::lock::{FileLock, LOCK_TIMEOUT};
+use super::migrations::{self, SCHEMA_VERSION};
+use crate::models::{Transaction, TransactionFilter};
+use chrono::Utc;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Layout of the JSON file: the store data tagged with its schema version.
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u64,
+    #[serde(flatten)]
+    data: &'a StoreData,
+}
+
+/// Storage backend that keeps the data in a pretty-printed JSON file.
+/// The whole file is rewritten on every change.
+/// Writes are atomic: the new content is written to a temporary file, flushed to disk
+/// and then renamed over the data file, so a crash never leaves a truncated file.
+#[derive(Debug)]
+pub struct JsonFileBackend {
+    path: String,
+    _lock: FileLock,
+}
+
+impl JsonFileBackend {
+    /// Opens the backend for the given file path, locking it against other processes.
+    /// The lock is held until the backend is dropped.
+    /// The file is created on first load if it does not exist.
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        let lock = FileLock::acquire(path, LOCK_TIMEOUT).map_err(|e| StoreError::io(path, e))?;
+        Ok(JsonFileBackend { path: path.to_string(), _lock: lock })
+    }
+
+    /// Atomically replaces the JSON file with the given data, creating parent directories if needed.
+    /// The document is tagged with the current schema version.
+    fn save(&self, data: &StoreData) -> Result<(), StoreError> {
+        let document = Document { version: SCHEMA_VERSION, data };
+        let json = serde_json::to_string_pretty(&document).map_err(|e| self.corrupted(e))?;
+        write_atomically(Path::new(&self.path), json.as_bytes()).map_err(|e| StoreError::io(&self.path, e))
+    }
+
+    /// Keeps a copy of a data file that is about to be upgraded from an older schema version,
+    /// e.g. `data.json.v0-20250401120000.bak`.
+    fn backup(&self, json: &str, version: u64) -> Result<(), StoreError> {
+        let backup_path = format!("{}.v{}-{}.bak", self.path, version, Utc::now().format("%Y%m%d%H%M%S"));
+        write_atomically(Path::new(&backup_path), json.as_bytes()).map_err(|e| StoreError::io(&backup_path, e))
+    }
+
+    /// Reads the data from the JSON file, upgrading it in memory if it has an older schema version.
+    /// It returns the data together with the original content of the file and its schema version,
+    /// or None if the file does not exist.
+    fn read(&self) -> Result<Option<(StoreData, String, u64)>, StoreError> {
+        let is_exists = fs::exists(&self.path).map_err(|e| StoreError::io(&self.path, e))?;
+        if !is_exists {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&self.path).map_err(|e| StoreError::io(&self.path, e))?;
+        let mut document: serde_json::Value = serde_json::from_str(&json).map_err(|e| self.corrupted(e))?;
+        let version = migrations::upgrade(&mut document).map_err(|e| self.corrupted(e))?;
+        let data: StoreData = serde_json::from_value(document).map_err(|e| self.corrupted(e))?;
+        Ok(Some((data, json, version)))
+    }
+
+    /// Builds the error reported when the file content can't be understood.
+    fn corrupted(&self, message: impl ToString) -> StoreError {
+        StoreError::Corrupted { path: self.path.clone(), message: message.to_string() }
+    }
+}
+
+/// Writes the content to a temporary file next to `path`, syncs it and renames it over `path`.
+fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp_path, path)?;
+
+    // Persist the rename itself; directories can't be opened for syncing on all platforms.
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+impl StorageBackend for JsonFileBackend {
+    /// Loads the data from the JSON file.
+    /// Files written with an older schema version are upgraded; the original file is kept
+    /// as a backup next to it and the upgraded data is written back right away.
+    /// If the file does not exist, it creates an empty file and returns empty data.
+    fn load(&mut self) -> Result<StoreData, StoreError> {
+        match self.read()? {
+            Some((data, json, version)) => {
+                if version < SCHEMA_VERSION {
+                    self.backup(&json, version)?;
+                    self.save(&data)?;
+                }
+                Ok(data)
+            }
+            None => {
+                let data = StoreData::default();
+                self.save(&data)?;
+                Ok(data)
+            }
+        }
+    }
+
+    fn apply(&mut self, _changes: &[Change], data: &StoreData) -> Result<(), StoreError> {
+        self.save(data)
+    }
+
+    /// Lists the transactions in the JSON file matching the filter.
+    /// The file is read as a whole, but nothing is written, even if it has an older schema version.
+    fn list(&self, filter: &TransactionFilter) -> Result<Vec<Transaction>, StoreError> {
+        Ok(self.read()?.map(|(data, ..)| select(data.transactions, filter)).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_replaces_file_atomically() {
+        let temp_file = "test_data_json_1.json";
+        let mut backend = JsonFileBackend::open(temp_file).unwrap();
+        let mut data = backend.load().unwrap();
+        data.categories.push(crate::models::Category::new(1, "Test category".to_string()));
+        backend.apply(&[Change::CategoryAdded(1)], &data).unwrap();
+        assert!(!fs::exists("test_data_json_1.json.tmp").unwrap());
+        drop(backend);
+        let data = JsonFileBackend::open(temp_file).unwrap().load().unwrap();
+        assert_eq!(data.categories.len(), 1);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file("test_data_json_1.json.lock").expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_list_does_not_write() {
+        let temp_file = "test_data_json_3.json";
+        let legacy = r#"{
+            "transactions": [
+                {"id": 1, "amount": 10, "description": "A", "datetime": "2025-04-01T10:00:00Z", "category": null},
+                {"id": 2, "amount": 20, "description": "B", "datetime": "2025-03-01T10:00:00Z", "category": null}
+            ],
+            "categories": []
+        }"#;
+        fs::write(temp_file, legacy).expect("Unable to write file");
+        let backend = JsonFileBackend::open(temp_file).unwrap();
+        let transactions = backend.list(&TransactionFilter::default()).unwrap();
+        assert_eq!(transactions.iter().map(|t| t.id()).collect::<Vec<_>>(), [2, 1]);
+        let filter = TransactionFilter { min_amount: Some("15".parse().unwrap()), ..Default::default() };
+        assert_eq!(backend.list(&filter).unwrap()[0].description(), "B");
+        assert_eq!(fs::read_to_string(temp_file).unwrap(), legacy);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file("test_data_json_3.json.lock").expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_corrupted_file_is_an_error() {
+        let temp_file = "test_data_json_2.json";
+        fs::write(temp_file, "{ not json").expect("Unable to write file");
+        let result = JsonFileBackend::open(temp_file).unwrap().load();
+        assert!(matches!(result, Err(StoreError::Corrupted { .. })));
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file("test_data_json_2.json.lock").expect("Unable to remove file");
+    }
+}
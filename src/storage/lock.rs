@@ -0,0 +1,76 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for another process to release the lock before giving up.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay between two attempts to acquire the lock.
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Advisory inter-process lock on a data file.
+/// The lock is taken on a separate `<data file>.lock` file, because the data file itself
+/// is replaced on every save. It is held until the value is dropped, which covers the
+/// whole read-modify-write cycle of a command.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Acquires an exclusive lock for the given data file,
+    /// waiting up to `timeout` for other processes to release it.
+    pub fn acquire(data_path: &str, timeout: Duration) -> io::Result<FileLock> {
+        let lock_path = format!("{}.lock", data_path);
+        if let Some(parent) = Path::new(&lock_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(FileLock { file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < timeout => thread::sleep(RETRY_INTERVAL),
+                Err(TryLockError::WouldBlock) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WouldBlock,
+                        format!(
+                            "`{}` is in use by another expense-tracker process (lock file `{}`)",
+                            data_path, lock_path
+                        ),
+                    ));
+                }
+                Err(TryLockError::Error(err)) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let temp_file = "test_data_lock_1.json";
+        let lock = FileLock::acquire(temp_file, Duration::ZERO).unwrap();
+        let err = FileLock::acquire(temp_file, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        drop(lock);
+        assert!(FileLock::acquire(temp_file, Duration::ZERO).is_ok());
+        fs::remove_file("test_data_lock_1.json.lock").expect("Unable to remove file");
+    }
+}
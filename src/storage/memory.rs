@@ -0,0 +1,32 @@
+use super::{select, Change, StorageBackend, StoreData};
+use crate::error::StoreError;
+use crate::models::{Transaction, TransactionFilter};
+
+/// Storage backend that keeps the data in memory only.
+/// Useful for tests and for embedding the store in tools that handle persistence themselves.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    data: StoreData,
+}
+
+impl MemoryBackend {
+    /// Creates a backend pre-filled with the given data.
+    pub fn new(data: StoreData) -> Self {
+        MemoryBackend { data }
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn load(&mut self) -> Result<StoreData, StoreError> {
+        Ok(self.data.clone())
+    }
+
+    fn apply(&mut self, _changes: &[Change], data: &StoreData) -> Result<(), StoreError> {
+        self.data = data.clone();
+        Ok(())
+    }
+
+    fn list(&self, filter: &TransactionFilter) -> Result<Vec<Transaction>, StoreError> {
+        Ok(select(self.data.transactions.iter().cloned(), filter))
+    }
+}
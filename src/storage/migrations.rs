@@ -0,0 +1,118 @@
+use serde_json::{Map, Value};
+
+/// Version of the persisted document written by this version of the application.
+/// It must be bumped, together with a new entry in `MIGRATIONS`, whenever a change
+/// to the models can't be read from older files as-is.
+pub const SCHEMA_VERSION: u64 = 2;
+
+/// Migration steps, in order. The step at index `i` upgrades a document from version `i` to `i + 1`.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[
+    v0_to_v1,
+    v1_to_v2,
+];
+
+/// Returns the schema version of a persisted document.
+/// Documents without a version field were written before versioning was introduced and are version 0.
+pub fn version(document: &Value) -> u64 {
+    document.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrades a persisted document to the current schema version by running
+/// all migration steps between its version and `SCHEMA_VERSION`.
+/// It returns the version the document had before the upgrade.
+pub fn upgrade(document: &mut Value) -> Result<u64, String> {
+    let from = version(document);
+    if from > SCHEMA_VERSION {
+        return Err(format!(
+            "the data file has schema version {}, but this version of expense-tracker only supports up to version {}",
+            from, SCHEMA_VERSION
+        ));
+    }
+    let Some(object) = document.as_object_mut() else {
+        return Err("the data file does not contain a JSON object".to_string());
+    };
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(object);
+    }
+    object.insert("version".to_string(), Value::from(SCHEMA_VERSION));
+    Ok(from)
+}
+
+/// Version 1 stores amounts as decimal strings instead of JSON numbers,
+/// and adds currencies with an exchange rate table.
+/// Numbers are converted through their shortest textual representation, which is exactly
+/// what was written to the file, so no precision is lost.
+fn v0_to_v1(document: &mut Map<String, Value>) {
+    let base = document
+        .get("exchange_rates")
+        .and_then(|rates| rates.get("base"))
+        .cloned()
+        .unwrap_or_else(|| Value::from("USD"));
+
+    if let Some(transactions) = document.get_mut("transactions").and_then(Value::as_array_mut) {
+        for transaction in transactions.iter_mut().filter_map(Value::as_object_mut) {
+            if let Some(amount) = transaction.get_mut("amount") {
+                number_to_string(amount);
+            }
+            transaction.entry("currency").or_insert_with(|| base.clone());
+        }
+    }
+    if let Some(limit) = document.get_mut("limit") {
+        number_to_string(limit);
+    }
+    document.entry("exchange_rates").or_insert_with(|| {
+        serde_json::json!({ "base": base, "rates": [] })
+    });
+}
+
+/// Version 2 keeps a history of monthly limits instead of a single limit.
+/// The single limit applied to every month, so it becomes the limit of the months before the first change.
+fn v1_to_v2(document: &mut Map<String, Value>) {
+    let limit = document.remove("limit").unwrap_or(Value::Null);
+    document.insert("limits".to_string(), serde_json::json!({ "initial": limit }));
+}
+
+/// Replaces a JSON number with its decimal string representation.
+fn number_to_string(value: &mut Value) {
+    if let Value::Number(number) = value {
+        *value = Value::from(number.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_upgrade_from_v0() {
+        let mut document = json!({
+            "transactions": [
+                {"id": 1, "amount": 0.1, "description": "A", "datetime": "2025-04-01T10:00:00Z", "category": null}
+            ],
+            "categories": [],
+            "limit": 100.5
+        });
+        assert_eq!(upgrade(&mut document), Ok(0));
+        assert_eq!(document["version"], json!(SCHEMA_VERSION));
+        assert_eq!(document["transactions"][0]["amount"], json!("0.1"));
+        assert_eq!(document["transactions"][0]["currency"], json!("USD"));
+        assert_eq!(document["limits"]["initial"], json!("100.5"));
+        assert_eq!(document.get("limit"), None);
+        assert_eq!(document["exchange_rates"]["base"], json!("USD"));
+    }
+
+    #[test]
+    fn test_upgrade_current_version_is_noop() {
+        let mut document = json!({"version": SCHEMA_VERSION, "transactions": [], "categories": [], "limits": {}});
+        let before = document.clone();
+        assert_eq!(upgrade(&mut document), Ok(SCHEMA_VERSION));
+        assert_eq!(document, before);
+    }
+
+    #[test]
+    fn test_upgrade_rejects_newer_version() {
+        let mut document = json!({"version": SCHEMA_VERSION + 1});
+        assert!(upgrade(&mut document).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+mod json;
+mod lock;
+mod memory;
+mod migrations;
+mod sqlite;
+
+pub use json::JsonFileBackend;
+pub use memory::MemoryBackend;
+pub use sqlite::SqliteBackend;
+
+use super::budget::{Budget, BudgetTransfer, LimitHistory};
+use super::currency::ExchangeRates;
+use super::error::StoreError;
+use super::models::{Account, AccountId, Category, CategoryId, Transaction, TransactionFilter, TransactionId};
+use super::recurring::{RecurringId, RecurringTransaction};
+use serde::{Serialize, Deserialize};
+use std::fmt::Debug;
+
+/// Everything the store persists: transactions, categories, accounts, the history of the spending limit,
+/// the category budgets and the transfers between them, the exchange rates and the recurring transactions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreData {
+    pub transactions: Vec<Transaction>,
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub accounts: Vec<Account>,
+    #[serde(default)]
+    pub limits: LimitHistory,
+    #[serde(default)]
+    pub budgets: Vec<Budget>,
+    #[serde(default)]
+    pub budget_transfers: Vec<BudgetTransfer>,
+    #[serde(default)]
+    pub exchange_rates: ExchangeRates,
+    #[serde(default)]
+    pub recurring: Vec<RecurringTransaction>,
+}
+
+/// A single change made to the store data.
+/// Backends that can update records in place use it to avoid rewriting everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// A transaction was added or modified.
+    TransactionSaved(TransactionId),
+    /// A transaction was removed.
+    TransactionDeleted(TransactionId),
+    /// A category was added.
+    CategoryAdded(CategoryId),
+    /// A category was removed.
+    CategoryDeleted(CategoryId),
+    /// An account was added.
+    AccountAdded(AccountId),
+    /// An account was removed.
+    AccountDeleted(AccountId),
+    /// The spending limit of one or more months was set or removed.
+    LimitChanged,
+    /// A category budget was set or removed, or budget was transferred between categories.
+    BudgetsChanged,
+    /// The base currency or the exchange rates changed.
+    ExchangeRatesChanged,
+    /// A recurring transaction was added or modified.
+    RecurringSaved(RecurringId),
+    /// A recurring transaction was removed.
+    RecurringDeleted(RecurringId),
+}
+
+/// Storage backend used by `Store` to load and persist its data.
+/// The store keeps all data in memory and calls `apply` after every modification.
+/// `list` queries the persisted transactions directly, e.g. in tools that use a backend without a store.
+pub trait StorageBackend: Debug {
+    /// Loads the persisted data.
+    /// A backend with nothing stored yet returns empty data.
+    fn load(&mut self) -> Result<StoreData, StoreError>;
+
+    /// Persists a set of changes.
+    /// `data` is the complete state after the changes were made, so backends
+    /// that can't apply individual changes can simply save all of it.
+    fn apply(&mut self, changes: &[Change], data: &StoreData) -> Result<(), StoreError>;
+
+    /// Lists the persisted transactions matching the filter, sorted by date.
+    fn list(&self, filter: &TransactionFilter) -> Result<Vec<Transaction>, StoreError>;
+}
+
+/// Selects the transactions matching the filter and sorts them by date, like `Store::filter_transactions`.
+fn select(transactions: impl IntoIterator<Item = Transaction>, filter: &TransactionFilter) -> Vec<Transaction> {
+    let mut transactions: Vec<Transaction> = transactions.into_iter().filter(|transaction| filter.matches(transaction)).collect();
+    transactions.sort_by_key(|transaction| transaction.datetime());
+    transactions
+}
@@ -0,0 +1,1043 @@
+use super::{select, Change, StorageBackend, StoreData};
+use crate::budget::{Budget, BudgetTransfer, NewBudget};
+use crate::currency::ExchangeRate;
+use crate::error::StoreError;
+use super::lock::{FileLock, LOCK_TIMEOUT};
+use crate::models::{Account, AccountId, Category, CategoryId, Limit, Money, Transaction, TransactionFilter, TransactionId};
+use crate::recurring::{NewRecurring, RecurringTransaction};
+use chrono::{SecondsFormat, Utc};
+use rusqlite::types::{Type, Value as SqlValue};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Schema of the SQLite database.
+/// Every field of a record has a column of its own; the tags of a transaction are kept in
+/// `transaction_tags`, one row per tag. Categories and accounts are referenced by ID.
+/// Amounts and rates are stored as decimal strings so no precision is lost, datetimes as fixed-width
+/// RFC 3339 strings in UTC and dates as YYYY-MM-DD, so both sort chronologically.
+/// Enums are stored by the name they have in the JSON file, e.g. `expense` or `monthly`.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS categories (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS accounts (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS transactions (
+        id INTEGER PRIMARY KEY,
+        datetime TEXT NOT NULL,
+        amount TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        description TEXT NOT NULL,
+        category_id INTEGER,
+        kind TEXT NOT NULL,
+        account_id INTEGER,
+        to_account_id INTEGER,
+        external_ref TEXT
+    );
+    CREATE INDEX IF NOT EXISTS transactions_datetime ON transactions (datetime);
+    CREATE INDEX IF NOT EXISTS transactions_category ON transactions (category_id);
+    CREATE TABLE IF NOT EXISTS transaction_tags (
+        transaction_id INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (transaction_id, tag)
+    );
+    CREATE INDEX IF NOT EXISTS transaction_tags_tag ON transaction_tags (tag);
+    CREATE TABLE IF NOT EXISTS limits (
+        scope TEXT PRIMARY KEY,
+        amount TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS budgets (
+        category_id INTEGER PRIMARY KEY,
+        period TEXT NOT NULL,
+        rollover TEXT NOT NULL,
+        since TEXT
+    );
+    CREATE TABLE IF NOT EXISTS budget_amounts (
+        category_id INTEGER NOT NULL,
+        scope TEXT NOT NULL,
+        amount TEXT NOT NULL,
+        PRIMARY KEY (category_id, scope)
+    );
+    CREATE TABLE IF NOT EXISTS budget_transfers (
+        position INTEGER PRIMARY KEY,
+        month TEXT NOT NULL,
+        from_category_id INTEGER NOT NULL,
+        to_category_id INTEGER NOT NULL,
+        amount TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS exchange_rates (
+        date TEXT NOT NULL,
+        from_currency TEXT NOT NULL,
+        to_currency TEXT NOT NULL,
+        rate TEXT NOT NULL,
+        PRIMARY KEY (date, from_currency, to_currency)
+    );
+    CREATE TABLE IF NOT EXISTS recurring (
+        id INTEGER PRIMARY KEY,
+        description TEXT NOT NULL,
+        amount TEXT NOT NULL,
+        currency TEXT NOT NULL,
+        category_id INTEGER,
+        kind TEXT NOT NULL,
+        account_id INTEGER,
+        to_account_id INTEGER,
+        frequency TEXT NOT NULL,
+        interval INTEGER NOT NULL,
+        start_date TEXT NOT NULL,
+        end_date TEXT,
+        day_of_month TEXT,
+        last_occurrence TEXT
+    );
+    CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// Version of the database schema, kept in SQLite's `user_version` pragma.
+/// It must be bumped, together with a new entry in `MIGRATIONS`, whenever `SCHEMA` changes.
+const SQLITE_SCHEMA_VERSION: i64 = 2;
+
+/// Step upgrading a database by one schema version.
+type Migration = fn(&Connection) -> Result<(), StoreError>;
+
+/// Migration steps for databases written by older versions, in order.
+/// The step at index `i` upgrades a database from version `i + 1` to `i + 2`;
+/// a new database (version 0) gets the current schema directly.
+/// Steps only rely on the tables as they were at their version, never on the current models.
+const MIGRATIONS: &[Migration] = &[
+    v1_to_v2,
+];
+
+/// Scopes of the amounts in the `limits` and `budget_amounts` tables:
+/// `default` holds the amount of the months before the first change, `since:YYYY-MM` the amount
+/// from a month on and `month:YYYY-MM` the amount of a single month. A removed limit is stored as 0.
+const DEFAULT_SCOPE: &str = "default";
+const SINCE_SCOPE: &str = "since:";
+const MONTH_SCOPE: &str = "month:";
+
+/// Key of the base currency in the `settings` table.
+const BASE_CURRENCY_SETTING: &str = "base_currency";
+
+/// Storage backend that keeps the data in an embedded SQLite database.
+/// Unlike the JSON file, only the records touched by a change are written,
+/// and all changes of one store operation are committed in a single SQLite transaction.
+#[derive(Debug)]
+pub struct SqliteBackend {
+    connection: Connection,
+    path: String,
+    _lock: FileLock,
+}
+
+impl SqliteBackend {
+    /// Opens the database at the given path, creating it and its tables if needed.
+    /// Like the JSON backend, it locks the database against other expense-tracker
+    /// processes until the backend is dropped.
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|e| StoreError::io(path, e))?;
+        }
+        let lock = FileLock::acquire(path, LOCK_TIMEOUT).map_err(|e| StoreError::io(path, e))?;
+        let mut connection = Connection::open(path)?;
+        let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SQLITE_SCHEMA_VERSION {
+            return Err(StoreError::Corrupted {
+                path: path.to_string(),
+                message: format!(
+                    "the database has schema version {}, but this version of expense-tracker only supports up to version {}",
+                    version, SQLITE_SCHEMA_VERSION
+                ),
+            });
+        }
+        let tx = connection.transaction()?;
+        if version > 0 && version < SQLITE_SCHEMA_VERSION {
+            backup(path, version)?;
+            for migration in &MIGRATIONS[(version - 1) as usize..] {
+                migration(&tx)?;
+            }
+        }
+        tx.execute_batch(SCHEMA)?;
+        tx.pragma_update(None, "user_version", SQLITE_SCHEMA_VERSION)?;
+        tx.commit()?;
+        Ok(SqliteBackend { connection, path: path.to_string(), _lock: lock })
+    }
+
+    /// Checks whether the database holds no transactions and no categories.
+    pub fn is_empty(&self) -> Result<bool, StoreError> {
+        let count: i64 = self.connection.query_row(
+            "SELECT (SELECT COUNT(*) FROM transactions) + (SELECT COUNT(*) FROM categories)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count == 0)
+    }
+
+    /// Writes all of the given data to the database, e.g. when migrating from a JSON file.
+    pub fn import(&mut self, data: &StoreData) -> Result<(), StoreError> {
+        let mut changes: Vec<Change> = data.categories.iter().map(|c| Change::CategoryAdded(c.id())).collect();
+        changes.extend(data.accounts.iter().map(|a| Change::AccountAdded(a.id())));
+        changes.extend(data.transactions.iter().map(|t| Change::TransactionSaved(t.id())));
+        changes.push(Change::LimitChanged);
+        changes.push(Change::BudgetsChanged);
+        changes.push(Change::ExchangeRatesChanged);
+        changes.extend(data.recurring.iter().map(|r| Change::RecurringSaved(r.id())));
+        self.apply(&changes, data)
+    }
+
+    /// Builds the error reported when a stored value can't be understood.
+    fn corrupted(&self, message: impl ToString) -> StoreError {
+        StoreError::Corrupted { path: self.path.clone(), message: message.to_string() }
+    }
+
+    /// Reads all categories, by ID.
+    fn categories(&self) -> Result<HashMap<CategoryId, Category>, StoreError> {
+        let mut statement = self.connection.prepare("SELECT id, name FROM categories")?;
+        let categories = statement
+            .query_map([], |row| Ok((row.get(0)?, Category::new(row.get(0)?, row.get(1)?))))?
+            .collect::<Result<_, _>>()?;
+        Ok(categories)
+    }
+
+    /// Reads all accounts, by ID.
+    fn accounts(&self) -> Result<HashMap<AccountId, Account>, StoreError> {
+        let mut statement = self.connection.prepare("SELECT id, name FROM accounts")?;
+        let accounts = statement
+            .query_map([], |row| Ok((row.get(0)?, Account::new(row.get(0)?, row.get(1)?))))?
+            .collect::<Result<_, _>>()?;
+        Ok(accounts)
+    }
+
+    /// Reads the transactions matching an SQL condition on the `transactions` table, with their tags, sorted by ID.
+    /// Categories and accounts are looked up in the given maps; references to missing ones are dropped.
+    fn transactions(
+        &self,
+        condition: &str,
+        values: &[SqlValue],
+        categories: &HashMap<CategoryId, Category>,
+        accounts: &HashMap<AccountId, Account>,
+    ) -> Result<Vec<Transaction>, StoreError> {
+        let mut tags: HashMap<TransactionId, BTreeSet<String>> = HashMap::new();
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT transaction_id, tag FROM transaction_tags WHERE transaction_id IN (SELECT id FROM transactions WHERE {})",
+            condition
+        ))?;
+        let rows = statement.query_map(params_from_iter(values), |row| Ok((row.get::<_, TransactionId>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().insert(tag);
+        }
+
+        let category = |id: Option<CategoryId>| id.and_then(|id| categories.get(&id).cloned());
+        let account = |id: Option<AccountId>| id.and_then(|id| accounts.get(&id).cloned());
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT id, amount, currency, description, category_id, datetime, kind, account_id, to_account_id, external_ref
+             FROM transactions WHERE {} ORDER BY id",
+            condition
+        ))?;
+        let transactions = statement
+            .query_map(params_from_iter(values), |row| {
+                let id = row.get(0)?;
+                let mut transaction = Transaction::new(id, parse(row, 1)?, parse(row, 2)?, row.get(3)?, category(row.get(4)?));
+                transaction.set_datetime(parse(row, 5)?);
+                transaction.set_kind(parse_name(row, 6)?);
+                transaction.set_account(account(row.get(7)?));
+                transaction.set_to_account(account(row.get(8)?));
+                transaction.set_external_ref(row.get(9)?);
+                transaction.set_tags(tags.remove(&id).unwrap_or_default());
+                Ok(transaction)
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(transactions)
+    }
+}
+
+impl StorageBackend for SqliteBackend {
+    fn load(&mut self) -> Result<StoreData, StoreError> {
+        let mut data = StoreData::default();
+
+        let (categories, accounts) = (self.categories()?, self.accounts()?);
+        data.transactions = self.transactions("TRUE", &[], &categories, &accounts)?;
+        data.categories = categories.values().cloned().collect();
+        data.categories.sort_by_key(|category| category.id());
+        data.accounts = accounts.values().cloned().collect();
+        data.accounts.sort_by_key(|account| account.id());
+        let category = |id: Option<CategoryId>| id.and_then(|id| categories.get(&id).cloned());
+        let account = |id: Option<AccountId>| id.and_then(|id| accounts.get(&id).cloned());
+
+        let mut statement = self.connection.prepare("SELECT scope, amount FROM limits ORDER BY scope")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, parse::<Limit>(row, 1)?)))?;
+        for row in rows {
+            let (scope, amount) = row?;
+            let limit = Some(amount).filter(|amount| !amount.is_zero());
+            match parse_scope(&scope) {
+                Some(Scope::Default) => data.limits.set_initial(limit),
+                Some(Scope::Since(month)) => data.limits.set_from(month.to_string(), limit),
+                Some(Scope::Month(month)) => data.limits.set_month(month.to_string(), limit),
+                None => return Err(self.corrupted(format!("unknown limit scope `{}`", scope))),
+            }
+        }
+
+        let mut amounts: HashMap<CategoryId, Vec<(String, Limit)>> = HashMap::new();
+        let mut statement = self.connection.prepare("SELECT category_id, scope, amount FROM budget_amounts ORDER BY scope")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, CategoryId>(0)?, row.get::<_, String>(1)?, parse::<Limit>(row, 2)?)))?;
+        for row in rows {
+            let (id, scope, amount) = row?;
+            amounts.entry(id).or_default().push((scope, amount));
+        }
+        let mut statement = self.connection.prepare("SELECT category_id, period, rollover, since FROM budgets ORDER BY category_id")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, CategoryId>(0)?, parse_name(row, 1)?, parse_name(row, 2)?, row.get(3)?)))?;
+        for row in rows {
+            let (id, period, rollover, since) = row?;
+            let budget_category = category(Some(id)).ok_or_else(|| self.corrupted(format!("budget of unknown category {}", id)))?;
+            let amounts = amounts.remove(&id).unwrap_or_default();
+            let Some(initial) = amounts.iter().find(|(scope, _)| scope == DEFAULT_SCOPE).map(|(_, amount)| *amount) else {
+                return Err(self.corrupted(format!("budget of category {} has no amount", id)));
+            };
+            let mut budget = Budget::new(budget_category, NewBudget { rollover, since, ..NewBudget::new(initial, period) });
+            for (scope, amount) in amounts {
+                match parse_scope(&scope) {
+                    Some(Scope::Default) => {}
+                    Some(Scope::Since(month)) => budget.set_from(month.to_string(), amount),
+                    Some(Scope::Month(month)) => budget.set_month(month.to_string(), amount),
+                    None => return Err(self.corrupted(format!("unknown budget scope `{}`", scope))),
+                }
+            }
+            data.budgets.push(budget);
+        }
+        data.budgets.sort_by(|a, b| a.category().name().cmp(b.category().name()));
+
+        let mut statement = self.connection.prepare(
+            "SELECT month, from_category_id, to_category_id, amount FROM budget_transfers ORDER BY position",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, CategoryId>(1)?, row.get::<_, CategoryId>(2)?, parse::<Money>(row, 3)?))
+        })?;
+        for row in rows {
+            let (month, from, to, amount) = row?;
+            let (Some(from), Some(to)) = (category(Some(from)), category(Some(to))) else {
+                return Err(self.corrupted(format!("budget transfer between unknown categories {} and {}", from, to)));
+            };
+            data.budget_transfers.push(BudgetTransfer { month, from, to, amount });
+        }
+
+        if let Some(base) = setting(&self.connection, BASE_CURRENCY_SETTING)? {
+            data.exchange_rates.set_base(base.parse().map_err(|e: String| self.corrupted(e))?);
+        }
+        let mut statement = self.connection.prepare(
+            "SELECT date, from_currency, to_currency, rate FROM exchange_rates ORDER BY from_currency, to_currency, date",
+        )?;
+        let rows = statement.query_map([], |row| Ok((parse(row, 0)?, parse(row, 1)?, parse(row, 2)?, parse(row, 3)?)))?;
+        for row in rows {
+            let (date, from, to, rate) = row?;
+            data.exchange_rates.add(ExchangeRate::new(date, from, to, rate).map_err(|e| self.corrupted(e))?);
+        }
+
+        let mut statement = self.connection.prepare(
+            "SELECT id, description, amount, currency, category_id, kind, account_id, to_account_id,
+                    frequency, interval, start_date, end_date, day_of_month, last_occurrence
+             FROM recurring ORDER BY id",
+        )?;
+        data.recurring = statement
+            .query_map([], |row| {
+                let new = NewRecurring {
+                    currency: None,
+                    category: category(row.get(4)?),
+                    kind: parse_name(row, 5)?,
+                    account: account(row.get(6)?),
+                    to_account: account(row.get(7)?),
+                    interval: row.get(9)?,
+                    end: parse_optional(row, 11)?,
+                    day_of_month: parse_optional(row, 12)?,
+                    ..NewRecurring::new(row.get(1)?, parse(row, 2)?, parse_name(row, 8)?, parse(row, 10)?)
+                };
+                let mut recurring = RecurringTransaction::new(row.get(0)?, new, parse(row, 3)?);
+                if let Some(last) = parse_optional(row, 13)? {
+                    recurring.set_last_occurrence(last);
+                }
+                Ok(recurring)
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(data)
+    }
+
+    fn apply(&mut self, changes: &[Change], data: &StoreData) -> Result<(), StoreError> {
+        let tx = self.connection.transaction()?;
+        for change in changes {
+            match *change {
+                Change::TransactionSaved(id) => {
+                    if let Some(transaction) = data.transactions.iter().find(|t| t.id() == id) {
+                        save_transaction(&tx, transaction)?;
+                    }
+                }
+                Change::TransactionDeleted(id) => {
+                    tx.execute("DELETE FROM transactions WHERE id = ?1", [id])?;
+                    tx.execute("DELETE FROM transaction_tags WHERE transaction_id = ?1", [id])?;
+                }
+                Change::CategoryAdded(id) => {
+                    if let Some(category) = data.categories.iter().find(|c| c.id() == id) {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO categories (id, name) VALUES (?1, ?2)",
+                            params![category.id(), category.name()],
+                        )?;
+                    }
+                }
+                Change::CategoryDeleted(id) => {
+                    tx.execute("DELETE FROM categories WHERE id = ?1", [id])?;
+                }
+                Change::AccountAdded(id) => {
+                    if let Some(account) = data.accounts.iter().find(|a| a.id() == id) {
+                        tx.execute(
+                            "INSERT OR REPLACE INTO accounts (id, name) VALUES (?1, ?2)",
+                            params![account.id(), account.name()],
+                        )?;
+                    }
+                }
+                Change::AccountDeleted(id) => {
+                    tx.execute("DELETE FROM accounts WHERE id = ?1", [id])?;
+                }
+                Change::LimitChanged => {
+                    tx.execute("DELETE FROM limits", [])?;
+                    let limits = &data.limits;
+                    for (scope, limit) in scopes(limits.initial().map(Some), limits.changes(), limits.months()) {
+                        tx.execute(
+                            "INSERT INTO limits (scope, amount) VALUES (?1, ?2)",
+                            params![scope, limit.unwrap_or(Money::ZERO).to_string()],
+                        )?;
+                    }
+                }
+                Change::BudgetsChanged => {
+                    tx.execute("DELETE FROM budgets", [])?;
+                    tx.execute("DELETE FROM budget_amounts", [])?;
+                    tx.execute("DELETE FROM budget_transfers", [])?;
+                    for budget in &data.budgets {
+                        let id = budget.category().id();
+                        tx.execute(
+                            "INSERT INTO budgets (category_id, period, rollover, since) VALUES (?1, ?2, ?3, ?4)",
+                            params![id, name(&budget.period()), name(&budget.rollover()), budget.since()],
+                        )?;
+                        for (scope, amount) in scopes(Some(budget.initial_amount()), budget.changes(), budget.months()) {
+                            tx.execute(
+                                "INSERT INTO budget_amounts (category_id, scope, amount) VALUES (?1, ?2, ?3)",
+                                params![id, scope, amount.to_string()],
+                            )?;
+                        }
+                    }
+                    for (position, transfer) in (0_i64..).zip(&data.budget_transfers) {
+                        tx.execute(
+                            "INSERT INTO budget_transfers (position, month, from_category_id, to_category_id, amount)
+                             VALUES (?1, ?2, ?3, ?4, ?5)",
+                            params![position, transfer.month, transfer.from.id(), transfer.to.id(), transfer.amount.to_string()],
+                        )?;
+                    }
+                }
+                Change::ExchangeRatesChanged => {
+                    save_setting(&tx, BASE_CURRENCY_SETTING, data.exchange_rates.base().code())?;
+                    tx.execute("DELETE FROM exchange_rates", [])?;
+                    for rate in data.exchange_rates.list() {
+                        tx.execute(
+                            "INSERT INTO exchange_rates (date, from_currency, to_currency, rate) VALUES (?1, ?2, ?3, ?4)",
+                            params![rate.date().to_string(), rate.from().code(), rate.to().code(), rate.rate().to_string()],
+                        )?;
+                    }
+                }
+                Change::RecurringSaved(id) => {
+                    if let Some(recurring) = data.recurring.iter().find(|r| r.id() == id) {
+                        save_recurring(&tx, recurring)?;
+                    }
+                }
+                Change::RecurringDeleted(id) => {
+                    tx.execute("DELETE FROM recurring WHERE id = ?1", [id])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Lists the transactions matching the filter.
+    /// Dates, categories, kinds, accounts and tags are matched in SQL, using the indexes where there are some;
+    /// amounts are stored as decimal strings and are compared after reading.
+    fn list(&self, filter: &TransactionFilter) -> Result<Vec<Transaction>, StoreError> {
+        let mut conditions = vec!["TRUE".to_string()];
+        let mut values = vec![];
+        if let Some(from) = filter.from {
+            conditions.push("datetime >= ?".to_string());
+            values.push(SqlValue::Text(from.to_string()));
+        }
+        if let Some(next) = filter.to.and_then(|to| to.succ_opt()) {
+            conditions.push("datetime < ?".to_string());
+            values.push(SqlValue::Text(next.to_string()));
+        }
+        if let Some(category) = filter.category {
+            conditions.push("category_id = ?".to_string());
+            values.push(SqlValue::Integer(category.into()));
+        }
+        if let Some(kind) = filter.kind {
+            conditions.push("kind = ?".to_string());
+            values.push(SqlValue::Text(name(&kind)));
+        }
+        if let Some(account) = filter.account {
+            conditions.push("(account_id = ? OR to_account_id = ?)".to_string());
+            values.extend([SqlValue::Integer(account.into()), SqlValue::Integer(account.into())]);
+        }
+        for tag in &filter.tags {
+            conditions.push("id IN (SELECT transaction_id FROM transaction_tags WHERE tag = ?)".to_string());
+            values.push(SqlValue::Text(tag.clone()));
+        }
+        let (categories, accounts) = (self.categories()?, self.accounts()?);
+        let transactions = self.transactions(&conditions.join(" AND "), &values, &categories, &accounts)?;
+        Ok(select(transactions, filter))
+    }
+}
+
+/// Keeps a copy of a database that is about to be upgraded from an older schema version,
+/// e.g. `data.db.v1-20250401120000.bak`, like the JSON backend does for data files.
+fn backup(path: &str, version: i64) -> Result<(), StoreError> {
+    let backup_path = format!("{}.v{}-{}.bak", path, version, Utc::now().format("%Y%m%d%H%M%S"));
+    fs::copy(path, &backup_path).map_err(|e| StoreError::io(&backup_path, e))?;
+    Ok(())
+}
+
+/// Version 2 stores every field in a column of its own instead of JSON.
+/// Version 1 kept transactions and recurring transactions as JSON in a `data` column, with only some fields
+/// copied to columns, and budgets, budget transfers and exchange rates as JSON settings.
+/// Fields added to the models after the JSON was written get their defaults, like when loading old JSON files.
+fn v1_to_v2(connection: &Connection) -> Result<(), StoreError> {
+    connection.execute_batch(
+        "
+        DROP INDEX IF EXISTS transactions_datetime;
+        DROP INDEX IF EXISTS transactions_category;
+        ALTER TABLE transactions RENAME TO transactions_v1;
+        ALTER TABLE recurring RENAME TO recurring_v1;
+
+        CREATE TABLE transactions (
+            id INTEGER PRIMARY KEY,
+            datetime TEXT NOT NULL,
+            amount TEXT NOT NULL,
+            currency TEXT NOT NULL,
+            description TEXT NOT NULL,
+            category_id INTEGER,
+            kind TEXT NOT NULL,
+            account_id INTEGER,
+            to_account_id INTEGER,
+            external_ref TEXT
+        );
+        CREATE INDEX transactions_datetime ON transactions (datetime);
+        CREATE INDEX transactions_category ON transactions (category_id);
+        CREATE TABLE transaction_tags (
+            transaction_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (transaction_id, tag)
+        );
+        CREATE INDEX transaction_tags_tag ON transaction_tags (tag);
+        CREATE TABLE budgets (
+            category_id INTEGER PRIMARY KEY,
+            period TEXT NOT NULL,
+            rollover TEXT NOT NULL,
+            since TEXT
+        );
+        CREATE TABLE budget_amounts (
+            category_id INTEGER NOT NULL,
+            scope TEXT NOT NULL,
+            amount TEXT NOT NULL,
+            PRIMARY KEY (category_id, scope)
+        );
+        CREATE TABLE budget_transfers (
+            position INTEGER PRIMARY KEY,
+            month TEXT NOT NULL,
+            from_category_id INTEGER NOT NULL,
+            to_category_id INTEGER NOT NULL,
+            amount TEXT NOT NULL
+        );
+        CREATE TABLE exchange_rates (
+            date TEXT NOT NULL,
+            from_currency TEXT NOT NULL,
+            to_currency TEXT NOT NULL,
+            rate TEXT NOT NULL,
+            PRIMARY KEY (date, from_currency, to_currency)
+        );
+        CREATE TABLE recurring (
+            id INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            amount TEXT NOT NULL,
+            currency TEXT NOT NULL,
+            category_id INTEGER,
+            kind TEXT NOT NULL,
+            account_id INTEGER,
+            to_account_id INTEGER,
+            frequency TEXT NOT NULL,
+            interval INTEGER NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT,
+            day_of_month TEXT,
+            last_occurrence TEXT
+        );
+
+        INSERT INTO transactions
+        SELECT id, datetime, amount, currency, description, category_id,
+               COALESCE(json_extract(data, '$.kind'), 'expense'),
+               json_extract(data, '$.account.id'),
+               json_extract(data, '$.to_account.id'),
+               json_extract(data, '$.external_ref')
+        FROM transactions_v1;
+        INSERT INTO transaction_tags
+        SELECT transactions_v1.id, tag.value FROM transactions_v1, json_each(transactions_v1.data, '$.tags') AS tag;
+
+        INSERT INTO recurring
+        SELECT id,
+               json_extract(data, '$.description'),
+               json_extract(data, '$.amount'),
+               json_extract(data, '$.currency'),
+               json_extract(data, '$.category.id'),
+               COALESCE(json_extract(data, '$.kind'), 'expense'),
+               json_extract(data, '$.account.id'),
+               json_extract(data, '$.to_account.id'),
+               json_extract(data, '$.frequency'),
+               json_extract(data, '$.interval'),
+               json_extract(data, '$.start'),
+               json_extract(data, '$.end'),
+               COALESCE(json_extract(data, '$.day_of_month.day'), json_extract(data, '$.day_of_month')),
+               json_extract(data, '$.last_occurrence')
+        FROM recurring_v1;
+
+        INSERT INTO budgets
+        SELECT json_extract(budget.value, '$.category.id'),
+               json_extract(budget.value, '$.period'),
+               COALESCE(json_extract(budget.value, '$.rollover'), 'none'),
+               json_extract(budget.value, '$.since')
+        FROM settings, json_each(settings.value) AS budget
+        WHERE settings.key = 'budgets';
+        INSERT INTO budget_amounts
+        SELECT json_extract(budget.value, '$.category.id'), 'default', json_extract(budget.value, '$.amount')
+        FROM settings, json_each(settings.value) AS budget
+        WHERE settings.key = 'budgets';
+        INSERT INTO budget_amounts
+        SELECT json_extract(budget.value, '$.category.id'), 'since:' || change.key, change.value
+        FROM settings, json_each(settings.value) AS budget, json_each(budget.value, '$.changes') AS change
+        WHERE settings.key = 'budgets';
+        INSERT INTO budget_amounts
+        SELECT json_extract(budget.value, '$.category.id'), 'month:' || month.key, month.value
+        FROM settings, json_each(settings.value) AS budget, json_each(budget.value, '$.months') AS month
+        WHERE settings.key = 'budgets';
+        INSERT INTO budget_transfers
+        SELECT transfer.key,
+               json_extract(transfer.value, '$.month'),
+               json_extract(transfer.value, '$.from.id'),
+               json_extract(transfer.value, '$.to.id'),
+               json_extract(transfer.value, '$.amount')
+        FROM settings, json_each(settings.value) AS transfer
+        WHERE settings.key = 'budget_transfers';
+
+        INSERT INTO exchange_rates
+        SELECT json_extract(rate.value, '$.date'),
+               json_extract(rate.value, '$.from'),
+               json_extract(rate.value, '$.to'),
+               json_extract(rate.value, '$.rate')
+        FROM settings, json_each(settings.value, '$.rates') AS rate
+        WHERE settings.key = 'exchange_rates';
+        INSERT INTO settings (key, value)
+        SELECT 'base_currency', json_extract(value, '$.base') FROM settings
+        WHERE key = 'exchange_rates' AND json_extract(value, '$.base') IS NOT NULL;
+
+        DELETE FROM settings WHERE key IN ('budgets', 'budget_transfers', 'exchange_rates');
+        DROP TABLE transactions_v1;
+        DROP TABLE recurring_v1;
+        ",
+    )?;
+    Ok(())
+}
+
+/// Scope of an amount in the `limits` and `budget_amounts` tables.
+enum Scope<'a> {
+    Default,
+    Since(&'a str),
+    Month(&'a str),
+}
+
+/// Parses a scope of the `limits` and `budget_amounts` tables. It returns None for an unknown scope.
+fn parse_scope(scope: &str) -> Option<Scope<'_>> {
+    if scope == DEFAULT_SCOPE {
+        Some(Scope::Default)
+    } else if let Some(month) = scope.strip_prefix(SINCE_SCOPE) {
+        Some(Scope::Since(month))
+    } else {
+        scope.strip_prefix(MONTH_SCOPE).map(Scope::Month)
+    }
+}
+
+/// Returns the rows of a monthly history, as kept by limits and budgets, with their scopes.
+fn scopes<'a, T: Copy>(initial: Option<T>, changes: &'a BTreeMap<String, T>, months: &'a BTreeMap<String, T>) -> impl Iterator<Item = (String, T)> + 'a {
+    initial
+        .map(|value| (DEFAULT_SCOPE.to_string(), value))
+        .into_iter()
+        .chain(changes.iter().map(|(month, value)| (format!("{}{}", SINCE_SCOPE, month), *value)))
+        .chain(months.iter().map(|(month, value)| (format!("{}{}", MONTH_SCOPE, month), *value)))
+}
+
+/// Returns the name of an enum value in the JSON file, e.g. `expense`, which is how enums are stored.
+/// The store's enums are unit variants, which always serialize to their name.
+fn name(value: &impl Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// Reads an enum stored by its name.
+fn parse_name<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let name: String = row.get(index)?;
+    serde_json::from_value(Value::String(name)).map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, e.into()))
+}
+
+/// Reads a text column holding a value like an amount, a currency or a date.
+fn parse<T: FromStr>(row: &Row, index: usize) -> rusqlite::Result<T>
+where
+    T::Err: Into<Box<dyn Error + Send + Sync>>,
+{
+    let value: String = row.get(index)?;
+    value.parse().map_err(|e: T::Err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, e.into()))
+}
+
+/// Reads a nullable text column holding a value like a date.
+fn parse_optional<T: FromStr>(row: &Row, index: usize) -> rusqlite::Result<Option<T>>
+where
+    T::Err: Into<Box<dyn Error + Send + Sync>>,
+{
+    match row.get::<_, Option<String>>(index)? {
+        Some(_) => parse(row, index).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Inserts or replaces a transaction row, together with its tags.
+fn save_transaction(connection: &Connection, transaction: &Transaction) -> Result<(), StoreError> {
+    connection.execute(
+        "INSERT OR REPLACE INTO transactions
+             (id, datetime, amount, currency, description, category_id, kind, account_id, to_account_id, external_ref)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            transaction.id(),
+            transaction.datetime().to_rfc3339_opts(SecondsFormat::Nanos, true),
+            transaction.amount().to_string(),
+            transaction.currency().code(),
+            transaction.description(),
+            transaction.category().map(|c| c.id()),
+            name(&transaction.kind()),
+            transaction.account().map(|a| a.id()),
+            transaction.to_account().map(|a| a.id()),
+            transaction.external_ref(),
+        ],
+    )?;
+    connection.execute("DELETE FROM transaction_tags WHERE transaction_id = ?1", [transaction.id()])?;
+    for tag in transaction.tags() {
+        connection.execute(
+            "INSERT INTO transaction_tags (transaction_id, tag) VALUES (?1, ?2)",
+            params![transaction.id(), tag],
+        )?;
+    }
+    Ok(())
+}
+
+/// Inserts or replaces a recurring transaction row.
+fn save_recurring(connection: &Connection, recurring: &RecurringTransaction) -> Result<(), StoreError> {
+    connection.execute(
+        "INSERT OR REPLACE INTO recurring
+             (id, description, amount, currency, category_id, kind, account_id, to_account_id,
+              frequency, interval, start_date, end_date, day_of_month, last_occurrence)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            recurring.id(),
+            recurring.description(),
+            recurring.amount().to_string(),
+            recurring.currency().code(),
+            recurring.category().map(|c| c.id()),
+            name(&recurring.kind()),
+            recurring.account().map(|a| a.id()),
+            recurring.to_account().map(|a| a.id()),
+            name(&recurring.frequency()),
+            recurring.interval(),
+            recurring.start().to_string(),
+            recurring.end().map(|date| date.to_string()),
+            recurring.day_of_month().map(|day| day.to_string()),
+            recurring.last_occurrence().map(|date| date.to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Reads a value from the `settings` table.
+fn setting(connection: &Connection, key: &str) -> Result<Option<String>, StoreError> {
+    let value = connection
+        .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()?;
+    Ok(value)
+}
+
+/// Writes a value to the `settings` table.
+fn save_setting(connection: &Connection, key: &str, value: &str) -> Result<(), StoreError> {
+    connection.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{BudgetPeriod, NewBudget, Rollover};
+    use crate::models::{NewTransaction, TransactionKind};
+    use chrono::Utc;
+    use crate::recurring::{DayOfMonth, Frequency};
+    use crate::store::Store;
+
+    #[test]
+    fn test_sqlite_round_trip() {
+        let temp_file = "test_data_sqlite_1.db";
+        let mut store = Store::with_backend(Box::new(SqliteBackend::open(temp_file).unwrap())).unwrap();
+        let category_id = store.add_category("Test category").unwrap();
+        let category = store.get_category(category_id).ok();
+        store.add_transaction(NewTransaction { category, ..NewTransaction::new("Test transaction 1".to_string(), "10.5".parse().unwrap()) }).unwrap();
+        let id = store.add_transaction(NewTransaction::new("Test transaction 2".to_string(), "20".parse().unwrap())).unwrap();
+        store.set_limit("100".parse().unwrap()).unwrap();
+        store.set_budget(category_id, NewBudget::new("40".parse().unwrap(), BudgetPeriod::Weekly)).unwrap();
+        store.add_account("Checking").unwrap();
+        store.delete_transaction(id).unwrap();
+        drop(store);
+
+        let store = Store::with_backend(Box::new(SqliteBackend::open(temp_file).unwrap())).unwrap();
+        let transactions = store.list_transactions(None);
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount(), "10.5".parse().unwrap());
+        assert_eq!(transactions[0].category().unwrap().name(), "Test category");
+        assert_eq!(store.list_categories().len(), 1);
+        assert_eq!(store.limit(), Some("100".parse().unwrap()));
+        assert_eq!(store.budget(category_id).unwrap().period(), BudgetPeriod::Weekly);
+        assert_eq!(store.list_accounts()[0].name(), "Checking");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_sqlite_typed_columns() {
+        let temp_file = "test_data_sqlite_4.db";
+        let mut store = Store::with_backend(Box::new(SqliteBackend::open(temp_file).unwrap())).unwrap();
+        let food = store.add_category("Food").unwrap();
+        let fun = store.add_category("Fun").unwrap();
+        let checking = store.add_account("Checking").unwrap();
+        let savings = store.add_account("Savings").unwrap();
+        store.add_transaction(NewTransaction {
+            currency: Some("EUR".parse().unwrap()),
+            category: store.get_category(food).ok(),
+            external_ref: Some("ofx:1:FITID".to_string()),
+            tags: ["trip".to_string(), "work".to_string()].into(),
+            ..NewTransaction::new("Lunch".to_string(), "12.345".parse().unwrap())
+        }).unwrap();
+        store.add_transaction(NewTransaction {
+            kind: TransactionKind::Transfer,
+            account: store.get_account(checking).ok(),
+            to_account: store.get_account(savings).ok(),
+            ..NewTransaction::new("Savings".to_string(), "100".parse().unwrap())
+        }).unwrap();
+        store.set_budget(food, NewBudget { rollover: Rollover::Full, since: Some("2025-01".to_string()), ..NewBudget::new("300".parse().unwrap(), BudgetPeriod::Monthly) }).unwrap();
+        store.set_budget(food, NewBudget::new("350".parse().unwrap(), BudgetPeriod::Monthly)).unwrap();
+        store.set_month_budget(food, "2025-02", "20".parse().unwrap()).unwrap();
+        store.set_budget(fun, NewBudget::new("50".parse().unwrap(), BudgetPeriod::Monthly)).unwrap();
+        store.transfer_budget("2025-03", food, fun, "25".parse().unwrap()).unwrap();
+        store.set_base_currency("EUR".parse().unwrap()).unwrap();
+        let date = "2025-01-01".parse().unwrap();
+        store.add_exchange_rates(vec![ExchangeRate::new(date, "USD".parse().unwrap(), "EUR".parse().unwrap(), "0.9".parse().unwrap()).unwrap()]).unwrap();
+        store.add_recurring(NewRecurring {
+            end: Some("2025-12-31".parse().unwrap()),
+            day_of_month: Some(DayOfMonth::Last),
+            interval: 3,
+            ..NewRecurring::new("Rent".to_string(), "900".parse().unwrap(), Frequency::Monthly, date)
+        }).unwrap();
+        let transactions: Vec<Transaction> = store.list_transactions(None).into_iter().cloned().collect();
+        let budgets = store.budgets().to_vec();
+        let transfers = store.budget_transfers().to_vec();
+        let recurring: Vec<RecurringTransaction> = store.list_recurring().into_iter().cloned().collect();
+        drop(store);
+
+        let backend = SqliteBackend::open(temp_file).unwrap();
+        let (amount, kind, external_ref): (String, String, String) = backend.connection
+            .query_row("SELECT amount, kind, external_ref FROM transactions WHERE id = 1", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap();
+        assert_eq!((amount.as_str(), kind.as_str(), external_ref.as_str()), ("12.345", "expense", "ofx:1:FITID"));
+        let tagged: i64 = backend.connection
+            .query_row("SELECT COUNT(*) FROM transaction_tags WHERE tag = 'work'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tagged, 1);
+        let store = Store::with_backend(Box::new(backend)).unwrap();
+        assert_eq!(store.list_transactions(None).into_iter().cloned().collect::<Vec<_>>(), transactions);
+        assert_eq!(store.budgets(), budgets);
+        assert_eq!(store.budget(food).unwrap().amount_for("2025-02"), "20".parse().unwrap());
+        assert_eq!(store.budget_transfers(), transfers);
+        assert_eq!(store.list_recurring().into_iter().cloned().collect::<Vec<_>>(), recurring);
+        assert_eq!(store.base_currency().code(), "EUR");
+        assert_eq!(store.exchange_rates().rate(&"USD".parse().unwrap(), &"EUR".parse().unwrap(), date), Some("0.9".parse().unwrap()));
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_sqlite_list() {
+        let temp_file = "test_data_sqlite_6.db";
+        let mut store = Store::with_backend(Box::new(SqliteBackend::open(temp_file).unwrap())).unwrap();
+        let food = store.add_category("Food").unwrap();
+        let checking = store.add_account("Checking").unwrap();
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        for (description, amount, datetime, tags) in [
+            ("Lunch", "12", "2025-04-01T12:00:00Z", vec!["work"]),
+            ("Dinner", "40", "2025-04-30T23:30:00Z", vec!["work", "trip"]),
+            ("Breakfast", "8", "2025-05-01T08:00:00Z", vec!["work"]),
+        ] {
+            store.add_transaction(NewTransaction {
+                category: store.get_category(food).ok(),
+                account: store.get_account(checking).ok(),
+                datetime: Some(at(datetime)),
+                tags: tags.into_iter().map(str::to_string).collect(),
+                ..NewTransaction::new(description.to_string(), amount.parse().unwrap())
+            }).unwrap();
+        }
+        store.add_transaction(NewTransaction { datetime: Some(at("2025-04-15T12:00:00Z")), ..NewTransaction::new("Other".to_string(), "5".parse().unwrap()) }).unwrap();
+        let filters = [
+            TransactionFilter::default(),
+            TransactionFilter { from: Some("2025-04-01".parse().unwrap()), to: Some("2025-04-30".parse().unwrap()), ..Default::default() },
+            TransactionFilter { category: Some(food), min_amount: Some("10".parse().unwrap()), ..Default::default() },
+            TransactionFilter { account: Some(checking), tags: ["trip".to_string()].into(), ..Default::default() },
+            TransactionFilter { kind: Some(TransactionKind::Income), ..Default::default() },
+        ];
+        let expected: Vec<Vec<Transaction>> = filters.iter().map(|filter| store.filter_transactions(filter).into_iter().cloned().collect()).collect();
+        drop(store);
+
+        let backend = SqliteBackend::open(temp_file).unwrap();
+        for (filter, expected) in filters.iter().zip(&expected) {
+            assert_eq!(&backend.list(filter).unwrap(), expected);
+        }
+        assert_eq!(expected.iter().map(Vec::len).collect::<Vec<_>>(), [4, 3, 2, 1, 0]);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_sqlite_upgrade_from_v1() {
+        let temp_file = "test_data_sqlite_5.db";
+        let connection = Connection::open(temp_file).unwrap();
+        connection.execute_batch(r#"
+            CREATE TABLE categories (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+            CREATE TABLE transactions (
+                id INTEGER PRIMARY KEY, datetime TEXT NOT NULL, category_id INTEGER, amount TEXT NOT NULL,
+                currency TEXT NOT NULL, description TEXT NOT NULL, data TEXT NOT NULL
+            );
+            CREATE INDEX transactions_datetime ON transactions (datetime);
+            CREATE INDEX transactions_category ON transactions (category_id);
+            CREATE TABLE limits (scope TEXT PRIMARY KEY, amount TEXT NOT NULL);
+            CREATE TABLE recurring (id INTEGER PRIMARY KEY, data TEXT NOT NULL);
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO categories VALUES (1, 'Food'), (2, 'Fun');
+            INSERT INTO accounts VALUES (1, 'Checking');
+            INSERT INTO transactions VALUES
+                (1, '2025-04-01T10:00:00.000000000Z', 1, '10.5', 'USD', 'Lunch',
+                 '{"id":1,"amount":"10.5","currency":"USD","description":"Lunch","datetime":"2025-04-01T10:00:00Z","category":{"id":1,"name":"Food"}}'),
+                (2, '2025-04-02T10:00:00.000000000Z', NULL, '2000', 'USD', 'Salary',
+                 '{"id":2,"amount":"2000","currency":"USD","description":"Salary","datetime":"2025-04-02T10:00:00Z","category":null,"kind":"income","account":{"id":1,"name":"Checking"},"external_ref":"ofx:1:A","tags":["work"]}');
+            INSERT INTO limits VALUES ('default', '500');
+            INSERT INTO recurring VALUES (1,
+                '{"id":1,"description":"Rent","amount":"900","currency":"USD","category":null,"frequency":"monthly","interval":1,"start":"2025-01-31","end":null,"day_of_month":{"day":31},"last_occurrence":"2025-03-31"}');
+            INSERT INTO settings VALUES
+                ('budgets', '[{"category":{"id":1,"name":"Food"},"amount":"300","period":"monthly","rollover":"full","since":"2025-01"},{"category":{"id":2,"name":"Fun"},"amount":"50","period":"weekly"}]'),
+                ('budget_transfers', '[{"month":"2025-04","from":{"id":1,"name":"Food"},"to":{"id":2,"name":"Fun"},"amount":"20"}]'),
+                ('exchange_rates', '{"base":"USD","rates":[{"date":"2025-01-01","from":"EUR","to":"USD","rate":"1.1"}]}');
+            PRAGMA user_version = 1;
+        "#).unwrap();
+        drop(connection);
+
+        let store = Store::with_backend(Box::new(SqliteBackend::open(temp_file).unwrap())).unwrap();
+        let transactions = store.list_transactions(None);
+        assert_eq!(transactions[0].category().unwrap().name(), "Food");
+        assert_eq!(transactions[0].kind(), TransactionKind::Expense);
+        assert_eq!(transactions[1].kind(), TransactionKind::Income);
+        assert_eq!(transactions[1].account().unwrap().name(), "Checking");
+        assert_eq!(transactions[1].external_ref(), Some("ofx:1:A"));
+        assert!(transactions[1].has_tag("work"));
+        assert_eq!(store.limit_for("2025-04").unwrap(), Some("500".parse().unwrap()));
+        assert_eq!(store.budget(1).unwrap().rollover(), Rollover::Full);
+        assert_eq!(store.budget(1).unwrap().amount_for("2025-04"), "300".parse().unwrap());
+        assert_eq!(store.budget(2).unwrap().rollover(), Rollover::None);
+        assert_eq!(store.budget_transfers()[0].to.name(), "Fun");
+        assert_eq!(store.exchange_rates().list()[0].rate(), "1.1".parse().unwrap());
+        let recurring = store.list_recurring();
+        assert_eq!(recurring[0].kind(), TransactionKind::Expense);
+        assert_eq!(recurring[0].day_of_month(), Some(DayOfMonth::Day(31)));
+        assert_eq!(recurring[0].last_occurrence(), Some("2025-03-31".parse().unwrap()));
+        drop(store);
+
+        let backend = SqliteBackend::open(temp_file).unwrap();
+        let version: i64 = backend.connection.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SQLITE_SCHEMA_VERSION);
+        drop(backend);
+        let backups: Vec<_> = fs::read_dir(".")
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("test_data_sqlite_5.db.v1-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        fs::remove_file(&backups[0]).expect("Unable to remove file");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_sqlite_delete_category() {
+        let temp_file = "test_data_sqlite_2.db";
+        let mut store = Store::with_backend(Box::new(SqliteBackend::open(temp_file).unwrap())).unwrap();
+        let category_id = store.add_category("Test category").unwrap();
+        let category = store.get_category(category_id).ok();
+        store.add_transaction(NewTransaction { category, ..NewTransaction::new("Test transaction".to_string(), "10".parse().unwrap()) }).unwrap();
+        store.delete_category(category_id).unwrap();
+        drop(store);
+
+        let backend = SqliteBackend::open(temp_file).unwrap();
+        let uncategorized: i64 = backend.connection
+            .query_row("SELECT COUNT(*) FROM transactions WHERE category_id IS NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(uncategorized, 1);
+        let store = Store::with_backend(Box::new(backend)).unwrap();
+        assert_eq!(store.list_categories().len(), 0);
+        assert_eq!(store.list_transactions(None)[0].category(), None);
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_sqlite_import() {
+        let temp_file = "test_data_sqlite_3.db";
+        let mut data = StoreData::default();
+        data.categories.push(Category::new(1, "Test category".to_string()));
+        data.transactions.push(Transaction::new(1, "10".parse().unwrap(), "EUR".parse().unwrap(), "Test transaction".to_string(), None));
+        data.exchange_rates.set_base("EUR".parse().unwrap());
+
+        let mut backend = SqliteBackend::open(temp_file).unwrap();
+        assert!(backend.is_empty().unwrap());
+        backend.import(&data).unwrap();
+        assert!(!backend.is_empty().unwrap());
+        let store = Store::with_backend(Box::new(backend)).unwrap();
+        assert_eq!(store.list_transactions(None).len(), 1);
+        assert_eq!(store.base_currency().code(), "EUR");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+}
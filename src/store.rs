@@ -1,302 +1,1050 @@
-use super::models::{Transaction, Category, TransactionId, CategoryId, Limit};
-use serde::{Serialize, Deserialize};
-use std::{fs, vec};
-use std::io::Write;
-use std::path::Path;
-
-/// Store struct to manage transactions and categories
-/// It contains methods to add, delete, list transactions and categories
-/// and to set a spending limit.
-/// It also handles the persistence of data to a JSON file.
-/// The data is stored in a JSON file, and the struct is initialized
-/// with the data from the file if it exists.
-/// If the file does not exist, it creates an empty file and initializes the struct.
-#[derive(Debug, Serialize, Deserialize)]
+use super::budget::{Budget, BudgetPeriod, BudgetTransfer, LimitHistory, NewBudget, Rollover};
+use super::models::{parse_tag, Transaction, TransactionKind, NewTransaction, TransactionUpdate, TransactionFilter, Account, Category, TransactionId, AccountId, CategoryId, Limit, Money};
+use super::currency::{Currency, ExchangeRate, ExchangeRates};
+use super::error::StoreError;
+use super::recurring::{NewRecurring, RecurringId, RecurringTransaction};
+use super::storage::{Change, JsonFileBackend, StorageBackend, StoreData};
+use super::summary::parse_month;
+use chrono::NaiveDate;
+use chrono::prelude::Utc;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Store struct to manage transactions, categories and accounts
+/// It contains methods to add, delete, list transactions, categories and accounts
+/// and to set a spending limit and category budgets.
+/// All data is kept in memory; it is loaded from a storage backend when the
+/// store is created, and every modification is passed on to that backend.
+#[derive(Debug)]
 pub struct Store {
-    transactions: Vec<Transaction>,
+    data: StoreData,
 
-    #[serde(skip)]
     max_transaction_id: TransactionId,
 
-    categories: Vec<Category>,
-
-    #[serde(skip)]
     max_category_id: CategoryId,
 
-    limit: Option<Limit>,
+    max_account_id: AccountId,
 
-    #[serde(skip)]
-    path: String
+    max_recurring_id: RecurringId,
+
+    backend: Box<dyn StorageBackend>,
 }
 
 impl Store {
-    /// Creates a new Store instance.
+    /// Creates a new Store instance backed by a JSON file.
     /// If a file path is provided, it will be used to load the data.
     /// If no file path is provided, it will default to "data/data.json".
-    pub fn new(file_path: Option<&str>) -> Self {
-        let path = if let Some(p) = file_path  {
-            p 
-        } else {
-            "data/data.json"            
-        };
-        if let Ok(is_exists) = fs::exists(path) {
-            if is_exists {
-                let data = fs::read_to_string(path).expect("Unable to read file");
-                let mut store: Store = serde_json::from_str(&data).expect("Unable to parse JSON");
-                store.max_transaction_id = store.transactions.iter().map(|i| i.id()).max().unwrap_or(0);
-                store.max_category_id = store.categories.iter().map(|i| i.id()).max().unwrap_or(0);
-                store.path = path.to_string();
-                store
-            } else {
-                let empty_transactions: Vec<Transaction> = vec![];
-                let empty_categories: Vec<Category> = vec![];
-                let s = Store {
-                    transactions: empty_transactions,
-                    max_transaction_id: 0,
-                    categories: empty_categories,
-                    max_category_id: 0,
-                    limit: None,
-                    path: path.to_string()
-                };
-                s.persist();
-                s
-            }
-        } else {
-            panic!("Can't check existence of file `data.json`");
-        }
+    /// If the file does not exist, it creates an empty file.
+    /// The file stays locked against other processes for the lifetime of the store.
+    pub fn new(file_path: Option<&str>) -> Result<Self, StoreError> {
+        let path = file_path.unwrap_or("data/data.json");
+        let backend = JsonFileBackend::open(path)?;
+        Store::with_backend(Box::new(backend))
     }
 
-    /// Persists the current state of the Store to a JSON file.
-    fn persist(&self) {
-        let json = serde_json::to_string_pretty(&self).expect("Unable to write JSON");
+    /// Creates a new Store instance on top of any storage backend.
+    pub fn with_backend(mut backend: Box<dyn StorageBackend>) -> Result<Self, StoreError> {
+        let data = backend.load()?;
+        let max_transaction_id = data.transactions.iter().map(|i| i.id()).max().unwrap_or(0);
+        let max_category_id = data.categories.iter().map(|i| i.id()).max().unwrap_or(0);
+        let max_account_id = data.accounts.iter().map(|i| i.id()).max().unwrap_or(0);
+        let max_recurring_id = data.recurring.iter().map(|i| i.id()).max().unwrap_or(0);
+        Ok(Store {
+            data,
+            max_transaction_id,
+            max_category_id,
+            max_account_id,
+            max_recurring_id,
+            backend,
+        })
+    }
 
-        let path = Path::new(&self.path);
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).expect("Unable to create directory");
-        }
-        let mut file = fs::File::create(&self.path).expect("Unable to create file");
-        file.write_all(json.as_bytes()).expect("Unable to write file");
+    /// Passes the changes made to the in-memory data on to the storage backend.
+    fn persist(&mut self, changes: &[Change]) -> Result<(), StoreError> {
+        self.backend.apply(changes, &self.data)
     }
 
     /// Adds a new transaction to the store.
+    /// If no currency is provided, the base currency of the store is used.
+    /// If no datetime is provided, the transaction is dated now.
     /// It returns the ID of the newly created transaction.
-    pub fn add_transaction(&mut self, description: String, amount: f64, category: Option<Category>) -> TransactionId{
-        let transaction = Transaction::new(
+    pub fn add_transaction(&mut self, new: NewTransaction) -> Result<TransactionId, StoreError> {
+        let ids = self.add_transactions(vec![new])?;
+        Ok(ids[0])
+    }
+
+    /// Adds several transactions at once, e.g. from an imported statement.
+    /// The changes are persisted in a single write.
+    /// It returns the IDs of the newly created transactions.
+    pub fn add_transactions(&mut self, mut new: Vec<NewTransaction>) -> Result<Vec<TransactionId>, StoreError> {
+        // Refunds and salaries are income rather than negative expenses.
+        if new.iter().any(|new| new.amount.is_negative()) {
+            return Err(StoreError::InvalidInput("transaction amount must not be negative".to_string()));
+        }
+        for new in &mut new {
+            validate_accounts(new.kind, new.account.as_ref(), new.to_account.as_ref())?;
+            new.tags = parse_tags(&new.tags)?;
+        }
+        let ids: Vec<TransactionId> = new.into_iter().map(|new| self.push_transaction(new)).collect();
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+        let changes: Vec<Change> = ids.iter().map(|&id| Change::TransactionSaved(id)).collect();
+        self.persist(&changes)?;
+        Ok(ids)
+    }
+
+    /// Adds a transaction to the in-memory data without persisting it.
+    /// It returns the ID of the new transaction.
+    fn push_transaction(&mut self, new: NewTransaction) -> TransactionId {
+        let mut transaction = Transaction::new(
             self.max_transaction_id + 1,
-            amount,
-            description,
-            category
+            new.amount,
+            new.currency.unwrap_or_else(|| self.data.exchange_rates.base().clone()),
+            new.description,
+            new.category
         );
-        self.transactions.push(transaction);
+        if let Some(datetime) = new.datetime {
+            transaction.set_datetime(datetime);
+        }
+        transaction.set_external_ref(new.external_ref);
+        transaction.set_kind(new.kind);
+        transaction.set_account(new.account);
+        transaction.set_to_account(new.to_account);
+        transaction.set_tags(new.tags);
+        self.data.transactions.push(transaction);
         self.max_transaction_id += 1;
-        self.persist();
         self.max_transaction_id
     }
 
     /// Deletes a transaction from the store.
-    pub fn delete_transaction(&mut self, id: TransactionId) {
-        if let Some(pos) = self.transactions.iter().position(|transaction| transaction.id() == id) {
-            self.transactions.remove(pos);
-            self.persist();
+    /// If the transaction is not found, it returns an error.
+    pub fn delete_transaction(&mut self, id: TransactionId) -> Result<(), StoreError> {
+        let pos = self.data.transactions
+            .iter()
+            .position(|transaction| transaction.id() == id)
+            .ok_or(StoreError::TransactionNotFound(id))?;
+        self.data.transactions.remove(pos);
+        self.persist(&[Change::TransactionDeleted(id)])
+    }
+
+    /// Updates an existing transaction, keeping its ID.
+    /// If the transaction is not found, it returns an error.
+    /// It returns the transaction before and after the update, or None if the update
+    /// did not change anything; unchanged transactions are not persisted.
+    pub fn update_transaction(&mut self, id: TransactionId, update: TransactionUpdate) -> Result<Option<(Transaction, Transaction)>, StoreError> {
+        if update.description.as_deref().is_some_and(|description| description.trim().is_empty()) {
+            return Err(StoreError::InvalidInput("transaction description must not be empty".to_string()));
+        }
+        if update.amount.is_some_and(|amount| amount.is_negative()) {
+            return Err(StoreError::InvalidInput("transaction amount must not be negative".to_string()));
+        }
+        let pos = self.data.transactions
+            .iter()
+            .position(|transaction| transaction.id() == id)
+            .ok_or(StoreError::TransactionNotFound(id))?;
+        let before = self.data.transactions[pos].clone();
+        // The update is applied to a copy, so an invalid result leaves the transaction as it was.
+        let mut after = before.clone();
+        if let Some(description) = update.description {
+            after.set_description(description);
+        }
+        if let Some(amount) = update.amount {
+            after.set_amount(amount);
+        }
+        if let Some(category) = update.category {
+            after.set_category(category);
+        }
+        if let Some(date) = update.date {
+            let time = after.datetime().time();
+            after.set_datetime(date.and_time(time).and_utc());
+        }
+        if let Some(kind) = update.kind {
+            after.set_kind(kind);
+            if kind != TransactionKind::Transfer {
+                after.set_to_account(None);
+            }
+        }
+        if let Some(account) = update.account {
+            after.set_account(account);
+        }
+        if let Some(to_account) = update.to_account {
+            after.set_to_account(to_account);
+        }
+        let remove_tags = parse_tags(&update.remove_tags)?;
+        let tags = after.tags().union(&parse_tags(&update.add_tags)?).filter(|tag| !remove_tags.contains(*tag)).cloned().collect();
+        after.set_tags(tags);
+        if after == before {
+            return Ok(None);
         }
+        validate_accounts(after.kind(), after.account().as_ref(), after.to_account().as_ref())?;
+        self.data.transactions[pos] = after.clone();
+        self.persist(&[Change::TransactionSaved(id)])?;
+        Ok(Some((before, after)))
     }
 
     /// Lists all transactions in the store.
     /// If a category is provided, it filters the transactions by that category, otherwise it lists all transactions.
     /// The transactions are sorted by their datetime in ascending order.
     pub fn list_transactions(&self, category: Option<Category>) -> Vec<&Transaction> {
-        let mut transactions: Vec<&Transaction> = if let Some(_) = category {
-            self.transactions.iter().filter(|&transaction| transaction.category() == category).collect()
+        let mut transactions: Vec<&Transaction> = if category.is_some() {
+            self.data.transactions.iter().filter(|&transaction| transaction.category() == category).collect()
         } else {
-            self.transactions.iter().collect()
+            self.data.transactions.iter().collect()
         };
-        transactions.sort_by(|a, b| a.datetime().cmp(&b.datetime()));
+        transactions.sort_by_key(|transaction| transaction.datetime());
         transactions
     }
 
-    /// Returns a clone of the category by its ID.
-    /// If the category is not found, it returns None.
-    pub fn get_category(&self, id: CategoryId) -> Option<Category> {
-        if let Some(cat) = self.categories.iter().find(|&cat| cat.id() == id) {
-            Some(cat.clone())
-        } else {
-            None    
+    /// Returns the transactions matching the filter, sorted by date.
+    pub fn filter_transactions(&self, filter: &TransactionFilter) -> Vec<&Transaction> {
+        let mut transactions: Vec<&Transaction> = self.data.transactions.iter().filter(|transaction| filter.matches(transaction)).collect();
+        transactions.sort_by_key(|transaction| transaction.datetime());
+        transactions
+    }
+
+    /// Lists the tags in use, with the number of transactions carrying each, sorted by tag.
+    pub fn list_tags(&self) -> Vec<(&str, usize)> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in self.data.transactions.iter().flat_map(|transaction| transaction.tags()) {
+            *counts.entry(tag).or_default() += 1;
+        }
+        counts.into_iter().collect()
+    }
+
+    /// Renames a tag on all transactions carrying it.
+    /// If a transaction already has the new tag, the two are merged, so renaming
+    /// `lisbon` to `trip-lisbon` also merges the two tags.
+    /// If no transaction carries the tag, it returns an error.
+    /// It returns the number of transactions that were changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize, StoreError> {
+        let from = parse_tag(from).map_err(StoreError::InvalidInput)?;
+        let to = parse_tag(to).map_err(StoreError::InvalidInput)?;
+        let mut changes = vec![];
+        for transaction in self.data.transactions.iter_mut().filter(|transaction| transaction.has_tag(&from)) {
+            let mut tags = transaction.tags().clone();
+            tags.remove(&from);
+            tags.insert(to.clone());
+            transaction.set_tags(tags);
+            changes.push(Change::TransactionSaved(transaction.id()));
+        }
+        if changes.is_empty() {
+            return Err(StoreError::TagNotFound(from));
         }
+        self.persist(&changes)?;
+        Ok(changes.len())
+    }
+
+    /// Returns a clone of the category by its ID.
+    /// If the category is not found, it returns an error.
+    pub fn get_category(&self, id: CategoryId) -> Result<Category, StoreError> {
+        self.data.categories
+            .iter()
+            .find(|&cat| cat.id() == id)
+            .cloned()
+            .ok_or(StoreError::CategoryNotFound(id))
+    }
+
+    /// Returns a clone of the category with the given name, ignoring case.
+    pub fn find_category(&self, name: &str) -> Option<Category> {
+        self.data.categories
+            .iter()
+            .find(|&cat| cat.name().trim().eq_ignore_ascii_case(name.trim()))
+            .cloned()
     }
 
     /// Adds a new category to the store.
-    /// It returns the ID of the newly created category.    
-    pub fn add_category(&mut self, name: &str) -> CategoryId {
+    /// It returns the ID of the newly created category.
+    /// The name must not be empty.
+    pub fn add_category(&mut self, name: &str) -> Result<CategoryId, StoreError> {
+        if name.trim().is_empty() {
+            return Err(StoreError::InvalidInput("category name must not be empty".to_string()));
+        }
         let category = Category::new(
             self.max_category_id + 1,
             name.to_string()
         );
-        self.categories.push(category);
+        self.data.categories.push(category);
         self.max_category_id += 1;
-        self.persist();
-        self.max_category_id
+        self.persist(&[Change::CategoryAdded(self.max_category_id)])?;
+        Ok(self.max_category_id)
     }
 
-    /// Deletes a category from the store.
-    /// If the category is used in any transaction, it will be removed from that transaction.
-    /// If the category is not found, it does nothing.
-    pub fn delete_category(&mut self, id: CategoryId) {
-        if let Some(pos) = self.categories.iter().position(|cat| cat.id() == id) {
-            self.categories.remove(pos);
-        }
-        for transaction in self.transactions.iter_mut() {
+    /// Deletes a category from the store, along with its budget and the budget transferred to or from it.
+    /// If the category is used in any transaction or recurring transaction, it will be removed from it.
+    /// If the category is not found, it returns an error.
+    pub fn delete_category(&mut self, id: CategoryId) -> Result<(), StoreError> {
+        let pos = self.data.categories
+            .iter()
+            .position(|cat| cat.id() == id)
+            .ok_or(StoreError::CategoryNotFound(id))?;
+        self.data.categories.remove(pos);
+        let mut changes = vec![Change::CategoryDeleted(id)];
+        for transaction in self.data.transactions.iter_mut() {
             if transaction.category().is_some() && transaction.category().unwrap().id() == id {
                 transaction.remove_category();
+                changes.push(Change::TransactionSaved(transaction.id()));
             }
         }
-        self.persist();
+        let budgets = self.data.budgets.len() + self.data.budget_transfers.len();
+        self.data.budgets.retain(|budget| budget.category().id() != id);
+        self.data.budget_transfers.retain(|transfer| transfer.from.id() != id && transfer.to.id() != id);
+        if self.data.budgets.len() + self.data.budget_transfers.len() != budgets {
+            changes.push(Change::BudgetsChanged);
+        }
+        for recurring in self.data.recurring.iter_mut() {
+            if recurring.category().is_some_and(|category| category.id() == id) {
+                recurring.remove_category();
+                changes.push(Change::RecurringSaved(recurring.id()));
+            }
+        }
+        self.persist(&changes)
     }
 
     /// Lists all categories in the store.
     pub fn list_categories(&self) -> Vec<&Category> {
-        self.categories.iter().collect()
+        self.data.categories.iter().collect()
     }
 
-    /// Sets a spending limit for the current month.
-    /// If the limit is set to 0, it removes the limit.
-    pub fn set_limit(&mut self, limit: f64) {
-        if limit > 0.0 {
-            self.limit = Some(limit);
-        } else {
-            self.limit = None;
+    /// Returns a clone of the account by its ID.
+    /// If the account is not found, it returns an error.
+    pub fn get_account(&self, id: AccountId) -> Result<Account, StoreError> {
+        self.data.accounts
+            .iter()
+            .find(|account| account.id() == id)
+            .cloned()
+            .ok_or(StoreError::AccountNotFound(id))
+    }
+
+    /// Adds a new account to the store.
+    /// It returns the ID of the newly created account.
+    /// The name must not be empty.
+    pub fn add_account(&mut self, name: &str) -> Result<AccountId, StoreError> {
+        if name.trim().is_empty() {
+            return Err(StoreError::InvalidInput("account name must not be empty".to_string()));
+        }
+        let account = Account::new(self.max_account_id + 1, name.to_string());
+        self.data.accounts.push(account);
+        self.max_account_id += 1;
+        self.persist(&[Change::AccountAdded(self.max_account_id)])?;
+        Ok(self.max_account_id)
+    }
+
+    /// Deletes an account from the store.
+    /// Transactions and recurring transactions paid from, into or to the account are kept, without the account.
+    /// If the account is not found, it returns an error.
+    pub fn delete_account(&mut self, id: AccountId) -> Result<(), StoreError> {
+        let pos = self.data.accounts
+            .iter()
+            .position(|account| account.id() == id)
+            .ok_or(StoreError::AccountNotFound(id))?;
+        self.data.accounts.remove(pos);
+        let mut changes = vec![Change::AccountDeleted(id)];
+        let is_deleted = |account: Option<Account>| account.is_some_and(|account| account.id() == id);
+        for transaction in self.data.transactions.iter_mut() {
+            let mut changed = false;
+            if is_deleted(transaction.account()) {
+                transaction.set_account(None);
+                changed = true;
+            }
+            if is_deleted(transaction.to_account()) {
+                transaction.set_to_account(None);
+                changed = true;
+            }
+            if changed {
+                changes.push(Change::TransactionSaved(transaction.id()));
+            }
         }
-        self.persist();
+        for recurring in self.data.recurring.iter_mut() {
+            if is_deleted(recurring.account()) || is_deleted(recurring.to_account()) {
+                recurring.remove_account(id);
+                changes.push(Change::RecurringSaved(recurring.id()));
+            }
+        }
+        self.persist(&changes)
+    }
+
+    /// Lists all accounts in the store.
+    pub fn list_accounts(&self) -> Vec<&Account> {
+        self.data.accounts.iter().collect()
+    }
+
+    /// Sets a spending limit for the current month and the following months.
+    /// The limits of past months are kept.
+    /// If the limit is set to 0, it removes the limit.
+    pub fn set_limit(&mut self, limit: Money) -> Result<(), StoreError> {
+        let limit = validate_limit(limit)?;
+        self.data.limits.set_from(current_month(), limit);
+        self.persist(&[Change::LimitChanged])
+    }
+
+    /// Sets a spending limit for a single month in the format YYYY-MM, overriding the inherited limit.
+    /// If the limit is set to 0, the month has no limit.
+    pub fn set_month_limit(&mut self, month: &str, limit: Money) -> Result<(), StoreError> {
+        let month = parse_month(month)?;
+        let limit = validate_limit(limit)?;
+        self.data.limits.set_month(month, limit);
+        self.persist(&[Change::LimitChanged])
     }
 
-    /// Returns the current spending limit.
+    /// Returns the spending limit of the current month.
     /// If no limit is set, it returns None.
     pub fn limit(&self) -> Option<Limit> {
-        self.limit
+        self.data.limits.get(&current_month())
+    }
+
+    /// Returns the spending limit of a month in the format YYYY-MM.
+    /// If no limit is set, it returns None.
+    pub fn limit_for(&self, month: &str) -> Result<Option<Limit>, StoreError> {
+        Ok(self.data.limits.get(&parse_month(month)?))
+    }
+
+    /// Returns the history of the spending limit.
+    pub fn limits(&self) -> &LimitHistory {
+        &self.data.limits
+    }
+
+    /// Sets the budget of a category, replacing any previous budget of that category.
+    /// A replaced budget keeps its first month unless a new one is given, so balances keep rolling over.
+    /// If the period stays the same, the new amount applies from the current month on and past months keep theirs.
+    /// If the amount is set to 0, it removes the budget.
+    /// If the category is not found, it returns an error.
+    pub fn set_budget(&mut self, category_id: CategoryId, mut new: NewBudget) -> Result<(), StoreError> {
+        if new.amount.is_negative() {
+            return Err(StoreError::InvalidInput("budget must not be negative".to_string()));
+        }
+        if new.rollover != Rollover::None && new.period != BudgetPeriod::Monthly {
+            return Err(StoreError::InvalidInput("only monthly budgets can roll over".to_string()));
+        }
+        let category = self.get_category(category_id)?;
+        new.since = match new.since {
+            Some(since) => Some(parse_month(&since)?),
+            None => Some(self.budget(category_id).and_then(|budget| budget.since()).map_or_else(current_month, str::to_string)),
+        };
+        let previous = self.data.budgets.iter().position(|budget| budget.category().id() == category_id).map(|index| self.data.budgets.remove(index));
+        if new.amount > Money::ZERO {
+            let budget = match previous {
+                Some(mut budget) if budget.period() == new.period => {
+                    budget.update(current_month(), new);
+                    budget
+                }
+                _ => Budget::new(category, new),
+            };
+            self.data.budgets.push(budget);
+            self.data.budgets.sort_by(|a, b| a.category().name().cmp(b.category().name()));
+        }
+        self.persist(&[Change::BudgetsChanged])
+    }
+
+    /// Sets the budget of a category for a single month in the format YYYY-MM, overriding the inherited amount.
+    /// If the amount is set to 0, nothing is allocated in that month.
+    /// The category must have a budget.
+    pub fn set_month_budget(&mut self, category_id: CategoryId, month: &str, amount: Money) -> Result<(), StoreError> {
+        let month = parse_month(month)?;
+        if amount.is_negative() {
+            return Err(StoreError::InvalidInput("budget must not be negative".to_string()));
+        }
+        let category = self.get_category(category_id)?;
+        let Some(budget) = self.data.budgets.iter_mut().find(|budget| budget.category().id() == category_id) else {
+            return Err(StoreError::InvalidInput(format!("category {:?} has no budget", category.name())));
+        };
+        budget.set_month(month, amount);
+        self.persist(&[Change::BudgetsChanged])
+    }
+
+    /// Moves budget from one category to another for a month in the format YYYY-MM.
+    /// Both categories must have a monthly budget. Returns the recorded transfer.
+    pub fn transfer_budget(&mut self, month: &str, from: CategoryId, to: CategoryId, amount: Money) -> Result<BudgetTransfer, StoreError> {
+        let month = parse_month(month)?;
+        if amount <= Money::ZERO {
+            return Err(StoreError::InvalidInput("transferred budget must be positive".to_string()));
+        }
+        if from == to {
+            return Err(StoreError::InvalidInput("budget can't be transferred to the same category".to_string()));
+        }
+        let from = self.get_category(from)?;
+        let to = self.get_category(to)?;
+        for category in [&from, &to] {
+            if self.budget(category.id()).is_none_or(|budget| budget.period() != BudgetPeriod::Monthly) {
+                return Err(StoreError::InvalidInput(format!("category {:?} has no monthly budget", category.name())));
+            }
+        }
+        let transfer = BudgetTransfer { month, from, to, amount };
+        self.data.budget_transfers.push(transfer.clone());
+        self.persist(&[Change::BudgetsChanged])?;
+        Ok(transfer)
+    }
+
+    /// Returns the transfers of budget between categories, in the order they were made.
+    pub fn budget_transfers(&self) -> &[BudgetTransfer] {
+        &self.data.budget_transfers
+    }
+
+    /// Returns the category budgets, sorted by category name.
+    pub fn budgets(&self) -> &[Budget] {
+        &self.data.budgets
+    }
+
+    /// Returns the budget of a category, if it has one.
+    pub fn budget(&self, category_id: CategoryId) -> Option<&Budget> {
+        self.data.budgets.iter().find(|budget| budget.category().id() == category_id)
+    }
+
+    /// Returns the base currency of the store.
+    /// Summaries, limits and exports are expressed in this currency.
+    pub fn base_currency(&self) -> &Currency {
+        self.data.exchange_rates.base()
+    }
+
+    /// Sets the base currency of the store.
+    /// Existing transactions keep their own currency and are converted using the exchange rate table.
+    pub fn set_base_currency(&mut self, currency: Currency) -> Result<(), StoreError> {
+        self.data.exchange_rates.set_base(currency);
+        self.persist(&[Change::ExchangeRatesChanged])
+    }
+
+    /// Returns the exchange rate table.
+    pub fn exchange_rates(&self) -> &ExchangeRates {
+        &self.data.exchange_rates
+    }
+
+    /// Adds exchange rates to the table, replacing rates for the same date and currency pair.
+    /// It returns the number of rates added.
+    pub fn add_exchange_rates(&mut self, rates: Vec<ExchangeRate>) -> Result<usize, StoreError> {
+        let count = rates.len();
+        for rate in rates {
+            self.data.exchange_rates.add(rate);
+        }
+        self.persist(&[Change::ExchangeRatesChanged])?;
+        Ok(count)
+    }
+
+    /// Adds a recurring transaction to the store.
+    /// If no currency is provided, the base currency of the store is used.
+    /// It returns the ID of the new recurring transaction.
+    pub fn add_recurring(&mut self, new: NewRecurring) -> Result<RecurringId, StoreError> {
+        if new.description.trim().is_empty() {
+            return Err(StoreError::InvalidInput("recurring transaction description must not be empty".to_string()));
+        }
+        if new.amount.is_negative() {
+            return Err(StoreError::InvalidInput("recurring transaction amount must not be negative".to_string()));
+        }
+        if new.interval == 0 {
+            return Err(StoreError::InvalidInput("recurring transaction interval must be at least 1".to_string()));
+        }
+        if new.end.is_some_and(|end| end < new.start) {
+            return Err(StoreError::InvalidInput("recurring transaction must not end before it starts".to_string()));
+        }
+        validate_accounts(new.kind, new.account.as_ref(), new.to_account.as_ref())?;
+        let currency = new.currency.clone().unwrap_or_else(|| self.data.exchange_rates.base().clone());
+        let recurring = RecurringTransaction::new(self.max_recurring_id + 1, new, currency);
+        self.data.recurring.push(recurring);
+        self.max_recurring_id += 1;
+        self.persist(&[Change::RecurringSaved(self.max_recurring_id)])?;
+        Ok(self.max_recurring_id)
+    }
+
+    /// Deletes a recurring transaction from the store.
+    /// Transactions that were already created from it are kept.
+    /// If the recurring transaction is not found, it returns an error.
+    pub fn delete_recurring(&mut self, id: RecurringId) -> Result<(), StoreError> {
+        let pos = self.data.recurring
+            .iter()
+            .position(|recurring| recurring.id() == id)
+            .ok_or(StoreError::RecurringNotFound(id))?;
+        self.data.recurring.remove(pos);
+        self.persist(&[Change::RecurringDeleted(id)])
+    }
+
+    /// Lists all recurring transactions in the store.
+    pub fn list_recurring(&self) -> Vec<&RecurringTransaction> {
+        self.data.recurring.iter().collect()
+    }
+
+    /// Creates transactions for all occurrences of recurring transactions that are due on or before `today`,
+    /// catching up on every period missed since the last run.
+    /// Each occurrence is created once: recurring transactions remember their latest occurrence, and
+    /// created transactions carry a `recurring:<id>:<date>` reference, so running it again adds nothing.
+    /// Occurrences are dated at midnight UTC. All changes are persisted in a single write.
+    /// It returns the IDs of the created transactions.
+    pub fn materialize_recurring(&mut self, today: NaiveDate) -> Result<Vec<TransactionId>, StoreError> {
+        let mut new = vec![];
+        let mut changes = vec![];
+        for recurring in self.data.recurring.iter_mut() {
+            let due = recurring.due_occurrences(today);
+            let Some(&last) = due.last() else { continue };
+            for date in due {
+                let external_ref = format!("recurring:{}:{}", recurring.id(), date);
+                if self.data.transactions.iter().any(|transaction| transaction.external_ref() == Some(&external_ref)) {
+                    continue;
+                }
+                new.push(NewTransaction {
+                    description: recurring.description().to_string(),
+                    amount: recurring.amount(),
+                    currency: Some(recurring.currency().clone()),
+                    category: recurring.category(),
+                    datetime: Some(date.and_time(Default::default()).and_utc()),
+                    external_ref: Some(external_ref),
+                    kind: recurring.kind(),
+                    account: recurring.account(),
+                    to_account: recurring.to_account(),
+                    tags: BTreeSet::new(),
+                });
+            }
+            recurring.set_last_occurrence(last);
+            changes.push(Change::RecurringSaved(recurring.id()));
+        }
+        let ids: Vec<TransactionId> = new.into_iter().map(|new| self.push_transaction(new)).collect();
+        changes.extend(ids.iter().map(|&id| Change::TransactionSaved(id)));
+        if !changes.is_empty() {
+            self.persist(&changes)?;
+        }
+        Ok(ids)
+    }
+
+}
+
+/// Checks that a limit is not negative. A limit of 0 means no limit.
+fn validate_limit(limit: Money) -> Result<Option<Limit>, StoreError> {
+    if limit.is_negative() {
+        return Err(StoreError::InvalidInput("spending limit must not be negative".to_string()));
     }
-    
+    Ok(Some(limit).filter(|limit| !limit.is_zero()))
+}
+
+/// Checks that only transfers have a destination account, and that it isn't the account they come from.
+fn validate_accounts(kind: TransactionKind, account: Option<&Account>, to_account: Option<&Account>) -> Result<(), StoreError> {
+    let Some(to_account) = to_account else {
+        return Ok(());
+    };
+    if kind != TransactionKind::Transfer {
+        return Err(StoreError::InvalidInput("only transfers can have a destination account".to_string()));
+    }
+    if account.is_some_and(|account| account.id() == to_account.id()) {
+        return Err(StoreError::InvalidInput("a transfer must go to a different account".to_string()));
+    }
+    Ok(())
+}
+
+/// Checks the tags and returns them in the form returned by `parse_tag`, e.g. without a leading `#`.
+fn parse_tags(tags: &BTreeSet<String>) -> Result<BTreeSet<String>, StoreError> {
+    tags.iter().map(|tag| parse_tag(tag).map_err(StoreError::InvalidInput)).collect()
+}
+
+/// Returns the current month in the format YYYY-MM.
+fn current_month() -> String {
+    Utc::now().format("%Y-%m").to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::MemoryBackend;
     use chrono::prelude::Utc;
+    use std::fs;
+
+    fn money(value: &str) -> Money {
+        value.parse().unwrap()
+    }
+
+    fn memory_store() -> Store {
+        Store::with_backend(Box::new(MemoryBackend::default())).unwrap()
+    }
 
     #[test]
     fn test_add_transaction() {
-        let temp_file = "test_data_1.json";
-        let mut store = Store::new(Some(temp_file));
-        let id = store.add_transaction("Test transaction".to_string(), 100.0, None);
-        assert_eq!(store.transactions.len(), 1);
-        assert_eq!(store.transactions[0].id(), id);
-        assert_eq!(store.transactions[0].description(), "Test transaction");
-        assert_eq!(store.transactions[0].amount(), 100.0);
-        assert_eq!(store.transactions[0].datetime().date_naive(), Utc::now().date_naive());
-        fs::remove_file(temp_file).expect("Unable to remove file");
+        let mut store = memory_store();
+        let id = store.add_transaction(NewTransaction::new("Test transaction".to_string(), money("100.0"))).unwrap();
+        assert_eq!(store.data.transactions.len(), 1);
+        assert_eq!(store.data.transactions[0].id(), id);
+        assert_eq!(store.data.transactions[0].description(), "Test transaction");
+        assert_eq!(store.data.transactions[0].amount(), money("100.0"));
+        assert_eq!(store.data.transactions[0].datetime().date_naive(), Utc::now().date_naive());
     }
 
     #[test]
     fn test_delete_transaction() {
-        let temp_file = "test_data_2.json";
-        let mut store = Store::new(Some(temp_file));
-        let id = store.add_transaction("Test transaction".to_string(), 100.0, None);
-        store.delete_transaction(id);
-        assert_eq!(store.transactions.len(), 0);
-        fs::remove_file(temp_file).expect("Unable to remove file");
+        let mut store = memory_store();
+        let id = store.add_transaction(NewTransaction::new("Test transaction".to_string(), money("100.0"))).unwrap();
+        store.delete_transaction(id).unwrap();
+        assert_eq!(store.data.transactions.len(), 0);
+    }
+
+    #[test]
+    fn test_add_backdated_transaction() {
+        let mut store = memory_store();
+        let datetime = chrono::DateTime::parse_from_rfc3339("2025-03-28T12:00:00Z").unwrap().with_timezone(&Utc);
+        store.add_transaction(NewTransaction::new("Test transaction 1".to_string(), money("100.0"))).unwrap();
+        store.add_transaction(NewTransaction { datetime: Some(datetime), ..NewTransaction::new("Test transaction 2".to_string(), money("50.0")) }).unwrap();
+        let transactions = store.list_transactions(None);
+        assert_eq!(transactions[0].description(), "Test transaction 2");
+        assert_eq!(transactions[0].datetime(), datetime);
+        assert_eq!(transactions[1].description(), "Test transaction 1");
+    }
+
+    #[test]
+    fn test_update_transaction() {
+        let mut store = memory_store();
+        let category_id = store.add_category("Test category").unwrap();
+        let id = store.add_transaction(NewTransaction::new("Test transaction".to_string(), money("100.0"))).unwrap();
+        let datetime = store.data.transactions[0].datetime();
+        let update = TransactionUpdate {
+            amount: Some(money("120.5")),
+            category: Some(Some(store.get_category(category_id).unwrap())),
+            date: Some(chrono::NaiveDate::from_ymd_opt(2025, 4, 1).unwrap()),
+            ..Default::default()
+        };
+        let (before, after) = store.update_transaction(id, update).unwrap().unwrap();
+        assert_eq!(before.amount(), money("100.0"));
+        assert_eq!(after.amount(), money("120.5"));
+        assert_eq!(after.id(), id);
+        assert_eq!(after.description(), "Test transaction");
+        assert_eq!(after.category().unwrap().name(), "Test category");
+        assert_eq!(after.datetime().date_naive().to_string(), "2025-04-01");
+        assert_eq!(after.datetime().time(), datetime.time());
+        assert_eq!(store.data.transactions[0], after);
+    }
+
+    #[test]
+    fn test_update_transaction_without_changes() {
+        let mut store = memory_store();
+        let id = store.add_transaction(NewTransaction::new("Test transaction".to_string(), money("100.0"))).unwrap();
+        let update = TransactionUpdate { amount: Some(money("100.00")), category: Some(None), ..Default::default() };
+        assert!(store.update_transaction(id, update).unwrap().is_none());
+        assert!(matches!(store.update_transaction(2, TransactionUpdate::default()), Err(StoreError::TransactionNotFound(2))));
     }
 
     #[test]
     fn test_list_transactions() {
-        let temp_file = "test_data_3.json";
-        let mut store = Store::new(Some(temp_file));
-        store.add_transaction("Test transaction 1".to_string(), 100.0, None);
-        store.add_transaction("Test transaction 2".to_string(), 200.0, None);
+        let mut store = memory_store();
+        store.add_transaction(NewTransaction::new("Test transaction 1".to_string(), money("100.0"))).unwrap();
+        store.add_transaction(NewTransaction::new("Test transaction 2".to_string(), money("200.0"))).unwrap();
         let transactions = store.list_transactions(None);
         assert_eq!(transactions.len(), 2);
         assert_eq!(transactions[0].description(), "Test transaction 1");
         assert_eq!(transactions[1].description(), "Test transaction 2");
-        fs::remove_file(temp_file).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_filter_transactions() {
+        let mut store = memory_store();
+        let food = store.add_category("Food").unwrap();
+        let at = |date: &str| Some(chrono::DateTime::parse_from_rfc3339(date).unwrap().to_utc());
+        store.add_transactions(vec![
+            NewTransaction { datetime: at("2025-03-31T23:00:00Z"), ..NewTransaction::new("March".to_string(), money("10")) },
+            NewTransaction { datetime: at("2025-04-01T08:00:00Z"), category: Some(store.get_category(food).unwrap()), ..NewTransaction::new("Lunch".to_string(), money("12.5")) },
+            NewTransaction { datetime: at("2025-04-30T20:00:00Z"), ..NewTransaction::new("Dinner".to_string(), money("40")) },
+        ]).unwrap();
+        let descriptions = |filter: TransactionFilter| store.filter_transactions(&filter).iter().map(|t| t.description().to_string()).collect::<Vec<_>>();
+        let april = TransactionFilter { from: "2025-04-01".parse().ok(), to: "2025-04-30".parse().ok(), ..Default::default() };
+        assert_eq!(descriptions(april.clone()), vec!["Lunch", "Dinner"]);
+        assert_eq!(descriptions(TransactionFilter { category: Some(food), ..Default::default() }), vec!["Lunch"]);
+        assert_eq!(descriptions(TransactionFilter { min_amount: Some(money("10")), max_amount: Some(money("20")), ..april }), vec!["Lunch"]);
+        assert_eq!(descriptions(TransactionFilter::default()).len(), 3);
     }
 
     #[test]
     fn test_add_category() {
-        let temp_file = "test_data_4.json";
-        let mut store = Store::new(Some(temp_file));
-        let id = store.add_category("Test category");
-        assert_eq!(store.categories.len(), 1);
-        assert_eq!(store.categories[0].id(), id);
-        assert_eq!(store.categories[0].name(), "Test category");
-        fs::remove_file(temp_file).expect("Unable to remove file");
+        let mut store = memory_store();
+        let id = store.add_category("Test category").unwrap();
+        assert_eq!(store.data.categories.len(), 1);
+        assert_eq!(store.data.categories[0].id(), id);
+        assert_eq!(store.data.categories[0].name(), "Test category");
     }
 
     #[test]
     fn test_delete_category() {
-        let temp_file = "test_data_5.json";
-        let mut store = Store::new(Some(temp_file));
-        let id = store.add_category("Test category");
-        store.delete_category(id);
-        assert_eq!(store.categories.len(), 0);
-        assert_eq!(store.transactions.len(), 0);
-        fs::remove_file(temp_file).expect("Unable to remove file");
+        let mut store = memory_store();
+        let id = store.add_category("Test category").unwrap();
+        store.delete_category(id).unwrap();
+        assert_eq!(store.data.categories.len(), 0);
+        assert_eq!(store.data.transactions.len(), 0);
     }
 
     #[test]
     fn test_list_categories() {
-        let temp_file = "test_data_6.json";
-        let mut store = Store::new(Some(temp_file));
-        store.add_category("Test category 1");
-        store.add_category("Test category 2");
+        let mut store = memory_store();
+        store.add_category("Test category 1").unwrap();
+        store.add_category("Test category 2").unwrap();
         let categories = store.list_categories();
         assert_eq!(categories.len(), 2);
         assert_eq!(categories[0].name(), "Test category 1");
         assert_eq!(categories[1].name(), "Test category 2");
-        fs::remove_file(temp_file).expect("Unable to remove file");
     }
 
     #[test]
     fn test_set_limit() {
-        let temp_file = "test_data_7.json";
-        let mut store = Store::new(Some(temp_file));
-        store.set_limit(1000.0);
-        assert_eq!(store.limit(), Some(1000.0));
-        store.set_limit(0.0);
+        let mut store = memory_store();
+        store.set_limit(money("1000.0")).unwrap();
+        assert_eq!(store.limit(), Some(money("1000.0")));
+        store.set_limit(money("0.0")).unwrap();
         assert_eq!(store.limit(), None);
-        fs::remove_file(temp_file).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_month_limits() {
+        let mut store = memory_store();
+        store.data.limits.set_initial(Some(money("300")));
+        store.set_limit(money("500")).unwrap();
+        store.set_month_limit("2025-4", money("100")).unwrap();
+        assert_eq!(store.limit(), Some(money("500")));
+        assert_eq!(store.limit_for("2025-04").unwrap(), Some(money("100")));
+        assert_eq!(store.limit_for("2025-05").unwrap(), Some(money("300")));
+        store.set_month_limit("2025-05", money("0")).unwrap();
+        assert_eq!(store.limit_for("2025-05").unwrap(), None);
+        assert!(matches!(store.set_month_limit("2025-13", money("1")), Err(StoreError::InvalidMonth(_))));
+    }
+
+    #[test]
+    fn test_set_budget() {
+        let mut store = memory_store();
+        let food = store.add_category("Food").unwrap();
+        let fun = store.add_category("Fun").unwrap();
+        store.set_budget(fun, NewBudget::new(money("50"), BudgetPeriod::Weekly)).unwrap();
+        store.set_budget(food, NewBudget { since: Some("2025-1".to_string()), ..NewBudget::new(money("100"), BudgetPeriod::Monthly) }).unwrap();
+        store.set_budget(food, NewBudget { rollover: Rollover::Full, ..NewBudget::new(money("200"), BudgetPeriod::Monthly) }).unwrap();
+        assert_eq!(store.budgets().len(), 2);
+        assert_eq!(store.budgets()[0].category().name(), "Food");
+        assert_eq!(store.budget(food).unwrap().amount_for("2025-01"), money("100"));
+        assert_eq!(store.budget(food).unwrap().amount_for(&current_month()), money("200"));
+        assert_eq!(store.budget(food).unwrap().since(), Some("2025-01"));
+        store.set_month_budget(food, "2025-2", money("40")).unwrap();
+        assert_eq!(store.budget(food).unwrap().amount_for("2025-02"), money("40"));
+        assert_eq!(store.budget(food).unwrap().amount_for("2025-03"), money("100"));
+        let gym = store.add_category("Gym").unwrap();
+        assert!(matches!(store.set_month_budget(gym, "2025-02", money("10")), Err(StoreError::InvalidInput(_))));
+        assert!(matches!(store.set_month_budget(food, "2025-02", money("-1")), Err(StoreError::InvalidInput(_))));
+        store.set_budget(food, NewBudget::new(money("0"), BudgetPeriod::Monthly)).unwrap();
+        assert_eq!(store.budget(food), None);
+        assert!(matches!(store.set_budget(99, NewBudget::new(money("10"), BudgetPeriod::Monthly)), Err(StoreError::CategoryNotFound(99))));
+        assert!(matches!(store.set_budget(fun, NewBudget::new(money("-1"), BudgetPeriod::Monthly)), Err(StoreError::InvalidInput(_))));
+        let weekly_rollover = NewBudget { rollover: Rollover::Surplus, ..NewBudget::new(money("10"), BudgetPeriod::Weekly) };
+        assert!(matches!(store.set_budget(fun, weekly_rollover), Err(StoreError::InvalidInput(_))));
+        store.delete_category(fun).unwrap();
+        assert!(store.budgets().is_empty());
+    }
+
+    #[test]
+    fn test_transfer_budget() {
+        let mut store = memory_store();
+        let food = store.add_category("Food").unwrap();
+        let fun = store.add_category("Fun").unwrap();
+        store.set_budget(food, NewBudget::new(money("300"), BudgetPeriod::Monthly)).unwrap();
+        assert!(matches!(store.transfer_budget("2025-04", food, fun, money("50")), Err(StoreError::InvalidInput(_))));
+        store.set_budget(fun, NewBudget::new(money("100"), BudgetPeriod::Monthly)).unwrap();
+        assert!(matches!(store.transfer_budget("2025-04", food, food, money("50")), Err(StoreError::InvalidInput(_))));
+        assert!(matches!(store.transfer_budget("2025-04", food, fun, money("0")), Err(StoreError::InvalidInput(_))));
+        let transfer = store.transfer_budget("2025-4", food, fun, money("50")).unwrap();
+        assert_eq!(transfer.month, "2025-04");
+        assert_eq!(store.budget_transfers(), [transfer]);
+        store.delete_category(fun).unwrap();
+        assert!(store.budget_transfers().is_empty());
     }
 
     #[test]
     fn test_get_category() {
-        let temp_file = "test_data_8.json";
-        let mut store = Store::new(Some(temp_file));
-        let id = store.add_category("Test category");
+        let mut store = memory_store();
+        let id = store.add_category("Test category").unwrap();
         let category = store.get_category(id);
         assert_eq!(category.unwrap().name(), "Test category");
-        fs::remove_file(temp_file).expect("Unable to remove file");
     }
 
     #[test]
     fn test_add_transaction_with_category() {
-        let temp_file = "test_data_10.json";
-        let mut store = Store::new(Some(temp_file));
-        let category_id = store.add_category("Test category");
-        let id = store.add_transaction("Test transaction".to_string(), 100.0, Some(store.get_category(category_id).unwrap()));
-        assert_eq!(store.transactions.len(), 1);
-        assert_eq!(store.transactions[0].id(), id);
-        assert_eq!(store.transactions[0].description(), "Test transaction");
-        assert_eq!(store.transactions[0].amount(), 100.0);
-        assert_eq!(store.transactions[0].category().unwrap().name(), "Test category");
-        fs::remove_file(temp_file).expect("Unable to remove file");
+        let mut store = memory_store();
+        let category_id = store.add_category("Test category").unwrap();
+        let id = store.add_transaction(NewTransaction { category: Some(store.get_category(category_id).unwrap()), ..NewTransaction::new("Test transaction".to_string(), money("100.0")) }).unwrap();
+        assert_eq!(store.data.transactions.len(), 1);
+        assert_eq!(store.data.transactions[0].id(), id);
+        assert_eq!(store.data.transactions[0].description(), "Test transaction");
+        assert_eq!(store.data.transactions[0].amount(), money("100.0"));
+        assert_eq!(store.data.transactions[0].category().unwrap().name(), "Test category");
     }
 
     #[test]
     fn test_delete_category_with_transactions() {
-        let temp_file = "test_data_11.json";
-        let mut store = Store::new(Some(temp_file));
-        let category_id = store.add_category("Test category");
-        store.add_transaction("Test transaction".to_string(), 100.0, Some(store.get_category(category_id).unwrap()));
-        store.delete_category(category_id);
-        assert_eq!(store.categories.len(), 0);
-        assert_eq!(store.transactions.len(), 1);
-        assert_eq!(store.transactions[0].category(), None);
+        let mut store = memory_store();
+        let category_id = store.add_category("Test category").unwrap();
+        store.add_transaction(NewTransaction { category: Some(store.get_category(category_id).unwrap()), ..NewTransaction::new("Test transaction".to_string(), money("100.0")) }).unwrap();
+        store.delete_category(category_id).unwrap();
+        assert_eq!(store.data.categories.len(), 0);
+        assert_eq!(store.data.transactions.len(), 1);
+        assert_eq!(store.data.transactions[0].category(), None);
+    }
+
+    #[test]
+    fn test_delete_account_with_transactions() {
+        let mut store = memory_store();
+        let checking = store.add_account("Checking").unwrap();
+        let savings = store.add_account("Savings").unwrap();
+        let new = NewTransaction {
+            kind: TransactionKind::Transfer,
+            account: store.get_account(checking).ok(),
+            to_account: store.get_account(savings).ok(),
+            ..NewTransaction::new("Savings".to_string(), money("100"))
+        };
+        store.add_transaction(new).unwrap();
+        store.delete_account(savings).unwrap();
+        assert_eq!(store.list_accounts().len(), 1);
+        assert_eq!(store.data.transactions[0].account().unwrap().name(), "Checking");
+        assert_eq!(store.data.transactions[0].to_account(), None);
+        assert!(matches!(store.delete_account(savings), Err(StoreError::AccountNotFound(_))));
+    }
+
+    #[test]
+    fn test_invalid_transfers() {
+        let mut store = memory_store();
+        let id = store.add_account("Checking").unwrap();
+        let checking = store.get_account(id).ok();
+        let transfer = |account: Option<Account>, to_account: Option<Account>| NewTransaction {
+            kind: TransactionKind::Transfer,
+            account,
+            to_account,
+            ..NewTransaction::new("Transfer".to_string(), money("100"))
+        };
+        let to_itself = store.add_transaction(transfer(checking.clone(), checking.clone()));
+        assert!(matches!(to_itself, Err(StoreError::InvalidInput(_))));
+        let expense = NewTransaction { kind: TransactionKind::Expense, ..transfer(None, checking.clone()) };
+        assert!(matches!(store.add_transaction(expense), Err(StoreError::InvalidInput(_))));
+        let id = store.add_transaction(transfer(None, checking.clone())).unwrap();
+        let update = TransactionUpdate { account: Some(checking), ..TransactionUpdate::default() };
+        assert!(matches!(store.update_transaction(id, update), Err(StoreError::InvalidInput(_))));
+        // Turning the transfer into an expense drops its destination.
+        let update = TransactionUpdate { kind: Some(TransactionKind::Expense), ..TransactionUpdate::default() };
+        store.update_transaction(id, update).unwrap();
+        assert_eq!(store.data.transactions[0].to_account(), None);
+        assert!(matches!(store.add_account(""), Err(StoreError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut store = memory_store();
+        let tagged = |description: &str, tags: &[&str]| NewTransaction {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..NewTransaction::new(description.to_string(), money("10"))
+        };
+        let flight = store.add_transaction(tagged("Flight", &["#Lisbon", "work"])).unwrap();
+        store.add_transaction(tagged("Hotel", &["trip-lisbon"])).unwrap();
+        assert_eq!(store.data.transactions[0].tags(), &BTreeSet::from(["lisbon".to_string(), "work".to_string()]));
+        assert!(matches!(store.add_transaction(tagged("Taxi", &["not a tag"])), Err(StoreError::InvalidInput(_))));
+
+        let update = TransactionUpdate { remove_tags: BTreeSet::from(["work".to_string()]), ..TransactionUpdate::default() };
+        store.update_transaction(flight, update).unwrap();
+        let filter = TransactionFilter { tags: BTreeSet::from(["lisbon".to_string()]), ..TransactionFilter::default() };
+        assert_eq!(store.filter_transactions(&filter).len(), 1);
+
+        // Renaming to a tag that is already in use merges the two.
+        assert_eq!(store.rename_tag("lisbon", "trip-lisbon").unwrap(), 1);
+        assert_eq!(store.list_tags(), vec![("trip-lisbon", 2)]);
+        assert!(matches!(store.rename_tag("lisbon", "trip-lisbon"), Err(StoreError::TagNotFound(_))));
+    }
+
+    #[test]
+    fn test_load_legacy_float_amounts() {
+        let temp_file = "test_data_12.json";
+        let legacy = r#"{
+            "transactions": [
+                {"id": 1, "amount": 0.1, "description": "A", "datetime": "2025-04-01T10:00:00Z", "category": null},
+                {"id": 2, "amount": 30.000000000000004, "description": "B", "datetime": "2025-04-02T10:00:00Z", "category": null}
+            ],
+            "categories": [],
+            "limit": 100.0
+        }"#;
+        fs::write(temp_file, legacy).expect("Unable to write file");
+        let store = Store::new(Some(temp_file)).unwrap();
+        assert_eq!(store.data.transactions[0].amount().to_string(), "0.10");
+        assert_eq!(store.data.transactions[1].amount(), money("30.000000000000004"));
+        assert_eq!(store.limit(), Some(money("100")));
+        let migrated = fs::read_to_string(temp_file).expect("Unable to read file");
+        assert!(migrated.contains(r#""amount": "30.000000000000004""#));
+        assert!(migrated.contains(r#""version": 2"#));
+        let mut backups = 0;
+        for entry in fs::read_dir(".").expect("Unable to read directory") {
+            let name = entry.unwrap().file_name().into_string().unwrap();
+            if name.starts_with("test_data_12.json.v0-") {
+                assert_eq!(fs::read_to_string(&name).expect("Unable to read file"), legacy);
+                fs::remove_file(name).expect("Unable to remove file");
+                backups += 1;
+            }
+        }
+        assert_eq!(backups, 1);
         fs::remove_file(temp_file).expect("Unable to remove file");
-    }   
-}
\ No newline at end of file
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_transaction_currency() {
+        let temp_file = "test_data_13.json";
+        let mut store = Store::new(Some(temp_file)).unwrap();
+        store.set_base_currency("EUR".parse().unwrap()).unwrap();
+        store.add_transaction(NewTransaction::new("Test transaction 1".to_string(), money("10"))).unwrap();
+        store.add_transaction(NewTransaction { currency: Some("GBP".parse().unwrap()), ..NewTransaction::new("Test transaction 2".to_string(), money("20")) }).unwrap();
+        assert_eq!(store.data.transactions[0].currency().code(), "EUR");
+        assert_eq!(store.data.transactions[1].currency().code(), "GBP");
+        drop(store);
+        let store = Store::new(Some(temp_file)).unwrap();
+        assert_eq!(store.base_currency().code(), "EUR");
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_store_with_prefilled_backend() {
+        let mut source = memory_store();
+        source.add_category("Test category").unwrap();
+        source.add_transaction(NewTransaction::new("Test transaction".to_string(), money("100.0"))).unwrap();
+        let backend = MemoryBackend::new(source.data.clone());
+        let mut store = Store::with_backend(Box::new(backend)).unwrap();
+        assert_eq!(store.list_transactions(None).len(), 1);
+        assert_eq!(store.add_transaction(NewTransaction::new("Test transaction 2".to_string(), money("50.0"))).unwrap(), 2);
+        assert_eq!(store.add_category("Test category 2").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_materialize_recurring() {
+        let temp_file = "test_data_14.json";
+        let mut store = Store::new(Some(temp_file)).unwrap();
+        let category_id = store.add_category("Housing").unwrap();
+        let rent = NewRecurring {
+            category: Some(store.get_category(category_id).unwrap()),
+            ..NewRecurring::new("Rent".to_string(), money("1000"), crate::recurring::Frequency::Monthly, "2025-01-31".parse().unwrap())
+        };
+        let id = store.add_recurring(rent).unwrap();
+        let ids = store.materialize_recurring("2025-03-15".parse().unwrap()).unwrap();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(store.data.transactions[1].datetime().date_naive(), "2025-02-28".parse().unwrap());
+        assert_eq!(store.data.transactions[1].external_ref(), Some("recurring:1:2025-02-28"));
+        assert_eq!(store.data.transactions[1].category().unwrap().id(), category_id);
+        // Running again for the same day adds nothing; a later run catches up on the missed months.
+        assert!(store.materialize_recurring("2025-03-15".parse().unwrap()).unwrap().is_empty());
+        drop(store);
+        let mut store = Store::new(Some(temp_file)).unwrap();
+        assert_eq!(store.list_recurring()[0].last_occurrence(), Some("2025-02-28".parse().unwrap()));
+        assert_eq!(store.materialize_recurring("2025-05-31".parse().unwrap()).unwrap(), vec![3, 4, 5]);
+        store.delete_category(category_id).unwrap();
+        assert_eq!(store.list_recurring()[0].category(), None);
+        store.delete_recurring(id).unwrap();
+        assert_eq!(store.list_transactions(None).len(), 5);
+        assert!(matches!(store.delete_recurring(id), Err(StoreError::RecurringNotFound(1))));
+        fs::remove_file(temp_file).expect("Unable to remove file");
+        fs::remove_file(format!("{}.lock", temp_file)).expect("Unable to remove file");
+    }
+
+    #[test]
+    fn test_add_invalid_recurring() {
+        let mut store = memory_store();
+        let gym = || NewRecurring::new("Gym".to_string(), money("30"), crate::recurring::Frequency::Weekly, "2025-01-06".parse().unwrap());
+        assert!(matches!(store.add_recurring(NewRecurring { end: Some("2025-01-01".parse().unwrap()), ..gym() }), Err(StoreError::InvalidInput(_))));
+        assert!(matches!(store.add_recurring(NewRecurring { interval: 0, ..gym() }), Err(StoreError::InvalidInput(_))));
+        assert_eq!(store.add_recurring(gym()).unwrap(), 1);
+        assert_eq!(store.list_recurring()[0].currency(), store.base_currency());
+    }
+
+    #[test]
+    fn test_not_found_errors() {
+        let mut store = memory_store();
+        assert!(matches!(store.get_category(1), Err(StoreError::CategoryNotFound(1))));
+        assert!(matches!(store.delete_category(1), Err(StoreError::CategoryNotFound(1))));
+        assert!(matches!(store.delete_transaction(1), Err(StoreError::TransactionNotFound(1))));
+        assert!(matches!(store.add_category(" "), Err(StoreError::InvalidInput(_))));
+    }
+}
@@ -1,79 +1,259 @@
-use super::models::{Transaction, Category, Limit};
-use std::collections::HashMap;
-use chrono::prelude::Utc;
+use super::models::{Transaction, TransactionKind, Account, AccountId, Category, Limit, Money};
+use super::budget::LimitHistory;
+use super::currency::ExchangeRates;
+use super::error::StoreError;
+use std::collections::{BTreeMap, HashMap};
+use chrono::{Months, NaiveDate};
 
 /// Function to create a summary of transactions for a given month or overall, and optionally filter by category.
+/// The month is given in the format YYYY-MM; `overall` or no month at all summarizes every transaction.
+/// Only expenses are counted; income and transfers are left out.
+/// Amounts are converted into the base currency using the rate valid on each transaction's date.
 /// It returns the total amount and a breakdown by day.
-pub fn summary(transactions: Vec<&Transaction>, month: Option<String>, category: Option<&Category>) -> (f64, HashMap<String, f64>) {
-    if let Some(month) = month { 
-        let transactions = transactions
-            .iter()
-            .filter(|&transaction| {
-                if month == "overall" {
-                    true
-                } else {
-                    transaction.datetime().format("%Y-%m").to_string() == month
-                }
-            })
-            .filter(|&transaction| {
-                if let Some(ref cat) = category {
-                    transaction.category().as_ref() == Some(cat)
-                } else {
-                    true
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let month_total = transactions.iter().map(|t| t.amount()).sum();
-        let by_day_total = transactions.iter().fold(HashMap::new(), |mut acc, transaction| {
-            let day = transaction.datetime().date_naive().format("%Y-%m-%d").to_string();
-            *acc.entry(day).or_insert(0.0) += transaction.amount();
-            acc
-        });
-        (month_total, by_day_total)
-    } else {
-        panic!("Invalid month format. Use YYYY-MM or 'overall'.");
-    }
+pub fn summary(transactions: Vec<&Transaction>, month: Option<String>, category: Option<&Category>, rates: &ExchangeRates) -> Result<(Money, HashMap<String, Money>), StoreError> {
+    let month = match month {
+        Some(month) if month != "overall" => Some(parse_month(&month)?),
+        _ => None,
+    };
+    let transactions = transactions
+        .iter()
+        .filter(|&transaction| transaction.is_expense())
+        .filter(|&transaction| {
+            if let Some(ref month) = month {
+                &transaction.datetime().format("%Y-%m").to_string() == month
+            } else {
+                true
+            }
+        })
+        .filter(|&transaction| {
+            if let Some(cat) = category {
+                transaction.category().as_ref() == Some(cat)
+            } else {
+                true
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let amounts = transactions
+        .iter()
+        .map(|&transaction| rates.to_base(transaction).map(|amount| (transaction, amount)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let month_total = amounts.iter().map(|(_, amount)| amount).sum();
+    let by_day_total = amounts.iter().fold(HashMap::new(), |mut acc, (transaction, amount)| {
+        let day = transaction.datetime().date_naive().format("%Y-%m-%d").to_string();
+        *acc.entry(day).or_insert(Money::ZERO) += *amount;
+        acc
+    });
+    Ok((month_total, by_day_total))
 }
 
-/// Function to check the remaining limit for the current month.
-/// It takes a vector of transactions and a limit, and returns the remaining amount.
+/// Function to check the remaining limit for a month in the format YYYY-MM.
+/// It takes a vector of transactions and a limit in the base currency, and returns the remaining amount.
 /// If the limit is exceeded, it returns a negative value.
-pub fn check_limit(transactions: Vec<&Transaction>, limit: Limit) -> f64 {
-    let month = Utc::now().format("%Y-%m").to_string();
-    let (total, _) = summary(transactions, Some(month), None);
-    limit - total
+pub fn check_limit(transactions: Vec<&Transaction>, month: &str, limit: Limit, rates: &ExchangeRates) -> Result<Money, StoreError> {
+    let (total, _) = summary(transactions, Some(month.to_string()), None, rates)?;
+    Ok(limit - total)
+}
+
+/// Spending of a month against the limit of that month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyLimit {
+    /// Month in the format YYYY-MM.
+    pub month: String,
+    pub limit: Option<Limit>,
+    /// Total spent during the month, in the base currency.
+    pub spent: Money,
+}
+
+impl MonthlyLimit {
+    /// Returns the amount left of the limit, or None if the month has no limit.
+    /// If the limit is exceeded, it returns a negative value.
+    pub fn remaining(&self) -> Option<Money> {
+        self.limit.map(|limit| limit - self.spent)
+    }
+}
+
+/// Function to compare the spending of each month with its limit.
+/// The report covers every month from the first month with a transaction or a limit up to `until`,
+/// in the format YYYY-MM, including months without spending.
+pub fn limit_report(transactions: Vec<&Transaction>, limits: &LimitHistory, until: &str, rates: &ExchangeRates) -> Result<Vec<MonthlyLimit>, StoreError> {
+    let until = parse_month(until)?;
+    let first = transactions
+        .iter()
+        .map(|transaction| transaction.datetime().format("%Y-%m").to_string())
+        .chain(limits.changes().keys().cloned())
+        .chain(limits.months().keys().cloned())
+        .min();
+    let Some(first) = first else {
+        return Ok(vec![]);
+    };
+    let mut report = vec![];
+    let mut month = month_start(&first)?;
+    while month <= month_start(&until)? {
+        let name = month.format("%Y-%m").to_string();
+        let (spent, _) = summary(transactions.clone(), Some(name.clone()), None, rates)?;
+        report.push(MonthlyLimit { limit: limits.get(&name), month: name, spent });
+        month = month + Months::new(1);
+    }
+    Ok(report)
+}
+
+/// Income and expenses of a month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CashFlow {
+    /// Month in the format YYYY-MM.
+    pub month: String,
+    /// Total received during the month, in the base currency.
+    pub income: Money,
+    /// Total spent during the month, in the base currency.
+    pub expenses: Money,
+}
+
+impl CashFlow {
+    /// Returns the income left after expenses. It is negative if more was spent than received.
+    pub fn net(&self) -> Money {
+        self.income - self.expenses
+    }
+}
+
+/// Function to sum up the income and expenses of each month with transactions, in order.
+/// Transfers between own accounts are neither, so they are left out.
+pub fn cash_flow(transactions: Vec<&Transaction>, rates: &ExchangeRates) -> Result<Vec<CashFlow>, StoreError> {
+    let mut months: BTreeMap<String, CashFlow> = BTreeMap::new();
+    for transaction in transactions {
+        if transaction.kind() == TransactionKind::Transfer {
+            continue;
+        }
+        let amount = rates.to_base(transaction)?;
+        let month = transaction.datetime().format("%Y-%m").to_string();
+        let flow = months
+            .entry(month.clone())
+            .or_insert_with(|| CashFlow { month, income: Money::ZERO, expenses: Money::ZERO });
+        match transaction.kind() {
+            TransactionKind::Income => flow.income += amount,
+            _ => flow.expenses += amount,
+        }
+    }
+    Ok(months.into_values().collect())
+}
+
+/// A transaction of an account, with its effect on the balance of the account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceEntry<'a> {
+    pub transaction: &'a Transaction,
+    /// Change of the balance in the base currency: expenses and transfers out are negative,
+    /// income and transfers in are positive.
+    pub change: Money,
+    /// Balance after the transaction, in the base currency.
+    pub balance: Money,
+}
+
+/// Function to follow the balance of an account through its transactions, sorted by date.
+/// Balances start at zero, so a starting amount is best recorded as income.
+pub fn running_balance<'a>(transactions: Vec<&'a Transaction>, account: AccountId, rates: &ExchangeRates) -> Result<Vec<BalanceEntry<'a>>, StoreError> {
+    let mut transactions: Vec<&Transaction> = transactions
+        .into_iter()
+        .filter(|transaction| involves(transaction.account(), account) || involves(transaction.to_account(), account))
+        .collect();
+    transactions.sort_by_key(|transaction| transaction.datetime());
+    let mut balance = Money::ZERO;
+    let mut entries = vec![];
+    for transaction in transactions {
+        let amount = rates.to_base(transaction)?;
+        let mut change = Money::ZERO;
+        if involves(transaction.account(), account) {
+            change += match transaction.kind() {
+                TransactionKind::Income => amount,
+                TransactionKind::Expense | TransactionKind::Transfer => -amount,
+            };
+        }
+        if involves(transaction.to_account(), account) {
+            change += amount;
+        }
+        balance += change;
+        entries.push(BalanceEntry { transaction, change, balance });
+    }
+    Ok(entries)
+}
+
+/// Function to compute the current balance of each account, in the base currency.
+pub fn account_balances(transactions: Vec<&Transaction>, accounts: Vec<&Account>, rates: &ExchangeRates) -> Result<Vec<(Account, Money)>, StoreError> {
+    accounts
+        .into_iter()
+        .map(|account| {
+            let entries = running_balance(transactions.clone(), account.id(), rates)?;
+            let balance = entries.last().map_or(Money::ZERO, |entry| entry.balance);
+            Ok((account.clone(), balance))
+        })
+        .collect()
+}
+
+fn involves(account: Option<Account>, id: AccountId) -> bool {
+    account.is_some_and(|account| account.id() == id)
+}
+
+/// Returns the first day of a month in the format YYYY-MM.
+pub(crate) fn month_start(month: &str) -> Result<NaiveDate, StoreError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").map_err(|_| StoreError::InvalidMonth(month.to_string()))
+}
+
+/// Validates a month in the format YYYY-MM and returns it normalized, e.g. `2025-4` becomes `2025-04`.
+pub fn parse_month(month: &str) -> Result<String, StoreError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map(|date| date.format("%Y-%m").to_string())
+        .map_err(|_| StoreError::InvalidMonth(month.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::currency::{Currency, ExchangeRate};
     use chrono::prelude::Utc;
 
+    fn money(value: &str) -> Money {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_summary_backdated_month() {
+        let mut backdated = Transaction::new(2, money("200.0"), Currency::default(), "Test transaction 2".to_string(), None);
+        backdated.set_datetime(chrono::DateTime::parse_from_rfc3339("2025-03-31T23:00:00Z").unwrap().with_timezone(&Utc));
+        let transactions = [
+            Transaction::new(1, money("100.0"), Currency::default(), "Test transaction 1".to_string(), None),
+            backdated,
+        ];
+        let (total, by_day) = summary(transactions.iter().collect(), Some("2025-03".to_string()), None, &ExchangeRates::default()).unwrap();
+        assert_eq!(total, money("200.0"));
+        assert_eq!(by_day.get("2025-03-31"), Some(&money("200.0")));
+        let remaining = check_limit(transactions.iter().collect(), &Utc::now().format("%Y-%m").to_string(), money("1000"), &ExchangeRates::default()).unwrap();
+        assert_eq!(remaining, money("900"));
+    }
+
     #[test]
     fn test_summary_overall() {
         let curr_day = Utc::now().date_naive().format("%Y-%m-%d").to_string();
-        let transactions = vec![
-            Transaction::new(1, 100.0, "Test transaction 1".to_string(), None),
-            Transaction::new(2, 200.0, "Test transaction 2".to_string(), None),
+        let transactions = [
+            Transaction::new(1, money("100.0"), Currency::default(), "Test transaction 1".to_string(), None),
+            Transaction::new(2, money("200.0"), Currency::default(), "Test transaction 2".to_string(), None),
         ];
-        let (total, by_day) = summary(transactions.iter().collect(), Some("overall".to_string()), None);
-        assert_eq!(total, 300.0);
+        let (total, by_day) = summary(transactions.iter().collect(), Some("overall".to_string()), None, &ExchangeRates::default()).unwrap();
+        assert_eq!(total, money("300.0"));
         assert_eq!(by_day.len(), 1);
-        assert_eq!(by_day.get(&curr_day).unwrap(), &300.0);
+        assert_eq!(by_day.get(&curr_day).unwrap(), &money("300.0"));
     }
     #[test]
     fn test_summary_month() {
         let curr_day = Utc::now().date_naive().format("%Y-%m-%d").to_string();
         let curr_month = Utc::now().format("%Y-%m").to_string();
-        let transactions = vec![
-            Transaction::new(1, 100.0, "Test transaction 1".to_string(), None),
-            Transaction::new(2, 200.0, "Test transaction 2".to_string(), None),
+        let transactions = [
+            Transaction::new(1, money("100.0"), Currency::default(), "Test transaction 1".to_string(), None),
+            Transaction::new(2, money("200.0"), Currency::default(), "Test transaction 2".to_string(), None),
         ];
-        let (total, by_day) = summary(transactions.iter().collect(), Some(curr_month), None);
-        assert_eq!(total, 300.0);
+        let (total, by_day) = summary(transactions.iter().collect(), Some(curr_month), None, &ExchangeRates::default()).unwrap();
+        assert_eq!(total, money("300.0"));
         assert_eq!(by_day.len(), 1);
-        assert_eq!(by_day.get(&curr_day).unwrap(), &300.0); 
+        assert_eq!(by_day.get(&curr_day).unwrap(), &money("300.0")); 
     }
 
     #[test]
@@ -81,27 +261,170 @@ mod tests {
         let curr_day = Utc::now().date_naive().format("%Y-%m-%d").to_string();
         let curr_month = Utc::now().format("%Y-%m").to_string();
         let category = Category::new(1, "Food".to_string());
-        let transactions = vec![
-            Transaction::new(1, 100.0, "Test transaction 1".to_string(), Some(category.clone())),
-            Transaction::new(2, 200.0, "Test transaction 2".to_string(), None),
+        let transactions = [
+            Transaction::new(1, money("100.0"), Currency::default(), "Test transaction 1".to_string(), Some(category.clone())),
+            Transaction::new(2, money("200.0"), Currency::default(), "Test transaction 2".to_string(), None),
         ];
-        let (total, by_day) = summary(transactions.iter().collect(), Some(curr_month), Some(&category));
-        assert_eq!(total, 100.0);
+        let (total, by_day) = summary(transactions.iter().collect(), Some(curr_month), Some(&category), &ExchangeRates::default()).unwrap();
+        assert_eq!(total, money("100.0"));
         assert_eq!(by_day.len(), 1);
-        assert_eq!(by_day.get(&curr_day).unwrap(), &100.0);
+        assert_eq!(by_day.get(&curr_day).unwrap(), &money("100.0"));
     }
 
     #[test]
     fn test_check_limit() {
-        let transactions = vec![
-            Transaction::new(1, 100.0, "Test transaction 1".to_string(), None),
-            Transaction::new(2, 200.0, "Test transaction 2".to_string(), None),
+        let month = Utc::now().format("%Y-%m").to_string();
+        let transactions = [
+            Transaction::new(1, money("100.0"), Currency::default(), "Test transaction 1".to_string(), None),
+            Transaction::new(2, money("200.0"), Currency::default(), "Test transaction 2".to_string(), None),
+        ];
+        let limit = money("500.0");
+        let remaining = check_limit(transactions.iter().collect(), &month, limit, &ExchangeRates::default()).unwrap();
+        assert_eq!(remaining, money("200.0"));
+        let limit = money("100.0");
+        let remaining = check_limit(transactions.iter().collect(), &month, limit, &ExchangeRates::default()).unwrap();
+        assert_eq!(remaining, -money("200.0"));
+    }
+
+    #[test]
+    fn test_limit_report() {
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        let mut january = Transaction::new(1, money("80"), Currency::default(), "Test transaction 1".to_string(), None);
+        january.set_datetime(at("2025-01-15T12:00:00Z"));
+        let mut march = Transaction::new(2, money("120"), Currency::default(), "Test transaction 2".to_string(), None);
+        march.set_datetime(at("2025-03-15T12:00:00Z"));
+        let transactions = [january, march];
+        let mut limits = LimitHistory::default();
+        limits.set_initial(Some(money("100")));
+        limits.set_from("2025-03".to_string(), Some(money("150")));
+        let report = limit_report(transactions.iter().collect(), &limits, "2025-04", &ExchangeRates::default()).unwrap();
+        let months: Vec<_> = report.iter().map(|month| (month.month.as_str(), month.spent, month.remaining())).collect();
+        assert_eq!(
+            months,
+            vec![
+                ("2025-01", money("80"), Some(money("20"))),
+                ("2025-02", money("0"), Some(money("100"))),
+                ("2025-03", money("120"), Some(money("30"))),
+                ("2025-04", money("0"), Some(money("150"))),
+            ]
+        );
+        assert!(limit_report(vec![], &LimitHistory::default(), "2025-04", &ExchangeRates::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_summary_only_counts_expenses() {
+        let mut salary = Transaction::new(2, money("2000"), Currency::default(), "Salary".to_string(), None);
+        salary.set_kind(TransactionKind::Income);
+        let mut savings = Transaction::new(3, money("500"), Currency::default(), "Savings".to_string(), None);
+        savings.set_kind(TransactionKind::Transfer);
+        let transactions = [
+            Transaction::new(1, money("100"), Currency::default(), "Groceries".to_string(), None),
+            salary,
+            savings,
         ];
-        let limit = 500.0;
-        let remaining = check_limit(transactions.iter().collect(), limit);
-        assert_eq!(remaining, 200.0);
-        let limit = 100.0;
-        let remaining = check_limit(transactions.iter().collect(), limit);
-        assert_eq!(remaining, -200.0);
-    }
-}
\ No newline at end of file
+        let (total, _) = summary(transactions.iter().collect(), None, None, &ExchangeRates::default()).unwrap();
+        assert_eq!(total, money("100"));
+        let remaining = check_limit(transactions.iter().collect(), &Utc::now().format("%Y-%m").to_string(), money("150"), &ExchangeRates::default()).unwrap();
+        assert_eq!(remaining, money("50"));
+    }
+
+    #[test]
+    fn test_cash_flow() {
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        let transaction = |id, amount: &str, kind, datetime| {
+            let mut transaction = Transaction::new(id, money(amount), Currency::default(), format!("Test transaction {}", id), None);
+            transaction.set_kind(kind);
+            transaction.set_datetime(at(datetime));
+            transaction
+        };
+        let transactions = [
+            transaction(1, "2000", TransactionKind::Income, "2025-03-01T09:00:00Z"),
+            transaction(2, "1200", TransactionKind::Expense, "2025-03-03T12:00:00Z"),
+            transaction(3, "500", TransactionKind::Transfer, "2025-03-05T12:00:00Z"),
+            transaction(4, "300", TransactionKind::Expense, "2025-01-10T12:00:00Z"),
+        ];
+        let report = cash_flow(transactions.iter().collect(), &ExchangeRates::default()).unwrap();
+        let months: Vec<_> = report.iter().map(|month| (month.month.as_str(), month.income, month.expenses, month.net())).collect();
+        assert_eq!(
+            months,
+            vec![
+                ("2025-01", money("0"), money("300"), -money("300")),
+                ("2025-03", money("2000"), money("1200"), money("800")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_running_balance() {
+        let card = Account::new(1, "Card".to_string());
+        let cash = Account::new(2, "Cash".to_string());
+        let at = |datetime: &str| chrono::DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc);
+        let transaction = |id, amount: &str, kind, account: &Account, datetime| {
+            let mut transaction = Transaction::new(id, money(amount), Currency::default(), format!("Test transaction {}", id), None);
+            transaction.set_kind(kind);
+            transaction.set_account(Some(account.clone()));
+            transaction.set_datetime(at(datetime));
+            transaction
+        };
+        let mut withdrawal = transaction(3, "100", TransactionKind::Transfer, &card, "2025-03-05T12:00:00Z");
+        withdrawal.set_to_account(Some(cash.clone()));
+        let transactions = [
+            transaction(2, "40", TransactionKind::Expense, &card, "2025-03-03T12:00:00Z"),
+            transaction(1, "1000", TransactionKind::Income, &card, "2025-03-01T09:00:00Z"),
+            withdrawal,
+            transaction(4, "30", TransactionKind::Expense, &cash, "2025-03-06T12:00:00Z"),
+        ];
+        let entries = running_balance(transactions.iter().collect(), card.id(), &ExchangeRates::default()).unwrap();
+        let balances: Vec<_> = entries.iter().map(|entry| (entry.transaction.id(), entry.change, entry.balance)).collect();
+        assert_eq!(balances, vec![(1, money("1000"), money("1000")), (2, -money("40"), money("960")), (3, -money("100"), money("860"))]);
+        let balances = account_balances(transactions.iter().collect(), vec![&card, &cash], &ExchangeRates::default()).unwrap();
+        assert_eq!(balances, vec![(card, money("860")), (cash, money("70"))]);
+    }
+
+    #[test]
+    fn test_summary_has_no_rounding_errors() {
+        let transactions = [
+            Transaction::new(1, money("0.1"), Currency::default(), "Test transaction 1".to_string(), None),
+            Transaction::new(2, money("0.2"), Currency::default(), "Test transaction 2".to_string(), None),
+            Transaction::new(3, money("29.7"), Currency::default(), "Test transaction 3".to_string(), None),
+        ];
+        let (total, _) = summary(transactions.iter().collect(), Some("overall".to_string()), None, &ExchangeRates::default()).unwrap();
+        assert_eq!(total, money("30"));
+        assert_eq!(total.to_string(), "30.00");
+    }
+
+    #[test]
+    fn test_summary_converts_to_base_currency() {
+        let today = Utc::now().date_naive();
+        let mut rates = ExchangeRates::default();
+        rates.set_base("EUR".parse().unwrap());
+        rates.add(ExchangeRate::new(today, "USD".parse().unwrap(), "EUR".parse().unwrap(), "0.5".parse().unwrap()).unwrap());
+        let transactions = [
+            Transaction::new(1, money("100"), "EUR".parse().unwrap(), "Test transaction 1".to_string(), None),
+            Transaction::new(2, money("100"), "USD".parse().unwrap(), "Test transaction 2".to_string(), None),
+        ];
+        let (total, _) = summary(transactions.iter().collect(), Some("overall".to_string()), None, &rates).unwrap();
+        assert_eq!(total, money("150"));
+        let remaining = check_limit(transactions.iter().collect(), &Utc::now().format("%Y-%m").to_string(), money("100"), &rates).unwrap();
+        assert_eq!(remaining, -money("50"));
+    }
+
+    #[test]
+    fn test_summary_invalid_month() {
+        let transactions = [
+            Transaction::new(1, money("100.0"), Currency::default(), "Test transaction 1".to_string(), None),
+        ];
+        let result = summary(transactions.iter().collect(), Some("2025-13".to_string()), None, &ExchangeRates::default());
+        assert!(matches!(result, Err(StoreError::InvalidMonth(_))));
+        assert_eq!(parse_month("2025-4").unwrap(), "2025-04");
+    }
+
+    #[test]
+    fn test_summary_missing_exchange_rate() {
+        let transactions = [
+            Transaction::new(1, money("100.0"), "GBP".parse().unwrap(), "Test transaction 1".to_string(), None),
+        ];
+        let result = summary(transactions.iter().collect(), None, None, &ExchangeRates::default());
+        assert!(matches!(result, Err(StoreError::MissingExchangeRate { .. })));
+    }
+}